@@ -0,0 +1,25 @@
+-- External identities (OAuth2 / OpenID Connect) linked to local accounts
+CREATE TABLE user_identities (
+    id              BIGSERIAL PRIMARY KEY,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider        TEXT NOT NULL,
+    subject         TEXT NOT NULL,
+    email           TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_login_at   TIMESTAMPTZ,
+    UNIQUE(provider, subject)
+);
+
+-- In-flight authorization requests: state, PKCE verifier and nonce
+CREATE TABLE oauth_states (
+    id              TEXT PRIMARY KEY,
+    provider        TEXT NOT NULL,
+    code_verifier   TEXT NOT NULL,
+    nonce           TEXT NOT NULL,
+    link_user_id    BIGINT REFERENCES users(id) ON DELETE CASCADE,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities(user_id);
+CREATE INDEX idx_oauth_states_expires_at ON oauth_states(expires_at);
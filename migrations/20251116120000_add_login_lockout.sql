@@ -0,0 +1,30 @@
+-- Per-account brute-force protection
+ALTER TABLE users
+ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN last_failed_login_at TIMESTAMPTZ,
+ADD COLUMN locked_until TIMESTAMPTZ;
+
+-- Every password login attempt, keyed by the submitted identifier and client IP.
+-- `user_id` is NULL when the identifier matched no account.
+CREATE TABLE login_attempts (
+    id              BIGSERIAL PRIMARY KEY,
+    identifier      TEXT NOT NULL,
+    user_id         BIGINT REFERENCES users(id) ON DELETE CASCADE,
+    ip_address      INET,
+    succeeded       BOOLEAN NOT NULL,
+    attempted_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Single-use links emailed when an account is locked, stored hashed
+CREATE TABLE account_unlock_tokens (
+    id              BIGSERIAL PRIMARY KEY,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token           TEXT UNIQUE NOT NULL,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    used            BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_login_attempts_identifier ON login_attempts(identifier, attempted_at);
+CREATE INDEX idx_login_attempts_ip_address ON login_attempts(ip_address, attempted_at);
+CREATE INDEX idx_users_locked_until ON users(locked_until) WHERE locked_until IS NOT NULL;
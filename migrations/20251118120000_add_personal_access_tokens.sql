@@ -0,0 +1,16 @@
+-- Scoped personal access tokens for API and CLI clients, stored hashed
+CREATE TABLE personal_access_tokens (
+    id              BIGSERIAL PRIMARY KEY,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name            TEXT NOT NULL,
+    token_hash      TEXT UNIQUE NOT NULL,
+    token_prefix    TEXT NOT NULL,
+    scopes          TEXT[] NOT NULL,
+    expires_at      TIMESTAMPTZ,
+    last_used_at    TIMESTAMPTZ,
+    last_used_ip    INET,
+    revoked_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_personal_access_tokens_user_id ON personal_access_tokens(user_id);
@@ -0,0 +1,23 @@
+-- Invite codes for invite-only registration, stored hashed
+CREATE TABLE invite_codes (
+    id              BIGSERIAL PRIMARY KEY,
+    code_hash       TEXT UNIQUE NOT NULL,
+    code_prefix     TEXT NOT NULL,
+    role            TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('user', 'author', 'admin')),
+    max_uses        INTEGER,
+    uses            INTEGER NOT NULL DEFAULT 0,
+    expires_at      TIMESTAMPTZ,
+    note            TEXT,
+    created_by      BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    revoked_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Which account redeemed which invite
+CREATE TABLE invite_redemptions (
+    id              BIGSERIAL PRIMARY KEY,
+    invite_id       BIGINT NOT NULL REFERENCES invite_codes(id) ON DELETE CASCADE,
+    user_id         BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    redeemed_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(invite_id, user_id)
+);
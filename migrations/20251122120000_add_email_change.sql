@@ -0,0 +1,3 @@
+-- Email changes are confirmed through a link sent to the new address
+ALTER TABLE email_verification_tokens
+ADD COLUMN new_email TEXT;
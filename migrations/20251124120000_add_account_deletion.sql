@@ -0,0 +1,11 @@
+-- Self-service account deletions waiting out their grace period.
+-- The account is deactivated at once and purged after `purge_after`
+-- unless the emailed restore link is used first.
+CREATE TABLE account_deletions (
+    user_id         BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    restore_token   TEXT UNIQUE NOT NULL,
+    requested_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    purge_after     TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_account_deletions_purge_after ON account_deletions(purge_after);
@@ -0,0 +1,18 @@
+-- Single-use passwordless sign-in links, stored hashed.
+-- Requests for addresses without an account are recorded too (`user_id` NULL)
+-- so throttling behaves the same whether or not the address is registered.
+CREATE TABLE magic_link_tokens (
+    id              BIGSERIAL PRIMARY KEY,
+    email           TEXT NOT NULL,
+    user_id         BIGINT REFERENCES users(id) ON DELETE CASCADE,
+    token           TEXT UNIQUE NOT NULL,
+    remember_me     BOOLEAN NOT NULL DEFAULT FALSE,
+    requested_ip    INET,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    used            BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_magic_link_tokens_email ON magic_link_tokens(email, created_at);
+CREATE INDEX idx_magic_link_tokens_requested_ip ON magic_link_tokens(requested_ip, created_at);
+CREATE INDEX idx_magic_link_tokens_user_id ON magic_link_tokens(user_id);
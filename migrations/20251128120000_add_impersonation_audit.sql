@@ -0,0 +1,18 @@
+-- Sessions an admin opened to view the site as another user
+ALTER TABLE user_sessions
+ADD COLUMN impersonator_id BIGINT REFERENCES users(id) ON DELETE CASCADE;
+
+-- Security-relevant actions taken by staff
+CREATE TABLE audit_log (
+    id              BIGSERIAL PRIMARY KEY,
+    actor_id        BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    action          TEXT NOT NULL,
+    target_user_id  BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    details         TEXT,
+    ip_address      INET,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at DESC);
+CREATE INDEX idx_audit_log_actor_id ON audit_log(actor_id);
+CREATE INDEX idx_audit_log_target_user_id ON audit_log(target_user_id);
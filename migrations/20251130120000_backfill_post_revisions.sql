@@ -0,0 +1,5 @@
+-- Give posts written before revisions were recorded a first revision
+INSERT INTO post_revisions (post_id, title, summary, body_markdown, body_html, revision_number, created_by, created_at)
+SELECT p.id, p.title, p.summary, p.body_markdown, p.body_html, 1, p.author_id, p.updated_at
+FROM posts p
+WHERE NOT EXISTS (SELECT 1 FROM post_revisions r WHERE r.post_id = p.id);
@@ -0,0 +1,11 @@
+-- Optional time after which a published post is hidden again
+ALTER TABLE posts ADD COLUMN unpublish_at TIMESTAMPTZ;
+
+-- Whether publish listeners were last told the post is live, so the
+-- scheduler fires each publish/unpublish event once
+ALTER TABLE posts ADD COLUMN announced_visible BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE posts SET announced_visible = (published_at IS NOT NULL AND published_at <= NOW());
+
+CREATE INDEX idx_posts_pending_publish ON posts (published_at) WHERE NOT announced_visible;
+CREATE INDEX idx_posts_pending_unpublish ON posts (unpublish_at) WHERE announced_visible;
@@ -0,0 +1,12 @@
+-- Shareable draft preview links. The link carries a signed token naming a
+-- row here, so it can be revoked before it expires.
+CREATE TABLE post_preview_links (
+    id              BIGSERIAL PRIMARY KEY,
+    post_id         BIGINT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_by      BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    revoked_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_post_preview_links_post_id ON post_preview_links(post_id);
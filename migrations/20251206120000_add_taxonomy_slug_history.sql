@@ -0,0 +1,16 @@
+-- Former slugs of renamed or merged categories and tags, so old links
+-- redirect to the current page
+CREATE TABLE category_slug_history (
+    old_slug        TEXT PRIMARY KEY,
+    category_id     BIGINT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE tag_slug_history (
+    old_slug        TEXT PRIMARY KEY,
+    tag_id          BIGINT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_category_slug_history_category_id ON category_slug_history(category_id);
+CREATE INDEX idx_tag_slug_history_tag_id ON tag_slug_history(tag_id);
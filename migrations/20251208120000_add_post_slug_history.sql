@@ -0,0 +1,8 @@
+-- Former slugs of posts, so links to a renamed post redirect to its new URL
+CREATE TABLE post_slug_history (
+    old_slug        TEXT PRIMARY KEY,
+    post_id         BIGINT NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_post_slug_history_post_id ON post_slug_history(post_id);
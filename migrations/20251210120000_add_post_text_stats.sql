@@ -0,0 +1,5 @@
+-- Figures computed from a post's body when it is saved. Existing posts are
+-- filled in by `cargo run --features ssr -- refresh-text-stats`.
+ALTER TABLE posts
+ADD COLUMN word_count INTEGER,
+ADD COLUMN excerpt TEXT;
@@ -0,0 +1,7 @@
+-- Failed logins for an account are counted from `login_attempts` over a sliding
+-- window, like unknown identifiers. A successful login or an unlock starts
+-- the count afresh from this point.
+ALTER TABLE users
+ADD COLUMN failed_logins_reset_at TIMESTAMPTZ;
+
+CREATE INDEX idx_login_attempts_user_id ON login_attempts(user_id, attempted_at) WHERE user_id IS NOT NULL;
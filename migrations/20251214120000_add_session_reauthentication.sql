@@ -0,0 +1,9 @@
+-- Accounts created through a login provider have a random password nobody
+-- knows. Signing in again through a linked provider marks the session, and
+-- sensitive changes accept that for a few minutes instead of the password.
+ALTER TABLE user_sessions
+ADD COLUMN reauthenticated_at TIMESTAMPTZ;
+
+-- The session a provider sign-in should confirm, for re-authentication requests
+ALTER TABLE oauth_states
+ADD COLUMN reauth_session_id TEXT REFERENCES user_sessions(id) ON DELETE CASCADE;
@@ -0,0 +1,207 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::AuthService,
+    db::Db,
+    error::{AppError, AppResult},
+    models::{CreateAccessTokenInput, NewAccessToken, PersonalAccessToken, TokenScope, User},
+    security::{generate_secure_token, hash_token},
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use sqlx::FromRow;
+#[cfg(feature = "ssr")]
+use std::net::IpAddr;
+
+/// Prefix that tells personal access tokens apart from JWT access tokens
+#[cfg(feature = "ssr")]
+pub const ACCESS_TOKEN_PREFIX: &str = "tlj_pat_";
+
+#[cfg(feature = "ssr")]
+const MAX_TOKENS_PER_USER: i64 = 50;
+
+#[cfg(feature = "ssr")]
+#[derive(FromRow)]
+struct AccessTokenRow {
+    id: i64,
+    name: String,
+    token_prefix: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<AccessTokenRow> for PersonalAccessToken {
+    fn from(row: AccessTokenRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            token_prefix: row.token_prefix,
+            scopes: row.scopes.iter().filter_map(|s| TokenScope::parse(s)).collect(),
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Scoped, long-lived API credentials issued by users to scripts and integrations
+#[cfg(feature = "ssr")]
+pub struct AccessTokenService;
+
+#[cfg(feature = "ssr")]
+impl AccessTokenService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Whether a bearer credential is a personal access token rather than a JWT
+    pub fn is_access_token(token: &str) -> bool {
+        token.starts_with(ACCESS_TOKEN_PREFIX)
+    }
+
+    /// Issue a token; scopes above the user's role are refused
+    pub async fn create(&self, db: &Db, user: &User, input: CreateAccessTokenInput) -> AppResult<NewAccessToken> {
+        let auth_service = AuthService::new();
+        if let Some(scope) = input
+            .scopes
+            .iter()
+            .find(|scope| !auth_service.check_permission(&user.role, &scope.required_role()))
+        {
+            return Err(AppError::Forbidden(format!(
+                "Your role can't grant the {} scope",
+                scope
+            )));
+        }
+
+        let active = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM personal_access_tokens WHERE user_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(user.id)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if active >= MAX_TOKENS_PER_USER {
+            return Err(AppError::Validation(
+                "You have too many access tokens; revoke some first".to_string(),
+            ));
+        }
+
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, generate_secure_token(32));
+        // Enough of the token to recognise it in a list without weakening it
+        let token_prefix: String = token.chars().take(ACCESS_TOKEN_PREFIX.len() + 6).collect();
+        let expires_at = input.expires_in_days.map(|days| Utc::now() + Duration::days(days));
+
+        let mut scopes: Vec<&str> = input.scopes.iter().map(TokenScope::as_str).collect();
+        scopes.sort_unstable();
+        scopes.dedup();
+
+        let row = sqlx::query_as::<_, AccessTokenRow>(
+            r#"
+            INSERT INTO personal_access_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+            "#,
+        )
+        .bind(user.id)
+        .bind(input.name.trim())
+        .bind(hash_token(&token))
+        .bind(&token_prefix)
+        .bind(&scopes)
+        .bind(expires_at)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(user_id = user.id, token_id = row.id, scopes = ?scopes, "Personal access token created");
+        Ok(NewAccessToken { token, details: row.into() })
+    }
+
+    /// The user's unrevoked tokens, newest first
+    pub async fn list(&self, db: &Db, user_id: i64) -> AppResult<Vec<PersonalAccessToken>> {
+        let rows = sqlx::query_as::<_, AccessTokenRow>(
+            r#"
+            SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+            FROM personal_access_tokens
+            WHERE user_id = $1 AND revoked_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// Revoke one of the user's own tokens
+    pub async fn revoke(&self, db: &Db, user_id: i64, token_id: i64) -> AppResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE personal_access_tokens SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Access token not found".to_string()));
+        }
+
+        tracing::info!(user_id = user_id, token_id = token_id, "Personal access token revoked");
+        Ok(())
+    }
+
+    /// Resolve a bearer token to its owner and scopes, recording the use
+    pub async fn authenticate(&self, db: &Db, token: &str, ip: Option<IpAddr>) -> AppResult<(User, Vec<TokenScope>)> {
+        let (token_id, user_id, scopes) = sqlx::query_as::<_, (i64, i64, Vec<String>)>(
+            r#"
+            SELECT id, user_id, scopes FROM personal_access_tokens
+            WHERE token_hash = $1 AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::Unauthorized)?;
+
+        let user = match AuthService::new().get_user_by_id(db, user_id).await {
+            Ok(user) => user,
+            Err(AppError::NotFound(_)) => return Err(AppError::Unauthorized),
+            Err(e) => return Err(e),
+        };
+
+        // Bounded to one write a minute per token so busy scripts don't hammer the row
+        sqlx::query(
+            r#"
+            UPDATE personal_access_tokens SET last_used_at = NOW(), last_used_ip = $2::text::inet
+            WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(token_id)
+        .bind(ip.map(|ip| ip.to_string()))
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let scopes = scopes.iter().filter_map(|s| TokenScope::parse(s)).collect();
+        Ok((user, scopes))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for AccessTokenService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,17 +2,45 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
+use crate::models::UserRole;
 use crate::pages::{
     home::HomePage, 
     post::PostPage, 
-    auth::{LoginPage, RegisterPage},
-    search::SearchPage
+    auth::{
+        LoginPage, RegisterPage, ForgotPasswordPage, ResetPasswordPage, VerifyEmailPage,
+        UnlockAccountPage, RestoreAccountPage, MagicLinkPage, MagicLinkLoginPage,
+        LogoutUser, get_current_user,
+    },
+    search::SearchPage,
+    account::{AccountSecurityPage, AccountSettingsPage},
+    admin::{AdminPage, StopImpersonation, get_impersonation_status},
+    editor::{WritePage, EditPostPage},
+    revisions::PostHistoryPage,
+    preview::PostPreviewPage,
+    taxonomy::{CategoryPage, TagPage},
 };
 
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
 
+    let current_user = create_resource(|| (), |_| async { get_current_user().await });
+    let impersonation = create_resource(|| (), |_| async { get_impersonation_status().await });
+    let logout = create_server_action::<LogoutUser>();
+    let stop_impersonation = create_server_action::<StopImpersonation>();
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = logout.value().get() {
+            window().location().set_href("/").ok();
+        }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = stop_impersonation.value().get() {
+            window().location().set_href("/admin").ok();
+        }
+    });
+
     view! {
         <Html lang="en" />
         <Title text="Rust/Leptos Blog" />
@@ -21,14 +49,45 @@ pub fn App() -> impl IntoView {
         <Body class="blog-app"/>
         
         <Router>
+            <Transition fallback=|| ()>
+                {move || impersonation.get().and_then(Result::ok).flatten().map(|status| view! {
+                    <div class="impersonation-banner" role="status">
+                        {format!(
+                            "Viewing as {} (@{}) · signed in as @{}",
+                            status.display_name, status.username, status.impersonator_username
+                        )}
+                        <ActionForm action=stop_impersonation>
+                            <button type="submit" class="btn">"Stop impersonating"</button>
+                        </ActionForm>
+                    </div>
+                })}
+            </Transition>
             <nav class="nav">
                 <div class="container">
                     <div class="nav-content">
                         <a href="/" class="logo">"Taleji"</a>
                         <div class="nav-links">
                             <a href="/search" class="nav-link">"Search"</a>
-                            <a href="/login" class="nav-link">"Login"</a>
-                            <a href="/register" class="nav-link btn btn-primary">"Sign Up"</a>
+                            <Transition fallback=|| ()>
+                                {move || match current_user.get() {
+                                    Some(Ok(Some(user))) => view! {
+                                        <Show when=move || matches!(user.role, UserRole::Author | UserRole::Admin)>
+                                            <a href="/write" class="nav-link">"Write"</a>
+                                        </Show>
+                                        <Show when=move || user.role == UserRole::Admin>
+                                            <a href="/admin" class="nav-link">"Admin"</a>
+                                        </Show>
+                                        <a href="/account/settings" class="nav-user">{user.display_name}</a>
+                                        <ActionForm action=logout class="nav-logout">
+                                            <button type="submit" class="nav-link btn">"Logout"</button>
+                                        </ActionForm>
+                                    }.into_view(),
+                                    _ => view! {
+                                        <a href="/login" class="nav-link">"Login"</a>
+                                        <a href="/register" class="nav-link btn btn-primary">"Sign Up"</a>
+                                    }.into_view(),
+                                }}
+                            </Transition>
                         </div>
                     </div>
                 </div>
@@ -36,10 +95,27 @@ pub fn App() -> impl IntoView {
             <main class="container">
                 <Routes>
                     <Route path="/" view=HomePage />
-                    <Route path="/post/:slug" view=PostPage />
+                    // Async so old slugs can still get a 301 before the response starts
+                    <Route path="/post/:slug" view=PostPage ssr=SsrMode::Async />
+                    <Route path="/post/:slug/edit" view=EditPostPage />
+                    <Route path="/post/:slug/history" view=PostHistoryPage />
+                    <Route path="/preview/:token" view=PostPreviewPage ssr=SsrMode::Async />
+                    <Route path="/write" view=WritePage />
+                    <Route path="/category/:slug" view=CategoryPage ssr=SsrMode::Async />
+                    <Route path="/tag/:slug" view=TagPage ssr=SsrMode::Async />
                     <Route path="/search" view=SearchPage />
                     <Route path="/login" view=LoginPage />
                     <Route path="/register" view=RegisterPage />
+                    <Route path="/forgot-password" view=ForgotPasswordPage />
+                    <Route path="/reset-password/:token" view=ResetPasswordPage />
+                    <Route path="/verify-email/:token" view=VerifyEmailPage />
+                    <Route path="/login/magic" view=MagicLinkPage />
+                    <Route path="/magic-login/:token" view=MagicLinkLoginPage />
+                    <Route path="/unlock-account/:token" view=UnlockAccountPage />
+                    <Route path="/restore-account/:token" view=RestoreAccountPage />
+                    <Route path="/account/settings" view=AccountSettingsPage />
+                    <Route path="/account/security" view=AccountSecurityPage />
+                    <Route path="/admin" view=AdminPage />
                 </Routes>
             </main>
             <footer class="footer">
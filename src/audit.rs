@@ -0,0 +1,80 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::AuditLogEntry,
+};
+#[cfg(feature = "ssr")]
+use std::net::IpAddr;
+
+#[cfg(feature = "ssr")]
+pub const IMPERSONATION_STARTED: &str = "impersonation.start";
+#[cfg(feature = "ssr")]
+pub const IMPERSONATION_STOPPED: &str = "impersonation.stop";
+#[cfg(feature = "ssr")]
+pub const ACCOUNT_UNLOCKED: &str = "account.unlock";
+
+/// Append-only log of security-relevant actions taken by staff
+#[cfg(feature = "ssr")]
+pub struct AuditService;
+
+#[cfg(feature = "ssr")]
+impl AuditService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn record(
+        &self,
+        db: &Db,
+        actor_id: i64,
+        action: &str,
+        target_user_id: Option<i64>,
+        details: Option<&str>,
+        ip: Option<IpAddr>,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (actor_id, action, target_user_id, details, ip_address)
+            VALUES ($1, $2, $3, $4, $5::text::inet)
+            "#,
+        )
+        .bind(actor_id)
+        .bind(action)
+        .bind(target_user_id)
+        .bind(details)
+        .bind(ip.map(|ip| ip.to_string()))
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(actor_id = actor_id, action = action, target_user_id = ?target_user_id, "Audit event recorded");
+        Ok(())
+    }
+
+    /// Most recent entries first
+    pub async fn recent(&self, db: &Db, limit: i64) -> AppResult<Vec<AuditLogEntry>> {
+        sqlx::query_as::<_, AuditLogEntry>(
+            r#"
+            SELECT l.id, a.username AS actor_username, l.action, t.username AS target_username,
+                   l.details, host(l.ip_address) AS ip_address, l.created_at
+            FROM audit_log l
+            LEFT JOIN users a ON a.id = l.actor_id
+            LEFT JOIN users t ON t.id = l.target_user_id
+            ORDER BY l.created_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for AuditService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,19 +2,39 @@
 use crate::{
     db::Db,
     error::{AppError, AppResult},
-    models::{User, UserProfile, UserRole, LoginInput, RegisterInput, AuthResponse},
+    invites::InviteService,
+    jwt_keys::JwtKeyring,
+    lockout::LoginThrottle,
+    magic_link::MagicLinkService,
+    mailer::{EmailMessage, Mailer},
+    models::{
+        User, UserProfile, UserRole, LoginInput, RegisterInput, AuthResponse,
+        TwoFactorLoginInput, RegistrationMode, UpdateProfileInput, ChangePasswordInput, ChangeEmailInput,
+    },
+    password::PasswordService,
+    security::{generate_secure_token, hash_token},
+    session::SessionService,
+    settings::get_registration_mode,
+    two_factor::TwoFactorService,
 };
 #[cfg(feature = "ssr")]
-use bcrypt::{hash, verify, DEFAULT_COST};
-#[cfg(feature = "ssr")]
 use chrono::{DateTime, Utc, Duration};
 #[cfg(feature = "ssr")]
-use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey};
-#[cfg(feature = "ssr")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
+use sqlx::FromRow;
+#[cfg(feature = "ssr")]
+use std::net::IpAddr;
+#[cfg(feature = "ssr")]
+use std::sync::OnceLock;
+#[cfg(feature = "ssr")]
 use uuid::Uuid;
 
+/// Hash of a random password, checked when no account matches so unknown
+/// identifiers take as long to reject as wrong passwords
+#[cfg(feature = "ssr")]
+static DUMMY_PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+
 #[cfg(feature = "ssr")]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -26,45 +46,137 @@ pub struct Claims {
     pub jti: String,     // JWT ID
 }
 
+/// Result of a first login step: the user, or a pending second factor
+#[cfg(feature = "ssr")]
+pub enum LoginStep {
+    Authenticated(User),
+    TwoFactorRequired {
+        challenge_token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+#[cfg(feature = "ssr")]
+#[derive(FromRow)]
+struct StoredRefreshToken {
+    id: i64,
+    user_id: i64,
+    family_id: String,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
 #[cfg(feature = "ssr")]
 pub struct AuthService {
-    jwt_secret: String,
-    jwt_expiry_hours: i64,
+    passwords: PasswordService,
+    access_token_expiry_minutes: i64,
+    refresh_token_expiry_days: i64,
+    app_base_url: String,
+    password_reset_expiry_minutes: i64,
+    email_verification_expiry_hours: i64,
+    require_verified_email: bool,
 }
 
 #[cfg(feature = "ssr")]
 impl AuthService {
     pub fn new() -> Self {
         Self {
-            jwt_secret: std::env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "your-super-secret-jwt-key-change-in-production".to_string()),
-            jwt_expiry_hours: std::env::var("JWT_EXPIRY_HOURS")
+            passwords: PasswordService::new(),
+            access_token_expiry_minutes: std::env::var("ACCESS_TOKEN_EXPIRY_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            refresh_token_expiry_days: std::env::var("REFRESH_TOKEN_EXPIRY_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            password_reset_expiry_minutes: std::env::var("PASSWORD_RESET_EXPIRY_MINUTES")
                 .ok()
                 .and_then(|v| v.parse().ok())
-                .unwrap_or(24),
+                .unwrap_or(60),
+            email_verification_expiry_hours: std::env::var("EMAIL_VERIFICATION_EXPIRY_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(48),
+            require_verified_email: std::env::var("REQUIRE_VERIFIED_EMAIL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
         }
     }
 
-    /// Hash a password using bcrypt
+    /// Hash a password with the configured algorithm (Argon2id by default)
     pub fn hash_password(&self, password: &str) -> AppResult<String> {
-        hash(password, DEFAULT_COST).map_err(|e| {
-            tracing::error!("Failed to hash password: {}", e);
-            AppError::Internal("Password hashing failed".to_string())
-        })
+        self.passwords.hash(password)
     }
 
-    /// Verify a password against a hash
+    /// Verify a password against an Argon2 or bcrypt hash
     pub fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
-        verify(password, hash).map_err(|e| {
-            tracing::error!("Failed to verify password: {}", e);
-            AppError::Internal("Password verification failed".to_string())
-        })
+        self.passwords.verify(password, hash)
+    }
+
+    /// Confirm a sensitive change with the current password. Accounts created
+    /// through a login provider have no password they know; for them an empty
+    /// password is accepted if this session just signed in again through a
+    /// linked provider.
+    pub async fn confirm_identity(&self, db: &Db, user: &User, password: &str, session_id: Option<&str>) -> AppResult<()> {
+        if !password.is_empty() {
+            if self.verify_password(password, &user.password_hash)? {
+                return Ok(());
+            }
+            return Err(AppError::Validation("Current password is incorrect".to_string()));
+        }
+
+        if let Some(session_id) = session_id {
+            if SessionService::new().recently_reauthenticated(db, session_id).await? {
+                return Ok(());
+            }
+        }
+        Err(AppError::Validation(
+            "Enter your current password, or confirm it's you with a linked login provider".to_string(),
+        ))
+    }
+
+    /// Spend the same hashing work as a real password check, for identifiers
+    /// that match no account
+    fn verify_dummy_password(&self, password: &str) {
+        let hash = DUMMY_PASSWORD_HASH
+            .get_or_init(|| self.passwords.hash(&generate_secure_token(16)).unwrap_or_default());
+        let _ = self.passwords.verify(password, hash);
     }
 
-    /// Generate a JWT token for a user
+    /// Upgrade a stored hash after a successful login if it uses an outdated
+    /// algorithm or cost. Failures are logged; the login still succeeds.
+    async fn rehash_password_if_needed(&self, db: &Db, user: &User, password: &str) {
+        if !self.passwords.needs_rehash(&user.password_hash) {
+            return;
+        }
+
+        let result = match self.hash_password(password) {
+            Ok(password_hash) => sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2 AND password_hash = $3")
+                .bind(password_hash)
+                .bind(user.id)
+                .bind(&user.password_hash)
+                .execute(&**db)
+                .await
+                .map(|_| ())
+                .map_err(AppError::Database),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => tracing::info!("Upgraded password hash for user {}", user.id),
+            Err(e) => tracing::error!("Failed to upgrade password hash for user {}: {}", user.id, e),
+        }
+    }
+
+    /// Generate a short-lived JWT access token for a user
     pub fn generate_token(&self, user: &User) -> AppResult<String> {
         let now = Utc::now();
-        let exp = now + Duration::hours(self.jwt_expiry_hours);
+        let exp = now + Duration::minutes(self.access_token_expiry_minutes);
         
         let claims = Claims {
             sub: user.id,
@@ -75,135 +187,789 @@ impl AuthService {
             jti: Uuid::new_v4().to_string(),
         };
 
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_ref()),
+        JwtKeyring::global()?.encode(&claims)
+    }
+
+    /// Decode a JWT token, checking only its signature and expiry
+    pub fn decode_token(&self, token: &str) -> AppResult<Claims> {
+        JwtKeyring::global()?.decode(token)
+    }
+
+    /// Validate a JWT token, rejecting revoked tokens and tokens issued
+    /// before the user last signed out everywhere
+    pub async fn validate_token(&self, db: &Db, token: &str) -> AppResult<Claims> {
+        let claims = self.decode_token(token)?;
+
+        let (revoked, tokens_valid_after) = sqlx::query_as::<_, (bool, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT
+                EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1),
+                (SELECT tokens_valid_after FROM users WHERE id = $2)
+            "#,
         )
-        .map_err(|e| {
-            tracing::error!("Failed to generate JWT: {}", e);
-            AppError::Internal("Token generation failed".to_string())
-        })
+        .bind(&claims.jti)
+        .bind(claims.sub)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if revoked {
+            tracing::warn!(jti = %claims.jti, "Revoked JWT presented");
+            return Err(AppError::Unauthorized);
+        }
+        if let Some(valid_after) = tokens_valid_after {
+            // `iat` only has whole seconds, so a token from the same second is rejected too
+            if claims.iat <= valid_after.timestamp() {
+                tracing::warn!(jti = %claims.jti, "JWT issued before sign-out-everywhere");
+                return Err(AppError::Unauthorized);
+            }
+        }
+
+        Ok(claims)
     }
 
-    /// Validate and decode a JWT token
-    pub fn validate_token(&self, token: &str) -> AppResult<Claims> {
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_ref()),
-            &Validation::new(Algorithm::HS256),
+    /// Issue a new access and refresh token pair for an API client. Browser
+    /// logins use a session cookie instead.
+    pub async fn issue_api_tokens(&self, db: &Db, user: User) -> AppResult<AuthResponse> {
+        let mut conn = db.acquire().await.map_err(AppError::Database)?;
+        self.issue_tokens(&mut conn, user, None).await
+    }
+
+    /// Issue an access token and a refresh token, continuing `family_id` when rotating
+    async fn issue_tokens(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        user: User,
+        family_id: Option<String>,
+    ) -> AppResult<AuthResponse> {
+        let token = self.generate_token(&user)?;
+        let expires_at = Utc::now() + Duration::minutes(self.access_token_expiry_minutes);
+
+        let refresh_token = generate_secure_token(32);
+        let family_id = family_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
         )
-        .map(|data| data.claims)
-        .map_err(|e| {
-            tracing::warn!("Invalid JWT token: {}", e);
-            AppError::Unauthorized
+        .bind(user.id)
+        .bind(&family_id)
+        .bind(hash_token(&refresh_token))
+        .bind(Utc::now() + Duration::days(self.refresh_token_expiry_days))
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(AuthResponse {
+            user: user.into(),
+            token,
+            refresh_token,
+            expires_at,
         })
     }
 
-    /// Register a new user
-    pub async fn register_user(&self, db: &Db, input: RegisterInput) -> AppResult<AuthResponse> {
-        // Check if username or email already exists
-        let existing_user = sqlx::query!(
-            "SELECT COUNT(*) as count FROM users WHERE username = $1 OR email = $2",
-            input.username,
-            input.email
+    /// Exchange a refresh token for a new token pair.
+    ///
+    /// Each refresh token works once. Presenting one that was already used or
+    /// revoked means it leaked, so its whole family is revoked.
+    pub async fn refresh_tokens(&self, db: &Db, refresh_token: &str) -> AppResult<AuthResponse> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let stored = sqlx::query_as::<_, StoredRefreshToken>(
+            r#"
+            SELECT id, user_id, family_id, expires_at, used_at, revoked_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
         )
-        .fetch_one(&**db)
+        .bind(hash_token(refresh_token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or(AppError::Unauthorized)?;
+
+        if stored.used_at.is_some() || stored.revoked_at.is_some() {
+            sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL")
+                .bind(&stored.family_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+            tx.commit().await.map_err(AppError::Database)?;
+
+            tracing::warn!(
+                user_id = stored.user_id,
+                family_id = %stored.family_id,
+                "Refresh token reuse detected; token family revoked"
+            );
+            return Err(AppError::Unauthorized);
+        }
+
+        if stored.expires_at <= Utc::now() {
+            return Err(AppError::Unauthorized);
+        }
+
+        sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+            .bind(stored.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        let user = self.get_user_by_id(db, stored.user_id).await.map_err(|_| AppError::Unauthorized)?;
+        let response = self.issue_tokens(&mut tx, user, Some(stored.family_id)).await?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(response)
+    }
+
+    /// Revoke an access token before it expires
+    pub async fn revoke_access_token(&self, db: &Db, claims: &Claims) -> AppResult<()> {
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now);
+
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(&claims.jti)
+        .bind(claims.sub)
+        .bind(expires_at)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    /// Revoke the refresh token family that `refresh_token` belongs to
+    pub async fn revoke_refresh_token(&self, db: &Db, refresh_token: &str) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens SET revoked_at = NOW()
+            WHERE revoked_at IS NULL
+              AND family_id = (SELECT family_id FROM refresh_tokens WHERE token_hash = $1)
+            "#,
+        )
+        .bind(hash_token(refresh_token))
+        .execute(&**db)
         .await
         .map_err(AppError::Database)?;
 
-        if existing_user.count.unwrap_or(0) > 0 {
+        Ok(())
+    }
+
+    /// Invalidate every credential a user holds: access tokens, refresh tokens and sessions
+    pub async fn sign_out_everywhere(&self, conn: &mut sqlx::PgConnection, user_id: i64) -> AppResult<()> {
+        sqlx::query("UPDATE users SET tokens_valid_after = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM user_sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+        tracing::info!("Signed out user {} everywhere", user_id);
+        Ok(())
+    }
+
+    /// Remove expired refresh tokens, revocation entries, login challenges, OAuth
+    /// states, sessions, unlock and sign-in links and old login attempts
+    pub async fn purge_expired_tokens(&self, db: &Db) -> AppResult<()> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM two_factor_challenges WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM oauth_states WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM user_sessions WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM account_unlock_tokens WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM login_attempts WHERE attempted_at < NOW() - INTERVAL '30 days'")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        // Kept past expiry for the hourly request throttle
+        sqlx::query("DELETE FROM magic_link_tokens WHERE created_at < NOW() - INTERVAL '1 day'")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM post_preview_links WHERE expires_at < NOW()")
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    /// Register a new user
+    ///
+    /// Honours the registration mode: closed sites refuse everyone and
+    /// invite-only sites require a valid invite code, whose role the new
+    /// account receives.
+    pub async fn register_user(&self, db: &Db, mailer: &dyn Mailer, input: RegisterInput) -> AppResult<User> {
+        let invite_code = input
+            .invite_code
+            .as_deref()
+            .map(str::trim)
+            .filter(|code| !code.is_empty());
+
+        match get_registration_mode(db).await? {
+            RegistrationMode::Closed => {
+                return Err(AppError::Forbidden("Registration is closed".to_string()));
+            }
+            RegistrationMode::InviteOnly if invite_code.is_none() => {
+                return Err(AppError::Forbidden("Registration requires an invite code".to_string()));
+            }
+            _ => {}
+        }
+
+        // Check if username or email already exists
+        let existing_users = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE username = $1 OR email = $2")
+            .bind(&input.username)
+            .bind(&input.email)
+            .fetch_one(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if existing_users > 0 {
             return Err(AppError::Validation("Username or email already exists".to_string()));
         }
 
         // Hash the password
         let password_hash = self.hash_password(&input.password)?;
 
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
         // Create the user
-        let user = sqlx::query_as!(
-            User,
+        let mut user = sqlx::query_as::<_, User>(
             r#"
             INSERT INTO users (username, email, password_hash, display_name, role)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, username, email, password_hash, display_name, bio, avatar_url, 
-                      role as "role: UserRole", email_verified, is_active, created_at, updated_at
+            RETURNING *
             "#,
-            input.username,
-            input.email,
-            password_hash,
-            input.display_name,
-            UserRole::User as UserRole
         )
-        .fetch_one(&**db)
+        .bind(&input.username)
+        .bind(&input.email)
+        .bind(&password_hash)
+        .bind(&input.display_name)
+        .bind(UserRole::User)
+        .fetch_one(&mut *tx)
         .await
         .map_err(AppError::Database)?;
 
-        // Generate token
-        let token = self.generate_token(&user)?;
-        let expires_at = Utc::now() + Duration::hours(self.jwt_expiry_hours);
+        // The invite is consumed with the account, so a failed signup doesn't use it up
+        if let Some(code) = invite_code {
+            let role = InviteService::new().redeem(&mut tx, code, user.id).await?;
+            if role != UserRole::User {
+                sqlx::query("UPDATE users SET role = $1 WHERE id = $2")
+                    .bind(&role)
+                    .bind(user.id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(AppError::Database)?;
+                user.role = role;
+            }
+        }
 
-        tracing::info!("User registered: {}", user.username);
+        tx.commit().await.map_err(AppError::Database)?;
 
-        Ok(AuthResponse {
-            user: user.into(),
-            token,
-            expires_at,
-        })
+        // A failed send shouldn't fail registration; the user can resend later
+        if let Err(e) = self.send_verification_email(db, mailer, user.id, &user.email).await {
+            tracing::error!("Failed to send verification email: {}", e);
+        }
+
+        tracing::info!("User registered: {}", user.username);
+        Ok(user)
     }
 
-    /// Authenticate a user with email/username and password
-    pub async fn login_user(&self, db: &Db, input: LoginInput) -> AppResult<AuthResponse> {
+    /// Authenticate a user with email/username and password.
+    ///
+    /// Accounts with two-factor enabled get a challenge instead; see
+    /// [`AuthService::complete_two_factor_login`].
+    ///
+    /// Failed attempts are throttled per account and per IP. Unknown
+    /// identifiers get the same "Invalid credentials" and rate-limit errors as
+    /// real accounts, so responses don't reveal which usernames exist.
+    pub async fn login_user(&self, db: &Db, mailer: &dyn Mailer, input: LoginInput, ip: Option<IpAddr>) -> AppResult<LoginStep> {
+        let throttle = LoginThrottle::new();
+        throttle.check_ip(db, ip).await?;
+
         // Find user by email or username
-        let user = sqlx::query_as!(
-            User,
-            r#"
-            SELECT id, username, email, password_hash, display_name, bio, avatar_url, 
-                   role as "role: UserRole", email_verified, is_active, created_at, updated_at
-            FROM users 
-            WHERE (email = $1 OR username = $1) AND is_active = true
-            "#,
-            input.email_or_username
+        let user = sqlx::query_as::<_, User>(
+            "SELECT * FROM users WHERE (email = $1 OR username = $1) AND is_active = true",
         )
+        .bind(&input.email_or_username)
         .fetch_optional(&**db)
         .await
         .map_err(AppError::Database)?;
 
-        let user = user.ok_or_else(|| AppError::Validation("Invalid credentials".to_string()))?;
+        let identifier = LoginThrottle::normalize_identifier(&input.email_or_username);
+        let state = throttle.failure_state(db, &identifier, user.as_ref()).await?;
+        throttle.ensure_allowed(&state)?;
 
         // Verify password
-        if !self.verify_password(&input.password, &user.password_hash)? {
-            return Err(AppError::Validation("Invalid credentials".to_string()));
-        }
+        let valid = match &user {
+            Some(user) => self.verify_password(&input.password, &user.password_hash)?,
+            None => {
+                self.verify_dummy_password(&input.password);
+                false
+            }
+        };
+        let user = match user {
+            Some(user) if valid => user,
+            user => {
+                throttle.record_failure(db, mailer, &identifier, user.as_ref(), ip).await?;
+                return Err(AppError::Validation("Invalid credentials".to_string()));
+            }
+        };
 
-        // Generate token
-        let token = self.generate_token(&user)?;
-        let expires_at = Utc::now() + Duration::hours(self.jwt_expiry_hours);
+        self.rehash_password_if_needed(db, &user, &input.password).await;
 
+        // The failure counter is only cleared once the second factor is in too
+        let two_factor = TwoFactorService::new();
+        if two_factor.is_enabled(db, user.id).await? {
+            let (challenge_token, expires_at) = two_factor
+                .create_challenge(db, user.id, input.remember_me.unwrap_or(false))
+                .await?;
+            tracing::info!("Password accepted, awaiting second factor: {}", user.username);
+            return Ok(LoginStep::TwoFactorRequired { challenge_token, expires_at });
+        }
+
+        throttle.record_success(db, &identifier, &user, ip).await?;
         tracing::info!("User logged in: {}", user.username);
+        Ok(LoginStep::Authenticated(user))
+    }
 
-        Ok(AuthResponse {
-            user: user.into(),
-            token,
-            expires_at,
-        })
+    /// Finish a login with a TOTP or recovery code; returns the user and the
+    /// "remember me" choice made at the password step. Wrong codes count
+    /// toward the same lockout as wrong passwords.
+    pub async fn complete_two_factor_login(&self, db: &Db, mailer: &dyn Mailer, input: TwoFactorLoginInput, ip: Option<IpAddr>) -> AppResult<(User, bool)> {
+        let throttle = LoginThrottle::new();
+        throttle.check_ip(db, ip).await?;
+
+        let two_factor = TwoFactorService::new();
+        let (user_id, remember_me) = two_factor.load_challenge(db, &input.challenge_token).await?;
+        let user = self.get_user_by_id(db, user_id).await?;
+
+        let identifier = LoginThrottle::normalize_identifier(&user.email);
+        let state = throttle.failure_state(db, &identifier, Some(&user)).await?;
+        throttle.ensure_allowed(&state)?;
+
+        if !two_factor.verify_code(db, &user, &input.code).await? {
+            throttle.record_failure(db, mailer, &identifier, Some(&user), ip).await?;
+            return Err(AppError::Validation("Invalid verification code".to_string()));
+        }
+        two_factor.complete_challenge(db, &input.challenge_token).await?;
+        throttle.record_success(db, &identifier, &user, ip).await?;
+
+        tracing::info!("User logged in with two-factor: {}", user.username);
+        Ok((user, remember_me))
+    }
+
+    /// Sign in through an emailed magic link; returns the outcome and the
+    /// "remember me" choice made when the link was requested
+    pub async fn login_with_magic_link(&self, db: &Db, token: &str) -> AppResult<(LoginStep, bool)> {
+        let (user_id, remember_me) = MagicLinkService::new().redeem(db, token).await?;
+        let user = self.get_user_by_id(db, user_id).await?;
+
+        // Like any other login, the link doesn't bypass the second factor
+        let two_factor = TwoFactorService::new();
+        if two_factor.is_enabled(db, user.id).await? {
+            let (challenge_token, expires_at) = two_factor.create_challenge(db, user.id, remember_me).await?;
+            return Ok((LoginStep::TwoFactorRequired { challenge_token, expires_at }, remember_me));
+        }
+
+        tracing::info!("User logged in with magic link: {}", user.username);
+        Ok((LoginStep::Authenticated(user), remember_me))
     }
 
     /// Get user by ID
     pub async fn get_user_by_id(&self, db: &Db, user_id: i64) -> AppResult<User> {
-        sqlx::query_as!(
-            User,
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND is_active = true")
+            .bind(user_id)
+            .fetch_optional(&**db)
+            .await
+            .map_err(AppError::Database)?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    /// Email a single-use password reset link.
+    ///
+    /// Succeeds whether or not the address belongs to an account, so callers
+    /// can't use it to discover registered emails.
+    pub async fn request_password_reset(&self, db: &Db, mailer: &dyn Mailer, email: &str) -> AppResult<()> {
+        let user = sqlx::query_as::<_, (i64, String)>("SELECT id, email FROM users WHERE email = $1 AND is_active = true")
+            .bind(email)
+            .fetch_optional(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let Some((user_id, user_email)) = user else {
+            tracing::info!("Password reset requested for unknown email");
+            return Ok(());
+        };
+
+        // Only the most recent link stays valid
+        sqlx::query("UPDATE password_reset_tokens SET used = true WHERE user_id = $1 AND used = false")
+            .bind(user_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::minutes(self.password_reset_expiry_minutes);
+
+        sqlx::query("INSERT INTO password_reset_tokens (user_id, token, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(hash_token(&token))
+            .bind(expires_at)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        // Delivery failures are logged rather than returned so the response
+        // looks the same for known and unknown addresses
+        let sent = mailer.send(&EmailMessage {
+            to: user_email,
+            subject: "Reset your Taleji password".to_string(),
+            body: format!(
+                "Someone asked to reset the password for your Taleji account.\n\n\
+                 Use this link within {} minutes to choose a new password:\n{}/reset-password/{}\n\n\
+                 If this wasn't you, you can ignore this email.",
+                self.password_reset_expiry_minutes, self.app_base_url, token
+            ),
+        });
+        if let Err(e) = sent {
+            tracing::error!("Failed to send password reset email: {}", e);
+        }
+
+        tracing::info!("Password reset issued for user {}", user_id);
+        Ok(())
+    }
+
+    /// Consume a password reset token, set the new password and sign out all sessions
+    pub async fn reset_password(&self, db: &Db, token: &str, new_password: &str) -> AppResult<()> {
+        let password_hash = self.hash_password(new_password)?;
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let user_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE password_reset_tokens SET used = true
+            WHERE token = $1 AND used = false AND expires_at > NOW()
+            RETURNING user_id
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("This reset link is invalid or has expired".to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1, failed_login_attempts = 0, last_failed_login_at = NULL,
+                locked_until = NULL, failed_logins_reset_at = NOW(), updated_at = NOW()
+            WHERE id = $2
+            "#,
+        )
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        self.sign_out_everywhere(&mut tx, user_id).await?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!("Password reset completed for user {}", user_id);
+        Ok(())
+    }
+
+    /// Issue an email verification token for `email` and mail the link to it
+    pub async fn send_verification_email(&self, db: &Db, mailer: &dyn Mailer, user_id: i64, email: &str) -> AppResult<()> {
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::hours(self.email_verification_expiry_hours);
+
+        sqlx::query("INSERT INTO email_verification_tokens (user_id, token, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(hash_token(&token))
+            .bind(expires_at)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        mailer.send(&EmailMessage {
+            to: email.to_string(),
+            subject: "Confirm your Taleji email address".to_string(),
+            body: format!(
+                "Welcome to Taleji!\n\n\
+                 Please confirm your email address within {} hours by opening this link:\n{}/verify-email/{}",
+                self.email_verification_expiry_hours, self.app_base_url, token
+            ),
+        })
+    }
+
+    /// Send a fresh verification email, throttled to one a minute and five an hour
+    pub async fn resend_verification_email(&self, db: &Db, mailer: &dyn Mailer, user: &User) -> AppResult<()> {
+        if user.email_verified {
+            return Err(AppError::Validation("Email address is already verified".to_string()));
+        }
+
+        let (last_minute, last_hour) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE created_at > NOW() - INTERVAL '1 minute'),
+                COUNT(*)
+            FROM email_verification_tokens
+            WHERE user_id = $1 AND new_email IS NULL AND created_at > NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(user.id)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if last_minute > 0 || last_hour >= 5 {
+            return Err(AppError::RateLimitExceeded(
+                "Please wait before requesting another verification email".to_string(),
+            ));
+        }
+
+        // Earlier links stop working once a new one is sent; a pending email
+        // change keeps its own link
+        sqlx::query("UPDATE email_verification_tokens SET used = true WHERE user_id = $1 AND new_email IS NULL AND used = false")
+            .bind(user.id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        self.send_verification_email(db, mailer, user.id, &user.email).await
+    }
+
+    /// Consume an email verification token and mark the address as verified
+    pub async fn verify_email(&self, db: &Db, token: &str) -> AppResult<()> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let (user_id, new_email) = sqlx::query_as::<_, (i64, Option<String>)>(
             r#"
-            SELECT id, username, email, password_hash, display_name, bio, avatar_url, 
-                   role as "role: UserRole", email_verified, is_active, created_at, updated_at
-            FROM users 
+            UPDATE email_verification_tokens SET used = true
+            WHERE token = $1 AND used = false AND expires_at > NOW()
+            RETURNING user_id, new_email
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("This verification link is invalid or has expired".to_string()))?;
+
+        // Links sent for an email change also switch the account to the new address
+        sqlx::query(
+            r#"
+            UPDATE users SET email = COALESCE($2, email), email_verified = true, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(new_email)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                AppError::Validation("That email address is already in use".to_string())
+            }
+            _ => AppError::Database(e),
+        })?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!("Email verified for user {}", user_id);
+        Ok(())
+    }
+
+    /// Update display name, bio and avatar. Bio and avatar are replaced
+    /// outright, so `None` clears them.
+    pub async fn update_profile(&self, db: &Db, user_id: i64, input: UpdateProfileInput) -> AppResult<User> {
+        let display_name = input.display_name.map(|name| name.trim().to_string());
+        let bio = input.bio.map(|bio| bio.trim().to_string()).filter(|bio| !bio.is_empty());
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET display_name = COALESCE($2, display_name), bio = $3, avatar_url = $4, updated_at = NOW()
             WHERE id = $1 AND is_active = true
+            RETURNING *
             "#,
-            user_id
         )
+        .bind(user_id)
+        .bind(display_name)
+        .bind(bio)
+        .bind(input.avatar_url)
         .fetch_optional(&**db)
         .await
         .map_err(AppError::Database)?
-        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        tracing::info!("Profile updated for user {}", user_id);
+        Ok(user)
+    }
+
+    /// Change the password after checking the current one, then sign the
+    /// user out of every session and token so only the caller's fresh
+    /// session (started by the caller) remains
+    pub async fn change_password(&self, db: &Db, user: &User, input: ChangePasswordInput, session_id: Option<&str>) -> AppResult<()> {
+        self.confirm_identity(db, user, &input.current_password, session_id).await?;
+
+        let password_hash = self.hash_password(&input.new_password)?;
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query("UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2")
+            .bind(&password_hash)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        self.sign_out_everywhere(&mut tx, user.id).await?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!("Password changed for user {}", user.id);
+        Ok(())
+    }
+
+    /// Start an email change: the new address must confirm through an
+    /// emailed link before it replaces the current one, and the current
+    /// address is told about the request
+    pub async fn request_email_change(&self, db: &Db, mailer: &dyn Mailer, user: &User, input: ChangeEmailInput, session_id: Option<&str>) -> AppResult<()> {
+        self.confirm_identity(db, user, &input.password, session_id).await?;
+
+        let new_email = input.new_email.trim().to_string();
+        if new_email.eq_ignore_ascii_case(&user.email) {
+            return Err(AppError::Validation("That is already your email address".to_string()));
+        }
+
+        let taken = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(email) = LOWER($1))")
+            .bind(&new_email)
+            .fetch_one(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if taken {
+            return Err(AppError::Validation("That email address is already in use".to_string()));
+        }
+
+        // Only the latest requested address can be confirmed
+        sqlx::query("UPDATE email_verification_tokens SET used = true WHERE user_id = $1 AND new_email IS NOT NULL AND used = false")
+            .bind(user.id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::hours(self.email_verification_expiry_hours);
+
+        sqlx::query("INSERT INTO email_verification_tokens (user_id, token, expires_at, new_email) VALUES ($1, $2, $3, $4)")
+            .bind(user.id)
+            .bind(hash_token(&token))
+            .bind(expires_at)
+            .bind(&new_email)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        mailer.send(&EmailMessage {
+            to: new_email.clone(),
+            subject: "Confirm your new Taleji email address".to_string(),
+            body: format!(
+                "Please confirm that you want to use this address for your Taleji account \
+                 within {} hours by opening this link:\n{}/verify-email/{}",
+                self.email_verification_expiry_hours, self.app_base_url, token
+            ),
+        })?;
+
+        // The old address is only notified; a failed send mustn't block the change
+        if let Err(e) = mailer.send(&EmailMessage {
+            to: user.email.clone(),
+            subject: "Your Taleji email address is changing".to_string(),
+            body: format!(
+                "Someone asked to change the email address of your Taleji account to {}.\n\n\
+                 If this wasn't you, reset your password right away:\n{}/forgot-password",
+                new_email, self.app_base_url
+            ),
+        }) {
+            tracing::error!("Failed to send email change notice: {}", e);
+        }
+
+        tracing::info!("Email change requested for user {}", user.id);
+        Ok(())
+    }
+
+    /// Address awaiting confirmation from an unfinished email change
+    pub async fn pending_email(&self, db: &Db, user_id: i64) -> AppResult<Option<String>> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT new_email FROM email_verification_tokens
+            WHERE user_id = $1 AND new_email IS NOT NULL AND used = false AND expires_at > NOW()
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    /// Enforce the `REQUIRE_VERIFIED_EMAIL` policy before authoring or commenting
+    pub fn ensure_can_contribute(&self, user: &User) -> AppResult<()> {
+        if self.require_verified_email && !user.email_verified {
+            return Err(AppError::Forbidden(
+                "Please verify your email address first".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     /// Check if user has permission for a given role
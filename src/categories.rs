@@ -0,0 +1,190 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::{Category, CategoryInput, Post},
+    slugs::slug_from_input,
+};
+
+#[cfg(feature = "ssr")]
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(feature = "ssr")]
+fn name_or_slug_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            AppError::Validation("A category with that name or slug already exists".to_string())
+        }
+        _ => AppError::Database(e),
+    }
+}
+
+/// Category administration and lookup. Inactive categories keep their posts
+/// but are hidden from readers.
+#[cfg(feature = "ssr")]
+pub struct CategoryService;
+
+#[cfg(feature = "ssr")]
+impl CategoryService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Every category, active or not, for the admin screen
+    pub async fn list_all(&self, db: &Db) -> AppResult<Vec<Category>> {
+        sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY name")
+            .fetch_all(&**db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    fn check(&self, input: &CategoryInput) -> AppResult<String> {
+        if !is_hex_color(input.color.trim()) {
+            return Err(AppError::Validation("Color must be a hex value like #3B82F6".to_string()));
+        }
+        slug_from_input(&input.name, &input.slug)
+    }
+
+    pub async fn create(&self, db: &Db, input: &CategoryInput) -> AppResult<Category> {
+        let slug = self.check(input)?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            INSERT INTO categories (name, slug, description, color, is_active)
+            VALUES ($1, $2, NULLIF($3, ''), $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(input.name.trim())
+        .bind(&slug)
+        .bind(input.description.trim())
+        .bind(input.color.trim())
+        .bind(input.is_active)
+        .fetch_one(&**db)
+        .await
+        .map_err(name_or_slug_conflict)?;
+
+        tracing::info!(category_id = category.id, "Category created");
+        Ok(category)
+    }
+
+    /// Update a category; a changed slug is kept in the slug history so old
+    /// links redirect
+    pub async fn update(&self, db: &Db, category_id: i64, input: &CategoryInput) -> AppResult<Category> {
+        let slug = self.check(input)?;
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let old_slug = sqlx::query_scalar::<_, String>("SELECT slug FROM categories WHERE id = $1 FOR UPDATE")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::Database)?
+            .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            UPDATE categories
+            SET name = $2, slug = $3, description = NULLIF($4, ''), color = $5, is_active = $6, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(category_id)
+        .bind(input.name.trim())
+        .bind(&slug)
+        .bind(input.description.trim())
+        .bind(input.color.trim())
+        .bind(input.is_active)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(name_or_slug_conflict)?;
+
+        if old_slug != slug {
+            // A live slug always wins over a redirect
+            sqlx::query("DELETE FROM category_slug_history WHERE old_slug = $1")
+                .bind(&slug)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO category_slug_history (old_slug, category_id) VALUES ($1, $2)
+                ON CONFLICT (old_slug) DO UPDATE SET category_id = EXCLUDED.category_id, created_at = NOW()
+                "#,
+            )
+            .bind(&old_slug)
+            .bind(category_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(category_id = category_id, is_active = category.is_active, "Category updated");
+        Ok(category)
+    }
+
+    /// Delete a category; its posts become uncategorized
+    pub async fn delete(&self, db: &Db, category_id: i64) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM categories WHERE id = $1")
+            .bind(category_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Category not found".to_string()));
+        }
+
+        tracing::info!(category_id = category_id, "Category deleted");
+        Ok(())
+    }
+
+    /// An active category by its current or a former slug
+    pub async fn resolve(&self, db: &Db, slug: &str) -> AppResult<Category> {
+        sqlx::query_as::<_, Category>(
+            r#"
+            SELECT c.* FROM categories c
+            LEFT JOIN category_slug_history h ON h.category_id = c.id AND h.old_slug = $1
+            WHERE c.is_active = true AND (c.slug = $1 OR h.old_slug IS NOT NULL)
+            ORDER BY c.slug = $1 DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(slug)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Category '{}' not found", slug)))
+    }
+
+    /// Live posts in a category, newest first
+    pub async fn posts(&self, db: &Db, category_id: i64) -> AppResult<Vec<Post>> {
+        sqlx::query_as::<_, Post>(
+            r#"
+            SELECT * FROM posts
+            WHERE category_id = $1
+              AND published_at <= NOW()
+              AND (unpublish_at IS NULL OR unpublish_at > NOW())
+            ORDER BY published_at DESC
+            LIMIT 50
+            "#,
+        )
+        .bind(category_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for CategoryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -11,6 +11,7 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub security: SecurityConfig,
     pub logging: LoggingConfig,
+    pub oauth: OAuthConfig,
 }
 
 #[cfg(feature = "ssr")]
@@ -49,6 +50,39 @@ pub struct LoggingConfig {
     pub format: String, // "json" or "pretty"
 }
 
+/// Social login providers (OAuth2 / OpenID Connect)
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    /// Public base URL used to build callback URLs
+    pub redirect_base_url: String,
+    pub providers: Vec<OAuthProviderConfig>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OAuthProviderKind {
+    /// OpenID Connect; endpoints come from issuer discovery unless set explicitly
+    Oidc,
+    /// GitHub's plain OAuth2 with its REST user API
+    GitHub,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProviderConfig {
+    pub name: String,
+    pub display_name: String,
+    pub kind: OAuthProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+    pub issuer: Option<String>,
+    pub authorization_url: Option<String>,
+    pub token_url: Option<String>,
+    pub userinfo_url: Option<String>,
+    pub scopes: Vec<String>,
+}
+
 #[cfg(feature = "ssr")]
 impl Default for AppConfig {
     fn default() -> Self {
@@ -57,6 +91,7 @@ impl Default for AppConfig {
             server: ServerConfig::default(),
             security: SecurityConfig::default(),
             logging: LoggingConfig::default(),
+            oauth: OAuthConfig::default(),
         }
     }
 }
@@ -137,6 +172,68 @@ impl Default for LoggingConfig {
     }
 }
 
+#[cfg(feature = "ssr")]
+impl Default for OAuthConfig {
+    fn default() -> Self {
+        // OAUTH_PROVIDERS=github,google,gitlab enables providers by name
+        let providers = env::var("OAUTH_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .map(|name| OAuthProviderConfig::from_env(&name))
+            .collect();
+
+        Self {
+            redirect_base_url: env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            providers,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl OAuthProviderConfig {
+    /// Read `OAUTH_<NAME>_*` variables, filling in well-known defaults for
+    /// GitHub, Google and GitLab
+    pub fn from_env(name: &str) -> Self {
+        let var = |suffix: &str| env::var(format!("OAUTH_{}_{}", name.to_uppercase(), suffix)).ok();
+
+        let (display_name, kind, issuer, scopes) = match name {
+            "github" => ("GitHub", OAuthProviderKind::GitHub, None, "read:user user:email"),
+            "google" => ("Google", OAuthProviderKind::Oidc, Some("https://accounts.google.com"), "openid email profile"),
+            "gitlab" => ("GitLab", OAuthProviderKind::Oidc, Some("https://gitlab.com"), "openid email profile"),
+            _ => (name, OAuthProviderKind::Oidc, None, "openid email profile"),
+        };
+
+        let (authorization_url, token_url, userinfo_url) = match kind {
+            OAuthProviderKind::GitHub => (
+                Some("https://github.com/login/oauth/authorize".to_string()),
+                Some("https://github.com/login/oauth/access_token".to_string()),
+                Some("https://api.github.com/user".to_string()),
+            ),
+            OAuthProviderKind::Oidc => (None, None, None),
+        };
+
+        Self {
+            name: name.to_string(),
+            display_name: var("DISPLAY_NAME").unwrap_or_else(|| display_name.to_string()),
+            kind,
+            client_id: var("CLIENT_ID").unwrap_or_default(),
+            client_secret: var("CLIENT_SECRET").unwrap_or_default(),
+            issuer: var("ISSUER").or_else(|| issuer.map(str::to_string)),
+            authorization_url: var("AUTHORIZATION_URL").or(authorization_url),
+            token_url: var("TOKEN_URL").or(token_url),
+            userinfo_url: var("USERINFO_URL").or(userinfo_url),
+            scopes: var("SCOPES")
+                .unwrap_or_else(|| scopes.to_string())
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 impl AppConfig {
     pub fn from_env() -> Self {
@@ -156,6 +253,19 @@ impl AppConfig {
             return Err("Server port must be greater than 0".to_string());
         }
 
+        for provider in &self.oauth.providers {
+            if provider.client_id.is_empty() {
+                return Err(format!("OAuth provider '{}' is missing a client ID", provider.name));
+            }
+            let has_endpoints = provider.authorization_url.is_some() && provider.token_url.is_some();
+            if provider.issuer.is_none() && !has_endpoints {
+                return Err(format!(
+                    "OAuth provider '{}' needs an issuer or explicit endpoints",
+                    provider.name
+                ));
+            }
+        }
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -19,8 +19,8 @@ pub enum AppError {
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
-    #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    #[error("Rate limit exceeded: {0}")]
+    RateLimitExceeded(String),
 
     #[error("Internal server error: {0}")]
     Internal(String),
@@ -29,6 +29,25 @@ pub enum AppError {
     Config(String),
 }
 
+#[cfg(feature = "ssr")]
+impl AppError {
+    /// HTTP status a request failing with this error should be answered with
+    pub fn status_code(&self) -> axum::http::StatusCode {
+        use axum::http::StatusCode;
+
+        match self {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) | AppError::Config(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
 impl From<AppError> for ServerFnError {
     fn from(err: AppError) -> Self {
         match err {
@@ -36,12 +55,22 @@ impl From<AppError> for ServerFnError {
             AppError::Unauthorized => ServerFnError::new("Unauthorized access".to_string()),
             AppError::Forbidden(msg) => ServerFnError::new(format!("Forbidden: {}", msg)),
             AppError::Validation(msg) => ServerFnError::new(format!("Validation error: {}", msg)),
-            AppError::RateLimitExceeded => ServerFnError::new("Rate limit exceeded".to_string()),
+            AppError::RateLimitExceeded(msg) => ServerFnError::new(msg),
             _ => ServerFnError::new("Internal server error".to_string()),
         }
     }
 }
 
+/// Convert an error for a server function's response, setting the matching
+/// HTTP status; server functions otherwise answer every error with a 500
+#[cfg(feature = "ssr")]
+pub fn server_error(err: AppError) -> ServerFnError {
+    if let Some(response) = leptos::use_context::<leptos_axum::ResponseOptions>() {
+        response.set_status(err.status_code());
+    }
+    ServerFnError::from(err)
+}
+
 /// Result type alias for convenience
 pub type AppResult<T> = Result<T, AppError>;
 
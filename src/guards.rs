@@ -0,0 +1,128 @@
+#[cfg(feature = "ssr")]
+use leptos::expect_context;
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::AuthService,
+    db::Db,
+    error::{AppError, AppResult},
+    models::{TokenScope, User, UserRole},
+    session::current_caller,
+    two_factor::TwoFactorService,
+};
+
+/// The signed-in user, or `Unauthorized` (401) for anonymous requests.
+///
+/// Intended as the first line of a `#[server]` function:
+///
+/// ```ignore
+/// let user = require_user().await.map_err(server_error)?;
+/// ```
+///
+/// Personal access tokens are refused (403): account management needs a
+/// browser session or JWT. Use [`require_scope`] for functions that API
+/// clients may call.
+#[cfg(feature = "ssr")]
+pub async fn require_user() -> AppResult<User> {
+    let caller = current_caller().await?.ok_or(AppError::Unauthorized)?;
+    if caller.is_access_token() {
+        return Err(AppError::Forbidden(
+            "Personal access tokens can't be used for this action".to_string(),
+        ));
+    }
+    Ok(caller.user)
+}
+
+/// The signed-in user acting for themselves. On top of [`require_user`],
+/// refuses (403) admins impersonating the user, for actions like changing
+/// credentials or deleting the account.
+#[cfg(feature = "ssr")]
+pub async fn require_account_owner() -> AppResult<User> {
+    let caller = current_caller().await?.ok_or(AppError::Unauthorized)?;
+    if caller.is_access_token() {
+        return Err(AppError::Forbidden(
+            "Personal access tokens can't be used for this action".to_string(),
+        ));
+    }
+    if caller.is_impersonated() {
+        return Err(AppError::Forbidden(
+            "This action isn't available while impersonating a user".to_string(),
+        ));
+    }
+    Ok(caller.user)
+}
+
+/// The caller, if signed in interactively or with a personal access token
+/// that carries `scope`. Impersonation sessions are read-only: they only get
+/// [`TokenScope::ReadPosts`].
+#[cfg(feature = "ssr")]
+pub async fn require_scope(scope: TokenScope) -> AppResult<User> {
+    let caller = current_caller().await?.ok_or(AppError::Unauthorized)?;
+    if caller.is_impersonated() && scope != TokenScope::ReadPosts {
+        return Err(AppError::Forbidden(
+            "Changes aren't allowed while impersonating a user".to_string(),
+        ));
+    }
+    if !caller.has_scope(scope) {
+        return Err(AppError::Forbidden(format!(
+            "Access token is missing the {} scope",
+            scope
+        )));
+    }
+    ensure_two_factor_policy(&caller.user).await?;
+    Ok(caller.user)
+}
+
+/// The signed-in user if their role is at least `role`, otherwise
+/// `Unauthorized` (401) or `Forbidden` (403). Personal access tokens need
+/// the admin scope.
+#[cfg(feature = "ssr")]
+pub async fn require_role(role: UserRole) -> AppResult<User> {
+    let user = require_scope(TokenScope::Admin).await?;
+    ensure_role(&user, &role)?;
+    Ok(user)
+}
+
+/// Refuse (403) authors and admins without two-factor while the admin policy
+/// requires it. Enrollment only needs [`require_user`], so it stays reachable.
+#[cfg(feature = "ssr")]
+pub async fn ensure_two_factor_policy(user: &User) -> AppResult<()> {
+    let db = expect_context::<Db>();
+    TwoFactorService::new().ensure_policy_compliance(&db, user).await
+}
+
+/// Reject users whose role is below `role`
+#[cfg(feature = "ssr")]
+pub fn ensure_role(user: &User, role: &UserRole) -> AppResult<()> {
+    if AuthService::new().check_permission(&user.role, role) {
+        return Ok(());
+    }
+
+    tracing::warn!(user_id = user.id, required = ?role, "Insufficient role");
+    Err(AppError::Forbidden(match role {
+        UserRole::Admin => "Admin access required".to_string(),
+        UserRole::Author => "Author access required".to_string(),
+        UserRole::User => "Access denied".to_string(),
+    }))
+}
+
+/// Allow the owner of a resource, or anyone holding `role` (usually admins)
+#[cfg(feature = "ssr")]
+pub fn ensure_owner_or_role(user: &User, owner_id: Option<i64>, role: &UserRole) -> AppResult<()> {
+    if owner_id == Some(user.id) {
+        return Ok(());
+    }
+    ensure_role(user, role)
+}
+
+/// Allow the author of a post or an admin
+#[cfg(feature = "ssr")]
+pub async fn ensure_post_author_or_admin(db: &Db, user: &User, post_id: i64) -> AppResult<()> {
+    let author_id = sqlx::query_scalar::<_, Option<i64>>("SELECT author_id FROM posts WHERE id = $1")
+        .bind(post_id)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    ensure_owner_or_role(user, author_id, &UserRole::Admin)
+}
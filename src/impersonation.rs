@@ -0,0 +1,112 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    audit::{AuditService, IMPERSONATION_STARTED, IMPERSONATION_STOPPED},
+    db::Db,
+    error::{AppError, AppResult},
+    models::{StartImpersonationInput, User, UserRole},
+    security::{client_ip, hash_token},
+    session::SessionService,
+};
+#[cfg(feature = "ssr")]
+use axum::http::HeaderMap;
+#[cfg(feature = "ssr")]
+use chrono::Duration;
+
+/// Lets admins view the site as another user through a dedicated session
+/// that remembers who is really behind it. Starting and stopping are
+/// recorded in the audit log.
+#[cfg(feature = "ssr")]
+pub struct ImpersonationService {
+    session_minutes: i64,
+}
+
+#[cfg(feature = "ssr")]
+impl ImpersonationService {
+    pub fn new() -> Self {
+        Self {
+            session_minutes: std::env::var("IMPERSONATION_SESSION_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+        }
+    }
+
+    /// Open an impersonation session for `input.username`; returns the raw
+    /// session token for the cookie
+    pub async fn start(&self, db: &Db, admin: &User, input: &StartImpersonationInput, headers: &HeaderMap) -> AppResult<String> {
+        let target = sqlx::query_as::<_, User>(
+            "SELECT * FROM users WHERE LOWER(username) = LOWER($1) AND is_active = true",
+        )
+        .bind(input.username.trim())
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if target.id == admin.id {
+            return Err(AppError::Validation("You can't impersonate yourself".to_string()));
+        }
+        // Viewing as another admin would hand out their privileges
+        if target.role == UserRole::Admin {
+            return Err(AppError::Forbidden("Admins can't be impersonated".to_string()));
+        }
+
+        let (token, _) = SessionService::new()
+            .create_impersonation_session(db, target.id, admin.id, Duration::minutes(self.session_minutes), headers)
+            .await?;
+
+        AuditService::new()
+            .record(db, admin.id, IMPERSONATION_STARTED, Some(target.id), Some(input.reason.trim()), client_ip(headers))
+            .await?;
+
+        tracing::warn!(admin_id = admin.id, user_id = target.id, "Admin started impersonating user");
+        Ok(token)
+    }
+
+    /// End the impersonation session behind `token`; returns the admin's id
+    /// if they may be signed back in. An expired session, or an admin who has
+    /// since been deactivated or demoted, only ends the impersonation.
+    pub async fn stop(&self, db: &Db, token: &str, headers: &HeaderMap) -> AppResult<i64> {
+        let (user_id, admin_id, unexpired) = sqlx::query_as::<_, (i64, i64, bool)>(
+            r#"
+            DELETE FROM user_sessions
+            WHERE id = $1 AND impersonator_id IS NOT NULL
+            RETURNING user_id, impersonator_id, expires_at > NOW()
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("You are not impersonating anyone".to_string()))?;
+
+        AuditService::new()
+            .record(db, admin_id, IMPERSONATION_STOPPED, Some(user_id), None, client_ip(headers))
+            .await?;
+
+        tracing::warn!(admin_id = admin_id, user_id = user_id, "Admin stopped impersonating user");
+
+        if !unexpired {
+            return Err(AppError::Unauthorized);
+        }
+        let still_admin = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND role = $2 AND is_active = true)",
+        )
+        .bind(admin_id)
+        .bind(UserRole::Admin)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+        if !still_admin {
+            return Err(AppError::Forbidden("Your admin access has been revoked".to_string()));
+        }
+        Ok(admin_id)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for ImpersonationService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
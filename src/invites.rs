@@ -0,0 +1,131 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::{CreateInviteInput, InviteCode, NewInvite, User, UserRole},
+    security::{generate_secure_token, hash_token},
+};
+#[cfg(feature = "ssr")]
+use chrono::{Duration, Utc};
+#[cfg(feature = "ssr")]
+use sqlx::PgConnection;
+
+#[cfg(feature = "ssr")]
+const INVITE_COLUMNS: &str =
+    "id, code_prefix, role, max_uses, uses, expires_at, note, created_at";
+
+/// Admin-issued invite codes for invite-only registration
+#[cfg(feature = "ssr")]
+pub struct InviteService {
+    app_base_url: String,
+}
+
+#[cfg(feature = "ssr")]
+impl InviteService {
+    pub fn new() -> Self {
+        Self {
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        }
+    }
+
+    /// Create an invite and return the code, shown only this once. Invites
+    /// grant at most the author role.
+    pub async fn create(&self, db: &Db, created_by: &User, input: CreateInviteInput) -> AppResult<NewInvite> {
+        if input.role == UserRole::Admin {
+            return Err(AppError::Validation("Invites can't grant the admin role".to_string()));
+        }
+
+        let code = generate_secure_token(12);
+        let code_prefix: String = code.chars().take(4).collect();
+        let expires_at = input.expires_in_days.map(|days| Utc::now() + Duration::days(days));
+        let note = input.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+        let invite = sqlx::query_as::<_, InviteCode>(&format!(
+            r#"
+            INSERT INTO invite_codes (code_hash, code_prefix, role, max_uses, expires_at, note, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            INVITE_COLUMNS
+        ))
+        .bind(hash_token(&code))
+        .bind(&code_prefix)
+        .bind(&input.role)
+        .bind(input.max_uses)
+        .bind(expires_at)
+        .bind(note)
+        .bind(created_by.id)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(invite_id = invite.id, role = %invite.role, created_by = created_by.id, "Invite code created");
+
+        let url = format!("{}/register?invite={}", self.app_base_url, code);
+        Ok(NewInvite { code, url, invite })
+    }
+
+    /// Unrevoked invites, newest first
+    pub async fn list(&self, db: &Db) -> AppResult<Vec<InviteCode>> {
+        sqlx::query_as::<_, InviteCode>(&format!(
+            "SELECT {} FROM invite_codes WHERE revoked_at IS NULL ORDER BY created_at DESC",
+            INVITE_COLUMNS
+        ))
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    pub async fn revoke(&self, db: &Db, invite_id: i64) -> AppResult<()> {
+        let result = sqlx::query("UPDATE invite_codes SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(invite_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Invite not found".to_string()));
+        }
+
+        tracing::info!(invite_id = invite_id, "Invite code revoked");
+        Ok(())
+    }
+
+    /// Consume one use of an invite inside the registration transaction and
+    /// return the role it grants. Admin invites left from before they were
+    /// refused are treated as invalid.
+    pub async fn redeem(&self, conn: &mut PgConnection, code: &str, user_id: i64) -> AppResult<UserRole> {
+        let (invite_id, role) = sqlx::query_as::<_, (i64, UserRole)>(
+            r#"
+            UPDATE invite_codes SET uses = uses + 1
+            WHERE code_hash = $1 AND revoked_at IS NULL AND role <> 'admin'
+              AND (expires_at IS NULL OR expires_at > NOW())
+              AND (max_uses IS NULL OR uses < max_uses)
+            RETURNING id, role
+            "#,
+        )
+        .bind(hash_token(code.trim()))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("Invalid or expired invite code".to_string()))?;
+
+        sqlx::query("INSERT INTO invite_redemptions (invite_id, user_id) VALUES ($1, $2)")
+            .bind(invite_id)
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+        tracing::info!(invite_id = invite_id, user_id = user_id, "Invite code redeemed");
+        Ok(role)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for InviteService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,465 @@
+#[cfg(feature = "ssr")]
+use crate::error::{AppError, AppResult};
+#[cfg(feature = "ssr")]
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+#[cfg(feature = "ssr")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+#[cfg(feature = "ssr")]
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+#[cfg(feature = "ssr")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "ssr")]
+use std::sync::{Arc, OnceLock};
+
+/// Placeholder secret shipped in `.env.example`; refused outside development
+#[cfg(feature = "ssr")]
+pub const DEFAULT_JWT_SECRET: &str = "your-super-secret-jwt-key-change-in-production";
+
+/// Key id assumed for tokens issued before key ids were introduced
+#[cfg(feature = "ssr")]
+const LEGACY_KID: &str = "default";
+
+#[cfg(feature = "ssr")]
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw key follows
+#[cfg(feature = "ssr")]
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+#[cfg(feature = "ssr")]
+static KEYRING: OnceLock<Arc<JwtKeyring>> = OnceLock::new();
+
+/// One signing or verification key, identified by its `kid`
+#[cfg(feature = "ssr")]
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    /// `None` for retired keys that only verify tokens still in circulation
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    /// Public JWK for asymmetric keys; shared secrets are never published
+    jwk: Option<serde_json::Value>,
+}
+
+/// The set of keys tokens may be signed with.
+///
+/// New tokens are signed with the active key and carry its `kid` header;
+/// every key in the ring verifies, so keys can be rotated without logging
+/// anyone out. Configured through `JWT_KEYS` (comma-separated key ids),
+/// `JWT_ACTIVE_KID` and per-key `JWT_KEY_<KID>_*` variables, or a single
+/// `JWT_SECRET` for the simple HS256 setup.
+#[cfg(feature = "ssr")]
+pub struct JwtKeyring {
+    keys: Vec<JwtKey>,
+    active_kid: String,
+}
+
+#[cfg(feature = "ssr")]
+impl JwtKeyring {
+    /// Load the keyring from the environment.
+    ///
+    /// Fails when a key is misconfigured, or when the placeholder or a short
+    /// HS256 secret is used while `ENV` isn't "development".
+    pub fn from_env() -> Result<Self, String> {
+        let development = std::env::var("ENV").is_ok_and(|env| env == "development");
+
+        let keyring = match std::env::var("JWT_KEYS").ok().filter(|v| !v.trim().is_empty()) {
+            Some(kids) => {
+                let kids: Vec<String> = kids
+                    .split(',')
+                    .map(|kid| kid.trim().to_string())
+                    .filter(|kid| !kid.is_empty())
+                    .collect();
+                let active_kid = std::env::var("JWT_ACTIVE_KID")
+                    .ok()
+                    .or_else(|| kids.first().cloned())
+                    .ok_or_else(|| "JWT_KEYS must list at least one key id".to_string())?;
+                let keys = kids
+                    .iter()
+                    .map(|kid| JwtKey::from_env(kid, development))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self { keys, active_kid }
+            }
+            None => {
+                let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string());
+                check_secret(LEGACY_KID, &secret, development)?;
+                Self {
+                    keys: vec![JwtKey::hmac(LEGACY_KID, &secret)],
+                    active_kid: LEGACY_KID.to_string(),
+                }
+            }
+        };
+
+        let active = keyring
+            .key(&keyring.active_kid)
+            .ok_or_else(|| format!("Active JWT key '{}' is not in JWT_KEYS", keyring.active_kid))?;
+        if active.encoding.is_none() {
+            return Err(format!("Active JWT key '{}' has no private key", active.kid));
+        }
+
+        Ok(keyring)
+    }
+
+    /// Make `keyring` the process-wide keyring; the first install wins
+    pub fn install(keyring: JwtKeyring) -> Arc<JwtKeyring> {
+        KEYRING.get_or_init(|| Arc::new(keyring)).clone()
+    }
+
+    /// The process-wide keyring, loaded from the environment on first use
+    /// if `main` hasn't installed one
+    pub fn global() -> AppResult<Arc<JwtKeyring>> {
+        if let Some(keyring) = KEYRING.get() {
+            return Ok(keyring.clone());
+        }
+
+        let keyring = Self::from_env().map_err(|e| {
+            tracing::error!("Invalid JWT key configuration: {}", e);
+            AppError::Config("Invalid JWT key configuration".to_string())
+        })?;
+        Ok(Self::install(keyring))
+    }
+
+    fn key(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub fn active_kid(&self) -> &str {
+        &self.active_kid
+    }
+
+    /// Sign claims with the active key
+    pub fn encode<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let key = self
+            .key(&self.active_kid)
+            .ok_or_else(|| AppError::Config("Active JWT key is missing".to_string()))?;
+        let encoding = key
+            .encoding
+            .as_ref()
+            .ok_or_else(|| AppError::Config("Active JWT key cannot sign".to_string()))?;
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+
+        encode(&header, claims, encoding).map_err(|e| {
+            tracing::error!("Failed to generate JWT: {}", e);
+            AppError::Internal("Token generation failed".to_string())
+        })
+    }
+
+    /// Verify a token against the key named by its `kid`, checking signature and expiry.
+    ///
+    /// Only the algorithm configured for that key is accepted, whatever the
+    /// token header claims.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> AppResult<T> {
+        let header = decode_header(token).map_err(|e| {
+            tracing::warn!("Invalid JWT header: {}", e);
+            AppError::Unauthorized
+        })?;
+        let kid = header.kid.as_deref().unwrap_or(LEGACY_KID);
+        let key = self.key(kid).ok_or_else(|| {
+            tracing::warn!(kid = kid, "JWT signed with unknown key");
+            AppError::Unauthorized
+        })?;
+
+        decode::<T>(token, &key.decoding, &Validation::new(key.algorithm))
+            .map(|data| data.claims)
+            .map_err(|e| {
+                tracing::warn!("Invalid JWT token: {}", e);
+                AppError::Unauthorized
+            })
+    }
+
+    /// JWKS document with the public half of every asymmetric key
+    pub fn jwks(&self) -> serde_json::Value {
+        serde_json::json!({
+            "keys": self.keys.iter().filter_map(|key| key.jwk.clone()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl JwtKey {
+    fn hmac(kid: &str, secret: &str) -> Self {
+        Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            jwk: None,
+        }
+    }
+
+    /// Read key `kid` from `JWT_KEY_<KID>_ALGORITHM` ("HS256", "RS256" or
+    /// "EdDSA") plus `_SECRET` for HS256, or `_PUBLIC_KEY[_FILE]` and optional
+    /// `_PRIVATE_KEY[_FILE]` PEMs for the asymmetric algorithms
+    fn from_env(kid: &str, development: bool) -> Result<Self, String> {
+        let prefix = env_prefix(kid);
+        let algorithm = std::env::var(format!("{}_ALGORITHM", prefix)).unwrap_or_else(|_| "HS256".to_string());
+
+        match algorithm.as_str() {
+            "HS256" => {
+                let secret = std::env::var(format!("{}_SECRET", prefix))
+                    .map_err(|_| format!("JWT key '{}' is missing {}_SECRET", kid, prefix))?;
+                check_secret(kid, &secret, development)?;
+                Ok(Self::hmac(kid, &secret))
+            }
+            "RS256" => {
+                let public_pem = read_pem(&prefix, "PUBLIC_KEY")?
+                    .ok_or_else(|| format!("JWT key '{}' is missing {}_PUBLIC_KEY_FILE", kid, prefix))?;
+                let encoding = read_pem(&prefix, "PRIVATE_KEY")?
+                    .map(|pem| EncodingKey::from_rsa_pem(pem.as_bytes()))
+                    .transpose()
+                    .map_err(|e| format!("JWT key '{}' has an invalid RSA private key: {}", kid, e))?;
+                let decoding = DecodingKey::from_rsa_pem(public_pem.as_bytes())
+                    .map_err(|e| format!("JWT key '{}' has an invalid RSA public key: {}", kid, e))?;
+
+                Ok(Self {
+                    kid: kid.to_string(),
+                    algorithm: Algorithm::RS256,
+                    encoding,
+                    decoding,
+                    jwk: Some(rsa_jwk(kid, &public_pem)?),
+                })
+            }
+            "EdDSA" => {
+                let public_pem = read_pem(&prefix, "PUBLIC_KEY")?
+                    .ok_or_else(|| format!("JWT key '{}' is missing {}_PUBLIC_KEY_FILE", kid, prefix))?;
+                let encoding = read_pem(&prefix, "PRIVATE_KEY")?
+                    .map(|pem| EncodingKey::from_ed_pem(pem.as_bytes()))
+                    .transpose()
+                    .map_err(|e| format!("JWT key '{}' has an invalid Ed25519 private key: {}", kid, e))?;
+                let decoding = DecodingKey::from_ed_pem(public_pem.as_bytes())
+                    .map_err(|e| format!("JWT key '{}' has an invalid Ed25519 public key: {}", kid, e))?;
+
+                Ok(Self {
+                    kid: kid.to_string(),
+                    algorithm: Algorithm::EdDSA,
+                    encoding,
+                    decoding,
+                    jwk: Some(ed25519_jwk(kid, &public_pem)?),
+                })
+            }
+            other => Err(format!("JWT key '{}' has unsupported algorithm '{}'", kid, other)),
+        }
+    }
+}
+
+/// Refuse the placeholder or a short HS256 secret unless in development
+#[cfg(feature = "ssr")]
+fn check_secret(kid: &str, secret: &str, development: bool) -> Result<(), String> {
+    if development {
+        return Ok(());
+    }
+    if secret == DEFAULT_JWT_SECRET {
+        return Err(format!(
+            "JWT key '{}' uses the placeholder secret; set a real one or ENV=development",
+            kid
+        ));
+    }
+    if secret.len() < MIN_SECRET_LENGTH {
+        return Err(format!(
+            "JWT key '{}' secret must be at least {} characters",
+            kid, MIN_SECRET_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Environment variable prefix for a key id, e.g. "2025-11" -> "JWT_KEY_2025_11"
+#[cfg(feature = "ssr")]
+fn env_prefix(kid: &str) -> String {
+    let name: String = kid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("JWT_KEY_{}", name)
+}
+
+/// Read a PEM from `<PREFIX>_<NAME>` (inline, `\n` escapes allowed) or the
+/// file named by `<PREFIX>_<NAME>_FILE`
+#[cfg(feature = "ssr")]
+fn read_pem(prefix: &str, name: &str) -> Result<Option<String>, String> {
+    if let Ok(pem) = std::env::var(format!("{}_{}", prefix, name)) {
+        return Ok(Some(pem.replace("\\n", "\n")));
+    }
+    match std::env::var(format!("{}_{}_FILE", prefix, name)) {
+        Ok(path) => std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| format!("Failed to read {}: {}", path, e)),
+        Err(_) => Ok(None),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn rsa_jwk(kid: &str, public_pem: &str) -> Result<serde_json::Value, String> {
+    use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
+
+    let key = RsaPublicKey::from_public_key_pem(public_pem)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(public_pem))
+        .map_err(|e| format!("JWT key '{}' has an invalid RSA public key: {}", kid, e))?;
+
+    Ok(serde_json::json!({
+        "kty": "RSA",
+        "use": "sig",
+        "alg": "RS256",
+        "kid": kid,
+        "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+    }))
+}
+
+#[cfg(feature = "ssr")]
+fn ed25519_jwk(kid: &str, public_pem: &str) -> Result<serde_json::Value, String> {
+    use base64::engine::general_purpose::STANDARD;
+
+    let body: String = public_pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let der = STANDARD
+        .decode(body.trim())
+        .map_err(|e| format!("JWT key '{}' has an invalid Ed25519 public key: {}", kid, e))?;
+    let raw = der
+        .strip_prefix(&ED25519_SPKI_PREFIX[..])
+        .filter(|raw| raw.len() == 32)
+        .ok_or_else(|| format!("JWT key '{}' is not an Ed25519 public key", kid))?;
+
+    Ok(serde_json::json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "use": "sig",
+        "alg": "EdDSA",
+        "kid": kid,
+        "x": URL_SAFE_NO_PAD.encode(raw),
+    }))
+}
+
+/// `GET /.well-known/jwks.json`: public keys for services verifying our tokens
+#[cfg(feature = "ssr")]
+pub async fn jwks_handler() -> Response {
+    match JwtKeyring::global() {
+        Ok(keyring) => (
+            [(header::CACHE_CONTROL, "public, max-age=300")],
+            Json(keyring.jwks()),
+        )
+            .into_response(),
+        Err(e) => e.status_code().into_response(),
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const RSA_PUBLIC_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAq7hnqeXaRLIIERga9HZC
+8dqyb9Z3OZEkGiXlDyf/jIhxsP8+XU78zlGUTB307gT7n1k34axsuj1Qz4CyNs5U
+en0XRhu3Jec4kGiDT+vCaXuc4Pl0jfNIhO41Tzavbq/YZGPaeYMYuVcPsExlkehR
+PbTi2Xbk5G7hK7CTVhKHLGWoqgL+mSptJZTUqhOJzDR5rbrggkVgpHobWzy/ClVZ
+0Z7axQLGFVHy9LM4UT6c4aZdKR6PbkCG09jrOSk2dx7AO9JyH7yGrT3aL/LJ1PL0
+i9ElV1E9JN2aX0zSXcfETikjhcwlgGtIqfHSWLHwGpuvhDBqsOhtp6PcG0HMnOfT
+LwIDAQAB
+-----END PUBLIC KEY-----
+";
+
+    const ED25519_PUBLIC_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAzQcQqnIeY2Ih+121kAGU1NpXtKHLXQz+JxfA2aW4b1s=
+-----END PUBLIC KEY-----
+";
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn keyring(kids: &[&str], active_kid: &str) -> JwtKeyring {
+        JwtKeyring {
+            keys: kids.iter().map(|kid| JwtKey::hmac(kid, &format!("{}-{}", SECRET, kid))).collect(),
+            active_kid: active_kid.to_string(),
+        }
+    }
+
+    fn claims() -> Value {
+        json!({ "sub": "1", "exp": chrono::Utc::now().timestamp() + 60 })
+    }
+
+    #[test]
+    fn round_trip_through_the_active_kid() {
+        let ring = keyring(&["old", "new"], "new");
+        let token = ring.encode(&claims()).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("new"));
+        assert_eq!(header.alg, Algorithm::HS256);
+
+        let decoded: Value = ring.decode(&token).unwrap();
+        assert_eq!(decoded["sub"], "1");
+    }
+
+    #[test]
+    fn retired_keys_still_verify() {
+        let token = keyring(&["old"], "old").encode(&claims()).unwrap();
+        let rotated = keyring(&["old", "new"], "new");
+        assert!(rotated.decode::<Value>(&token).is_ok());
+    }
+
+    #[test]
+    fn algorithm_other_than_the_kid_pins_is_rejected() {
+        let ring = keyring(&["main"], "main");
+        let mut header = Header::new(Algorithm::HS384);
+        header.kid = Some("main".to_string());
+        let secret = format!("{}-main", SECRET);
+        let token = encode(&header, &claims(), &EncodingKey::from_secret(secret.as_bytes())).unwrap();
+
+        assert!(matches!(ring.decode::<Value>(&token), Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn unknown_kid_is_rejected() {
+        let token = keyring(&["elsewhere"], "elsewhere").encode(&claims()).unwrap();
+        let ring = keyring(&["main"], "main");
+        assert!(matches!(ring.decode::<Value>(&token), Err(AppError::Unauthorized)));
+    }
+
+    #[test]
+    fn check_secret_refuses_placeholder_and_short_secrets_outside_development() {
+        assert!(check_secret("k", DEFAULT_JWT_SECRET, false).is_err());
+        assert!(check_secret("k", "too-short", false).is_err());
+        assert!(check_secret("k", SECRET, false).is_ok());
+
+        assert!(check_secret("k", DEFAULT_JWT_SECRET, true).is_ok());
+        assert!(check_secret("k", "too-short", true).is_ok());
+    }
+
+    #[test]
+    fn rsa_jwk_publishes_modulus_and_exponent() {
+        let jwk = rsa_jwk("rsa-1", RSA_PUBLIC_PEM).unwrap();
+        assert_eq!(jwk["kty"], "RSA");
+        assert_eq!(jwk["alg"], "RS256");
+        assert_eq!(jwk["use"], "sig");
+        assert_eq!(jwk["kid"], "rsa-1");
+        assert_eq!(jwk["e"], "AQAB");
+        let n = URL_SAFE_NO_PAD.decode(jwk["n"].as_str().unwrap()).unwrap();
+        assert_eq!(n.len(), 256);
+    }
+
+    #[test]
+    fn ed25519_jwk_publishes_the_raw_key() {
+        let jwk = ed25519_jwk("ed-1", ED25519_PUBLIC_PEM).unwrap();
+        assert_eq!(jwk["kty"], "OKP");
+        assert_eq!(jwk["crv"], "Ed25519");
+        assert_eq!(jwk["alg"], "EdDSA");
+        assert_eq!(jwk["use"], "sig");
+        assert_eq!(jwk["kid"], "ed-1");
+        assert_eq!(jwk["x"], "zQcQqnIeY2Ih-121kAGU1NpXtKHLXQz-JxfA2aW4b1s");
+
+        assert!(ed25519_jwk("rsa-1", RSA_PUBLIC_PEM).is_err());
+    }
+
+    #[test]
+    fn jwks_leaves_out_shared_secrets() {
+        assert_eq!(keyring(&["main"], "main").jwks(), json!({ "keys": [] }));
+    }
+}
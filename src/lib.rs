@@ -12,6 +12,50 @@ pub mod security;
 pub mod config;
 #[cfg(feature = "ssr")]
 pub mod auth;
+#[cfg(feature = "ssr")]
+pub mod session;
+#[cfg(feature = "ssr")]
+pub mod mailer;
+#[cfg(feature = "ssr")]
+pub mod password;
+#[cfg(feature = "ssr")]
+pub mod settings;
+#[cfg(feature = "ssr")]
+pub mod two_factor;
+#[cfg(feature = "ssr")]
+pub mod oauth;
+#[cfg(feature = "ssr")]
+pub mod lockout;
+#[cfg(feature = "ssr")]
+pub mod guards;
+#[cfg(feature = "ssr")]
+pub mod access_tokens;
+#[cfg(feature = "ssr")]
+pub mod jwt_keys;
+#[cfg(feature = "ssr")]
+pub mod invites;
+#[cfg(feature = "ssr")]
+pub mod privacy;
+#[cfg(feature = "ssr")]
+pub mod magic_link;
+#[cfg(feature = "ssr")]
+pub mod audit;
+#[cfg(feature = "ssr")]
+pub mod impersonation;
+#[cfg(feature = "ssr")]
+pub mod posts;
+#[cfg(feature = "ssr")]
+pub mod revisions;
+#[cfg(feature = "ssr")]
+pub mod publishing;
+#[cfg(feature = "ssr")]
+pub mod previews;
+#[cfg(feature = "ssr")]
+pub mod tags;
+#[cfg(feature = "ssr")]
+pub mod slugs;
+#[cfg(feature = "ssr")]
+pub mod categories;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
@@ -0,0 +1,365 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    audit::{AuditService, ACCOUNT_UNLOCKED},
+    db::Db,
+    error::{AppError, AppResult},
+    mailer::{EmailMessage, Mailer},
+    models::{LockedAccount, User},
+    security::{generate_secure_token, hash_token},
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use std::net::IpAddr;
+
+#[cfg(feature = "ssr")]
+const UNLOCK_TOKEN_EXPIRY_HOURS: i64 = 24;
+
+/// Failed-login bookkeeping for one identifier
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Default)]
+pub struct FailureState {
+    pub failures: i32,
+    pub last_failed_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Per-account and per-IP brute-force protection for password logins.
+///
+/// Unknown identifiers are throttled exactly like real accounts so that the
+/// responses don't reveal which usernames exist.
+#[cfg(feature = "ssr")]
+pub struct LoginThrottle {
+    lockout_threshold: i32,
+    lockout_minutes: i64,
+    backoff_after: i32,
+    backoff_max_seconds: i64,
+    ip_max_failures: i64,
+    ip_window_minutes: i64,
+    app_base_url: String,
+}
+
+#[cfg(feature = "ssr")]
+impl LoginThrottle {
+    pub fn new() -> Self {
+        Self {
+            lockout_threshold: std::env::var("LOGIN_LOCKOUT_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            lockout_minutes: std::env::var("LOGIN_LOCKOUT_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            backoff_after: std::env::var("LOGIN_BACKOFF_AFTER")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            backoff_max_seconds: std::env::var("LOGIN_BACKOFF_MAX_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            ip_max_failures: std::env::var("LOGIN_IP_MAX_FAILURES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50),
+            ip_window_minutes: std::env::var("LOGIN_IP_WINDOW_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        }
+    }
+
+    /// Normalise the submitted email or username so case variations share a counter
+    pub fn normalize_identifier(identifier: &str) -> String {
+        identifier.trim().to_lowercase()
+    }
+
+    /// Delay required after `failures` consecutive failures: 1s, 2s, 4s, ...
+    /// once past the back-off threshold, capped at the configured maximum
+    fn backoff(&self, failures: i32) -> Duration {
+        if failures < self.backoff_after {
+            return Duration::zero();
+        }
+        let exponent = (failures - self.backoff_after).min(30) as u32;
+        Duration::seconds(2i64.saturating_pow(exponent).min(self.backoff_max_seconds))
+    }
+
+    /// Reject the request when this IP has failed too often across all accounts
+    pub async fn check_ip(&self, db: &Db, ip: Option<IpAddr>) -> AppResult<()> {
+        let Some(ip) = ip else {
+            return Ok(());
+        };
+
+        let failures = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM login_attempts
+            WHERE ip_address = $1::text::inet AND NOT succeeded
+              AND attempted_at > NOW() - make_interval(mins => $2)
+            "#,
+        )
+        .bind(ip.to_string())
+        .bind(self.ip_window_minutes as i32)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if failures >= self.ip_max_failures {
+            tracing::warn!(client_ip = %ip, failures = failures, "Login attempts throttled for IP");
+            return Err(AppError::RateLimitExceeded(
+                "Too many failed sign-in attempts. Please wait and try again.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Failed attempts on an account within the lockout window, since its last
+    /// successful login or unlock
+    async fn account_failures(&self, db: &Db, user_id: i64) -> AppResult<(i32, Option<DateTime<Utc>>)> {
+        let (failures, last_failed_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT COUNT(a.id), MAX(a.attempted_at)
+            FROM users u
+            JOIN login_attempts a ON a.user_id = u.id AND NOT a.succeeded
+            WHERE u.id = $1
+              AND a.attempted_at > NOW() - make_interval(mins => $2)
+              AND a.attempted_at > COALESCE(u.failed_logins_reset_at, '-infinity')
+            "#,
+        )
+        .bind(user_id)
+        .bind(self.lockout_minutes as i32)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok((failures as i32, last_failed_at))
+    }
+
+    /// Current failure state for an account, or for an identifier matching no account
+    pub async fn failure_state(&self, db: &Db, identifier: &str, user: Option<&User>) -> AppResult<FailureState> {
+        if let Some(user) = user {
+            let (failures, last_failed_at) = self.account_failures(db, user.id).await?;
+            let locked_until = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+                "SELECT locked_until FROM users WHERE id = $1",
+            )
+            .bind(user.id)
+            .fetch_one(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+            return Ok(FailureState { failures, last_failed_at, locked_until });
+        }
+
+        let (failures, last_failed_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT COUNT(*), MAX(attempted_at) FROM login_attempts
+            WHERE identifier = $1 AND user_id IS NULL AND NOT succeeded
+              AND attempted_at > NOW() - make_interval(mins => $2)
+            "#,
+        )
+        .bind(identifier)
+        .bind(self.lockout_minutes as i32)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let failures = failures as i32;
+        let locked_until = last_failed_at
+            .filter(|_| failures >= self.lockout_threshold)
+            .map(|at| at + Duration::minutes(self.lockout_minutes));
+
+        Ok(FailureState { failures, last_failed_at, locked_until })
+    }
+
+    /// Refuse the attempt while the identifier is locked or still backing off
+    pub fn ensure_allowed(&self, state: &FailureState) -> AppResult<()> {
+        let now = Utc::now();
+
+        if state.locked_until.is_some_and(|until| until > now) {
+            return Err(AppError::RateLimitExceeded(
+                "Too many failed sign-in attempts. Please wait and try again, or check your email for an unlock link."
+                    .to_string(),
+            ));
+        }
+
+        if let Some(last_failed_at) = state.last_failed_at {
+            if last_failed_at + self.backoff(state.failures) > now {
+                return Err(AppError::RateLimitExceeded(
+                    "Please wait a moment before trying again.".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn record_attempt(&self, db: &Db, identifier: &str, user_id: Option<i64>, ip: Option<IpAddr>, succeeded: bool) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO login_attempts (identifier, user_id, ip_address, succeeded)
+            VALUES ($1, $2, $3::text::inet, $4)
+            "#,
+        )
+        .bind(identifier)
+        .bind(user_id)
+        .bind(ip.map(|ip| ip.to_string()))
+        .bind(succeeded)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, locking the account and emailing an unlock
+    /// link once the threshold is reached
+    pub async fn record_failure(&self, db: &Db, mailer: &dyn Mailer, identifier: &str, user: Option<&User>, ip: Option<IpAddr>) -> AppResult<()> {
+        self.record_attempt(db, identifier, user.map(|u| u.id), ip, false).await?;
+
+        let Some(user) = user else {
+            return Ok(());
+        };
+
+        let (failures, _) = self.account_failures(db, user.id).await?;
+        let locked_until = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = $2,
+                last_failed_login_at = NOW(),
+                locked_until = CASE
+                    WHEN $2 >= $3 THEN NOW() + make_interval(mins => $4)
+                    ELSE locked_until
+                END
+            WHERE id = $1
+            RETURNING locked_until
+            "#,
+        )
+        .bind(user.id)
+        .bind(failures)
+        .bind(self.lockout_threshold)
+        .bind(self.lockout_minutes as i32)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        // The caller only gets here when the account wasn't already locked,
+        // so reaching the threshold means the lock was just applied
+        if failures >= self.lockout_threshold {
+            tracing::warn!(
+                user_id = user.id,
+                failures = failures,
+                locked_until = ?locked_until,
+                "Account locked after repeated failed logins"
+            );
+
+            // The lock holds either way; a mail failure must not change the response
+            if let Err(e) = self.send_unlock_email(db, mailer, user).await {
+                tracing::error!("Failed to send account unlock email: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a successful login and clear the account's failure counter
+    pub async fn record_success(&self, db: &Db, identifier: &str, user: &User, ip: Option<IpAddr>) -> AppResult<()> {
+        self.record_attempt(db, identifier, Some(user.id), ip, true).await?;
+        self.clear_lock(db, user.id).await
+    }
+
+    async fn clear_lock(&self, db: &Db, user_id: i64) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL,
+                failed_logins_reset_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn send_unlock_email(&self, db: &Db, mailer: &dyn Mailer, user: &User) -> AppResult<()> {
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::hours(UNLOCK_TOKEN_EXPIRY_HOURS);
+
+        sqlx::query("INSERT INTO account_unlock_tokens (user_id, token, expires_at) VALUES ($1, $2, $3)")
+            .bind(user.id)
+            .bind(hash_token(&token))
+            .bind(expires_at)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        mailer.send(&EmailMessage {
+            to: user.email.clone(),
+            subject: "Your Taleji account has been locked".to_string(),
+            body: format!(
+                "We locked your account for {} minutes after too many failed sign-in attempts.\n\n\
+                 If this was you, unlock it now by opening this link:\n{}/unlock-account/{}\n\n\
+                 If it wasn't, someone may be guessing your password. Consider resetting it:\n{}/forgot-password",
+                self.lockout_minutes, self.app_base_url, token, self.app_base_url
+            ),
+        })
+    }
+
+    /// Redeem an emailed unlock link
+    pub async fn unlock_with_token(&self, db: &Db, token: &str) -> AppResult<()> {
+        let user_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE account_unlock_tokens SET used = true
+            WHERE token = $1 AND used = false AND expires_at > NOW()
+            RETURNING user_id
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("Invalid or expired unlock link".to_string()))?;
+
+        self.clear_lock(db, user_id).await?;
+        tracing::info!(user_id = user_id, "Account unlocked via email link");
+        Ok(())
+    }
+
+    /// Lift a lock on behalf of an admin and record it in the audit log
+    pub async fn unlock(&self, db: &Db, user_id: i64, unlocked_by: i64, ip: Option<IpAddr>) -> AppResult<()> {
+        self.clear_lock(db, user_id).await?;
+        AuditService::new()
+            .record(db, unlocked_by, ACCOUNT_UNLOCKED, Some(user_id), None, ip)
+            .await?;
+        tracing::info!(user_id = user_id, unlocked_by = unlocked_by, "Account unlocked by admin");
+        Ok(())
+    }
+
+    /// Accounts currently locked out, most recent first
+    pub async fn locked_accounts(&self, db: &Db) -> AppResult<Vec<LockedAccount>> {
+        sqlx::query_as::<_, LockedAccount>(
+            r#"
+            SELECT id AS user_id, username, email, failed_login_attempts,
+                   last_failed_login_at, locked_until
+            FROM users
+            WHERE locked_until > NOW()
+            ORDER BY locked_until DESC
+            "#,
+        )
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    mailer::{EmailMessage, Mailer},
+    models::MagicLinkInput,
+    security::{generate_secure_token, hash_token},
+};
+#[cfg(feature = "ssr")]
+use chrono::{Duration, Utc};
+#[cfg(feature = "ssr")]
+use std::net::IpAddr;
+
+/// Passwordless sign-in through single-use links emailed to the account address.
+///
+/// Every request gets the same answer whether or not the address has an
+/// account, and is throttled per address and per IP.
+#[cfg(feature = "ssr")]
+pub struct MagicLinkService {
+    expiry_minutes: i64,
+    max_per_address: i64,
+    max_per_ip: i64,
+    app_base_url: String,
+}
+
+#[cfg(feature = "ssr")]
+impl MagicLinkService {
+    pub fn new() -> Self {
+        Self {
+            expiry_minutes: std::env::var("MAGIC_LINK_EXPIRY_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            max_per_address: std::env::var("MAGIC_LINK_MAX_PER_ADDRESS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            max_per_ip: std::env::var("MAGIC_LINK_MAX_PER_IP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20),
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        }
+    }
+
+    /// Email a sign-in link if the address belongs to an active account
+    pub async fn request(&self, db: &Db, mailer: &dyn Mailer, input: MagicLinkInput, ip: Option<IpAddr>) -> AppResult<()> {
+        let email = input.email.trim().to_lowercase();
+
+        // Hourly limits, counted the same for unknown addresses
+        let (per_address, per_ip) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE email = $1),
+                COUNT(*) FILTER (WHERE requested_ip = $2::text::inet)
+            FROM magic_link_tokens
+            WHERE created_at > NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(&email)
+        .bind(ip.map(|ip| ip.to_string()))
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if per_address >= self.max_per_address || per_ip >= self.max_per_ip {
+            tracing::warn!(client_ip = ?ip, "Magic link requests throttled");
+            return Err(AppError::RateLimitExceeded(
+                "Too many sign-in links requested. Please wait a while and try again.".to_string(),
+            ));
+        }
+
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM users WHERE LOWER(email) = $1 AND is_active = true",
+        )
+        .bind(&email)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::minutes(self.expiry_minutes);
+
+        sqlx::query(
+            r#"
+            INSERT INTO magic_link_tokens (email, user_id, token, remember_me, requested_ip, expires_at)
+            VALUES ($1, $2, $3, $4, $5::text::inet, $6)
+            "#,
+        )
+        .bind(&email)
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(input.remember_me.unwrap_or(false))
+        .bind(ip.map(|ip| ip.to_string()))
+        .bind(expires_at)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let Some(user_id) = user_id else {
+            tracing::info!("Magic link requested for unknown address");
+            return Ok(());
+        };
+
+        // Don't reveal whether the address exists through a mail error
+        if let Err(e) = mailer.send(&EmailMessage {
+            to: email,
+            subject: "Your Taleji sign-in link".to_string(),
+            body: format!(
+                "Use this link within {} minutes to sign in to Taleji:\n{}/magic-login/{}\n\n\
+                 The link works once. If you didn't ask for it, you can ignore this email.",
+                self.expiry_minutes, self.app_base_url, token
+            ),
+        }) {
+            tracing::error!("Failed to send magic link email: {}", e);
+        }
+
+        tracing::info!("Magic link sent to user {}", user_id);
+        Ok(())
+    }
+
+    /// Consume a link; returns the account it signs in to and the "remember me" choice
+    pub async fn redeem(&self, db: &Db, token: &str) -> AppResult<(i64, bool)> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        // The link only counts while it still matches the account's address
+        let (user_id, remember_me) = sqlx::query_as::<_, (i64, bool)>(
+            r#"
+            UPDATE magic_link_tokens m SET used = true
+            FROM users u
+            WHERE m.token = $1 AND m.used = false AND m.expires_at > NOW()
+              AND u.id = m.user_id AND u.is_active = true AND LOWER(u.email) = m.email
+            RETURNING m.user_id, m.remember_me
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("This sign-in link is invalid or has expired".to_string()))?;
+
+        // Other outstanding links for the account stop working too
+        sqlx::query("UPDATE magic_link_tokens SET used = true WHERE user_id = $1 AND used = false")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        // Opening the link proves the address belongs to the user
+        sqlx::query("UPDATE users SET email_verified = true WHERE id = $1 AND email_verified = false")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!("Magic link redeemed for user {}", user_id);
+        Ok((user_id, remember_me))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for MagicLinkService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(feature = "ssr")]
+use crate::error::{AppError, AppResult};
+#[cfg(feature = "ssr")]
+use std::{path::PathBuf, sync::Arc};
+
+/// An outgoing email message
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivery backend for transactional email
+#[cfg(feature = "ssr")]
+pub trait Mailer: Send + Sync {
+    fn send(&self, message: &EmailMessage) -> AppResult<()>;
+}
+
+/// Shared mailer handle provided through Leptos context
+#[cfg(feature = "ssr")]
+pub type SharedMailer = Arc<dyn Mailer>;
+
+/// Writes messages to the log; the default for development
+#[cfg(feature = "ssr")]
+pub struct StdoutMailer;
+
+#[cfg(feature = "ssr")]
+impl Mailer for StdoutMailer {
+    fn send(&self, message: &EmailMessage) -> AppResult<()> {
+        tracing::info!(
+            to = %message.to,
+            subject = %message.subject,
+            "Outgoing email:\n{}",
+            message.body
+        );
+        Ok(())
+    }
+}
+
+/// Writes each message to its own file in a directory, for offline testing
+#[cfg(feature = "ssr")]
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "ssr")]
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Mailer for FileMailer {
+    fn send(&self, message: &EmailMessage) -> AppResult<()> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            tracing::error!("Failed to create mail directory: {}", e);
+            AppError::Internal("Email delivery failed".to_string())
+        })?;
+
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            uuid::Uuid::new_v4()
+        ));
+        let contents = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            message.to, message.subject, message.body
+        );
+
+        std::fs::write(&path, contents).map_err(|e| {
+            tracing::error!("Failed to write email to {}: {}", path.display(), e);
+            AppError::Internal("Email delivery failed".to_string())
+        })
+    }
+}
+
+/// Build the mailer selected by `MAIL_TRANSPORT` ("stdout" or "file")
+#[cfg(feature = "ssr")]
+pub fn mailer_from_env() -> SharedMailer {
+    match std::env::var("MAIL_TRANSPORT").as_deref() {
+        Ok("file") => {
+            let dir = std::env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "target/mail".to_string());
+            Arc::new(FileMailer::new(dir))
+        }
+        _ => Arc::new(StdoutMailer),
+    }
+}
@@ -1,11 +1,12 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    use axum::{Router, middleware};
+    use axum::{Extension, Router, middleware, routing::get};
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use leptos_blog::app::*;
-    use leptos_blog::{db, security};
+    use leptos_blog::{config::AppConfig, db, jwt_keys::{self, JwtKeyring}, mailer, oauth, privacy, publishing, security};
+    use std::sync::Arc;
     use tower_http::{compression::CompressionLayer, trace::TraceLayer};
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -29,6 +30,13 @@ async fn main() -> anyhow::Result<()> {
 
     let db_url = std::env::var("DATABASE_URL")
         .expect("DATABASE_URL must be set");
+
+    let config = AppConfig::from_env();
+    config.validate().map_err(anyhow::Error::msg)?;
+
+    // Refuse to start with a missing or placeholder signing key
+    let keyring = JwtKeyring::install(JwtKeyring::from_env().map_err(anyhow::Error::msg)?);
+    tracing::info!("JWT signing key: {}", keyring.active_kid());
     
     let pool = db::create_pool(&db_url).await?;
     
@@ -36,21 +44,86 @@ async fn main() -> anyhow::Result<()> {
     db::health_check(&pool).await?;
     tracing::info!("Database connection established: {}", db::pool_status(&pool));
 
+    // Maintenance commands run against the database and exit
+    if std::env::args().nth(1).as_deref() == Some("repair-tag-counts") {
+        let fixed = leptos_blog::tags::TagService::new().recount_all(&pool).await?;
+        tracing::info!("Repaired {} tag usage counts", fixed);
+        return Ok(());
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("refresh-text-stats") {
+        let refreshed = leptos_blog::posts::PostService::new().refresh_text_stats(&pool).await?;
+        tracing::info!("Refreshed word counts and reading times of {} posts", refreshed);
+        return Ok(());
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("rerender-html") {
+        let rendered = leptos_blog::posts::PostService::new().rerender_html(&pool).await?;
+        tracing::info!("Re-rendered the HTML of {} posts and revisions", rendered);
+        return Ok(());
+    }
+
+    let mailer = mailer::mailer_from_env();
+    let oauth_service = Arc::new(oauth::OAuthService::new(config.oauth.clone())?);
+
+    // Periodically drop expired tokens, login challenges and OAuth states, and
+    // purge accounts whose deletion grace period has passed
+    {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let auth_service = leptos_blog::auth::AuthService::new();
+            let privacy_service = privacy::PrivacyService::new();
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = auth_service.purge_expired_tokens(&pool).await {
+                    tracing::error!("Failed to purge expired tokens: {}", e);
+                }
+                if let Err(e) = privacy_service.purge_due_deletions(&pool).await {
+                    tracing::error!("Failed to purge deleted accounts: {}", e);
+                }
+            }
+        });
+    }
+
+    // Fire publish events as scheduled posts go live or expire
+    {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let publishing_service = publishing::PublishingService::new();
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(publishing_service.poll_seconds));
+            loop {
+                interval.tick().await;
+                if let Err(e) = publishing_service.dispatch_due(&pool).await {
+                    tracing::error!("Failed to dispatch publish events: {}", e);
+                }
+            }
+        });
+    }
+
     let routes = generate_route_list(App);
 
     // Create rate limiter (100 requests per minute)
     let rate_limiter = security::RateLimiter::new(100, 60);
 
     let app = Router::new()
+        .route("/.well-known/jwks.json", get(jwt_keys::jwks_handler))
+        .route("/account/export", get(privacy::export_handler))
+        .route("/auth/oauth/:provider", get(oauth::start_handler))
+        .route("/auth/oauth/:provider/callback", get(oauth::callback_handler))
         .leptos_routes_with_context(
             &leptos_options,
             routes,
             move || {
                 provide_context(pool.clone());
+                provide_context(mailer.clone());
+                provide_context(oauth_service.clone());
                 provide_context(leptos_blog::auth::AuthService::new());
             },
             App,
         )
+        .layer(Extension(pool.clone()))
+        .layer(Extension(oauth_service.clone()))
         // Add security middleware
         .layer(middleware::from_fn(security::request_id))
         .layer(middleware::from_fn(security::security_headers))
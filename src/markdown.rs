@@ -1,12 +1,217 @@
-use pulldown_cmark::{Options, Parser, html};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd, html};
 
-pub fn markdown_to_html(md: &str) -> String {
+/// Reading speed for space-separated words
+const WORDS_PER_MINUTE: f64 = 230.0;
+/// Reading speed for Chinese and Japanese text, which is read per character
+const CJK_CHARS_PER_MINUTE: f64 = 500.0;
+/// Length of a generated excerpt, in characters
+const EXCERPT_LENGTH: usize = 200;
+
+fn options() -> Options {
     let mut opts = Options::empty();
     opts.insert(Options::ENABLE_TABLES);
     opts.insert(Options::ENABLE_FOOTNOTES);
     opts.insert(Options::ENABLE_STRIKETHROUGH);
-    let parser = Parser::new_ext(md, opts);
+    opts
+}
+
+/// Render markdown to HTML that is safe to insert into a page: scripts,
+/// event handlers and `javascript:` links in raw HTML are removed
+pub fn markdown_to_html(md: &str) -> String {
+    let parser = Parser::new_ext(md, options());
     let mut out = String::new();
-    html::push_html(&mut out, parser);
-    out
+    #[cfg(feature = "ssr")]
+    {
+        html::push_html(&mut out, parser);
+        sanitizer().clean(&out).to_string()
+    }
+    // Without the sanitizer, raw HTML is shown as text
+    #[cfg(not(feature = "ssr"))]
+    {
+        html::push_html(&mut out, parser.map(|event| match event {
+            Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+            event => event,
+        }));
+        out
+    }
+}
+
+/// Ammonia's defaults, plus the attributes footnotes, code blocks and table
+/// alignment are rendered with
+#[cfg(feature = "ssr")]
+fn sanitizer() -> ammonia::Builder<'static> {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_generic_attributes(["id", "class"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .filter_style_properties(["text-align"].into());
+    builder
+}
+
+/// Figures derived from a post's readable text when it is saved
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStats {
+    pub word_count: i32,
+    pub reading_time_minutes: i32,
+    pub excerpt: String,
+}
+
+/// Han ideographs and kana; each character counts as one word
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Word count, reading time and a plaintext excerpt of the text readers see,
+/// leaving out code blocks and HTML
+pub fn text_stats(md: &str) -> TextStats {
+    let mut text = String::new();
+    let mut paragraphs = String::new();
+    let mut in_code_block = false;
+    let mut in_paragraph = false;
+
+    for event in Parser::new_ext(md, options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Paragraph) => in_paragraph = true,
+            Event::End(TagEnd::Paragraph) => {
+                in_paragraph = false;
+                text.push(' ');
+                paragraphs.push(' ');
+            }
+            Event::Text(t) | Event::Code(t) if !in_code_block => {
+                text.push_str(&t);
+                if in_paragraph {
+                    paragraphs.push_str(&t);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                text.push(' ');
+                paragraphs.push(' ');
+            }
+            Event::End(TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let (mut words, mut cjk_chars) = (0usize, 0usize);
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk_chars += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if c.is_whitespace() || !matches!(c, '\'' | '’' | '-') {
+            in_word = false;
+        }
+    }
+
+    let minutes = words as f64 / WORDS_PER_MINUTE + cjk_chars as f64 / CJK_CHARS_PER_MINUTE;
+
+    TextStats {
+        word_count: i32::try_from(words + cjk_chars).unwrap_or(i32::MAX),
+        reading_time_minutes: (minutes.ceil() as i32).max(1),
+        excerpt: excerpt(&paragraphs),
+    }
+}
+
+/// The opening of `text` with whitespace collapsed, cut at a word boundary
+/// where there is one
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_LENGTH {
+        return text;
+    }
+
+    let cut: String = text.chars().take(EXCERPT_LENGTH).collect();
+    let cut = match cut.rfind(' ') {
+        Some(i) if !cut.ends_with(|c: char| is_cjk(c)) => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_to_html_strips_scripts_and_handlers() {
+        let html = markdown_to_html("<script>alert(1)</script>\n\n<img src=\"a.png\" onerror=\"alert(1)\">");
+        assert!(!html.contains("script"));
+        assert!(!html.contains("onerror"));
+        assert!(html.contains("<img src=\"a.png\">"));
+    }
+
+    #[test]
+    fn markdown_to_html_drops_javascript_links() {
+        let html = markdown_to_html("[click](javascript:alert(1))");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(">click</a>"));
+    }
+
+    #[test]
+    fn markdown_to_html_keeps_footnotes_code_and_alignment() {
+        let html = markdown_to_html("a[^1]\n\n[^1]: note\n\n```rust\nfn f() {}\n```\n\n| x |\n|--:|\n| 1 |");
+        assert!(html.contains("id=\"1\""));
+        assert!(html.contains("class=\"language-rust\""));
+        assert!(html.contains("style=\"text-align:right\""));
+    }
+
+    #[test]
+    fn text_stats_counts_only_readable_words() {
+        let md = "# Title\n\nHello *world*, don't stop the well-known `code`.\n\n```\nlet ignored = words;\n```\n\n<div>raw html</div>\n";
+        assert_eq!(text_stats(md).word_count, 8);
+    }
+
+    #[test]
+    fn text_stats_counts_cjk_characters_as_words() {
+        assert_eq!(text_stats("日本語の文章").word_count, 6);
+        assert_eq!(text_stats("Rust と 日本").word_count, 4);
+    }
+
+    #[test]
+    fn text_stats_rounds_reading_time_up_to_at_least_a_minute() {
+        assert_eq!(text_stats("").reading_time_minutes, 1);
+        assert_eq!(text_stats(&"word ".repeat(230)).reading_time_minutes, 1);
+        assert_eq!(text_stats(&"word ".repeat(231)).reading_time_minutes, 2);
+        assert_eq!(text_stats(&"字".repeat(1000)).reading_time_minutes, 2);
+    }
+
+    #[test]
+    fn text_stats_excerpt_comes_from_paragraphs() {
+        let stats = text_stats("# Heading\n\nFirst   paragraph\nwraps here.\n\n- a list item\n\nSecond one.");
+        assert_eq!(stats.excerpt, "First paragraph wraps here. Second one.");
+    }
+
+    #[test]
+    fn excerpt_keeps_short_text_whole() {
+        assert_eq!(excerpt("  short \n text  "), "short text");
+    }
+
+    #[test]
+    fn excerpt_cuts_long_text_at_a_word_boundary() {
+        let text = "lorem ipsum, ".repeat(40);
+        let cut = excerpt(&text);
+        assert!(cut.ends_with("ipsum…") || cut.ends_with("lorem…"), "{cut}");
+        assert!(cut.chars().count() <= EXCERPT_LENGTH + 1);
+        assert!(text.starts_with(cut.trim_end_matches('…')));
+    }
+
+    #[test]
+    fn excerpt_cuts_cjk_text_at_the_length_limit() {
+        let cut = excerpt(&"字".repeat(EXCERPT_LENGTH + 50));
+        assert_eq!(cut, format!("{}…", "字".repeat(EXCERPT_LENGTH)));
+    }
 }
@@ -9,7 +9,7 @@ use validator::{Validate, ValidationError};
 /// User roles for authorization
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[cfg_attr(feature = "ssr", derive(sqlx::Type))]
-#[cfg_attr(feature = "ssr", sqlx(type_name = "text"))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "text", rename_all = "lowercase"))]
 pub enum UserRole {
     #[serde(rename = "user")]
     User,
@@ -101,6 +101,41 @@ pub struct Tag {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Admin input for creating or editing a category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct CategoryInput {
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters")))]
+    pub name: String,
+
+    /// Derived from the name when blank
+    #[cfg_attr(feature = "ssr", validate(length(max = 100, message = "Slug must be less than 100 characters")))]
+    pub slug: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(max = 500, message = "Description must be less than 500 characters")))]
+    pub description: String,
+
+    /// Hex color such as `#3B82F6`
+    pub color: String,
+
+    pub is_active: bool,
+}
+
+/// Admin input for creating or editing a tag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct TagInput {
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 50, message = "Name must be between 1 and 50 characters")))]
+    pub name: String,
+
+    /// Derived from the name when blank
+    #[cfg_attr(feature = "ssr", validate(length(max = 100, message = "Slug must be less than 100 characters")))]
+    pub slug: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(max = 500, message = "Description must be less than 500 characters")))]
+    pub description: String,
+}
+
 /// Enhanced Post model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(FromRow))]
@@ -119,7 +154,11 @@ pub struct Post {
     pub meta_title: Option<String>,
     pub meta_description: Option<String>,
     pub reading_time_minutes: Option<i32>,
+    pub word_count: Option<i32>,
+    /// Plaintext opening of the body, shown when the summary is blank
+    pub excerpt: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -149,40 +188,133 @@ pub struct PostRevision {
     pub created_at: DateTime<Utc>,
 }
 
+/// Active draft preview link as listed to the post's author
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewLink {
+    pub id: i64,
+    pub url: String,
+    pub created_by_username: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Input model for creating new posts with validation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(Validate))]
 pub struct CreatePostInput {
-    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100, message = "Slug must be between 1 and 100 characters")))]
+    /// Generated from the title when blank
+    #[serde(default)]
+    #[cfg_attr(feature = "ssr", validate(length(max = 100, message = "Slug must be less than 100 characters")))]
     #[cfg_attr(feature = "ssr", validate(regex(path = "SLUG_REGEX", message = "Slug can only contain lowercase letters, numbers, and hyphens")))]
     pub slug: String,
 
     #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters")))]
     pub title: String,
 
-    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 500, message = "Summary must be between 1 and 500 characters")))]
+    /// An excerpt of the body is shown instead when blank
+    #[serde(default)]
+    #[cfg_attr(feature = "ssr", validate(length(max = 500, message = "Summary must be less than 500 characters")))]
     pub summary: String,
 
     #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100000, message = "Body must be between 1 and 100000 characters")))]
     pub body_markdown: String,
 
     pub published: bool,
+
+    /// When a published post goes live; defaults to now
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+
+    /// When a published post is hidden again
+    #[serde(default)]
+    pub unpublish_at: Option<DateTime<Utc>>,
+
+    /// Tag names; unknown tags are created
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Input model for updating posts with validation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(Validate))]
 pub struct UpdatePostInput {
+    /// A new slug; the old one keeps working as a redirect
+    #[serde(default)]
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100, message = "Slug must be between 1 and 100 characters")))]
+    #[cfg_attr(feature = "ssr", validate(regex(path = "SLUG_REGEX", message = "Slug can only contain lowercase letters, numbers, and hyphens")))]
+    pub slug: Option<String>,
+
     #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters")))]
     pub title: Option<String>,
 
-    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 500, message = "Summary must be between 1 and 500 characters")))]
+    #[cfg_attr(feature = "ssr", validate(length(max = 500, message = "Summary must be less than 500 characters")))]
     pub summary: Option<String>,
 
     #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100000, message = "Body must be between 1 and 100000 characters")))]
     pub body_markdown: Option<String>,
 
     pub published: Option<bool>,
+
+    /// Schedule fields are only applied together with `published`
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+
+    #[serde(default)]
+    pub unpublish_at: Option<DateTime<Utc>>,
+
+    /// Replaces the post's tags when present
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+
+    /// Short note stored with the revision this update creates
+    #[serde(default)]
+    #[cfg_attr(feature = "ssr", validate(length(max = 500, message = "Change summary must be less than 500 characters")))]
+    pub change_summary: Option<String>,
+}
+
+/// A revision as listed in a post's history, without its content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct RevisionInfo {
+    pub id: i64,
+    pub revision_number: i32,
+    pub change_summary: Option<String>,
+    pub created_by_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A post's revision history for its history page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostHistory {
+    pub post_id: i64,
+    pub slug: String,
+    pub title: String,
+    /// Newest first
+    pub revisions: Vec<RevisionInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of text that is unchanged, added or removed between two revisions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffSegment {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Word-level differences between two revisions of a post
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevisionDiff {
+    pub from_revision: i32,
+    pub to_revision: i32,
+    pub title: Vec<DiffSegment>,
+    pub summary: Vec<DiffSegment>,
+    pub body_markdown: Vec<DiffSegment>,
 }
 
 /// Authentication input models
@@ -216,6 +348,10 @@ pub struct RegisterInput {
 
     #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100, message = "Display name must be 1-100 characters")))]
     pub display_name: String,
+
+    /// Required while registration is invite-only
+    #[serde(default)]
+    pub invite_code: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -234,7 +370,7 @@ pub struct UpdateProfileInput {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(Validate))]
 pub struct ChangePasswordInput {
-    #[cfg_attr(feature = "ssr", validate(length(min = 1, message = "Current password is required")))]
+    /// Empty for accounts that just confirmed through a linked login provider
     pub current_password: String,
 
     #[cfg_attr(feature = "ssr", validate(length(min = 8, message = "New password must be at least 8 characters")))]
@@ -244,16 +380,360 @@ pub struct ChangePasswordInput {
     pub confirm_password: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct ChangeEmailInput {
+    #[cfg_attr(feature = "ssr", validate(email(message = "Invalid email address")))]
+    pub new_email: String,
+
+    /// Empty for accounts that just confirmed through a linked login provider
+    pub password: String,
+}
+
+/// The signed-in user's own account details for the settings page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSettings {
+    pub username: String,
+    pub display_name: String,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: String,
+    pub email_verified: bool,
+    /// New address awaiting confirmation, if an email change is in progress
+    pub pending_email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct MagicLinkInput {
+    #[cfg_attr(feature = "ssr", validate(email(message = "Invalid email address")))]
+    pub email: String,
+    pub remember_me: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct ForgotPasswordInput {
+    #[cfg_attr(feature = "ssr", validate(email(message = "Invalid email address")))]
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct ResetPasswordInput {
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, message = "Reset token is required")))]
+    pub token: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(min = 8, message = "New password must be at least 8 characters")))]
+    pub new_password: String,
+
+    #[cfg_attr(feature = "ssr", validate(must_match(other = "new_password", message = "Passwords do not match")))]
+    pub confirm_password: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub user: UserProfile,
     pub token: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Result of the password step of a browser login; the session itself
+/// travels in the cookie
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LoginOutcome {
+    Authenticated,
+    /// The account has two-factor enabled; finish with `challenge_token` and a code
+    TwoFactorRequired {
+        challenge_token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+/// Result of the password step of an API client login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ApiLoginOutcome {
+    Authenticated(AuthResponse),
+    TwoFactorRequired {
+        challenge_token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct TwoFactorLoginInput {
+    /// Empty when the challenge is held in the browser's challenge cookie
+    pub challenge_token: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(min = 6, max = 20, message = "Enter an authenticator or recovery code")))]
+    pub code: String,
+}
+
+/// Pending TOTP enrollment shown to the user once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+    pub required_by_policy: bool,
+}
+
+/// Social login provider offered on the login page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProviderInfo {
+    pub name: String,
+    pub display_name: String,
+}
+
+/// External identity linked to a local account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct LinkedIdentity {
+    pub id: i64,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// Account temporarily locked after repeated failed logins, for the admin view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct LockedAccount {
+    pub user_id: i64,
+    pub username: String,
+    pub email: String,
+    pub failed_login_attempts: i32,
+    pub last_failed_login_at: Option<DateTime<Utc>>,
+    pub locked_until: DateTime<Utc>,
+}
+
+/// Server-side login session; `id` is the hash of the session cookie value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct UserSession {
+    pub id: String,
+    pub user_id: i64,
     pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// A signed-in browser or device, as listed on the account security page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub id: String,
+    pub browser: String,
+    pub os: String,
+    pub device: String,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    pub is_current: bool,
+}
+
+/// Permission granted to a personal access token
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TokenScope {
+    #[serde(rename = "posts:read")]
+    ReadPosts,
+    #[serde(rename = "posts:write")]
+    WritePosts,
+    #[serde(rename = "tags:manage")]
+    ManageTags,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 4] = [
+        TokenScope::ReadPosts,
+        TokenScope::WritePosts,
+        TokenScope::ManageTags,
+        TokenScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadPosts => "posts:read",
+            TokenScope::WritePosts => "posts:write",
+            TokenScope::ManageTags => "tags:manage",
+            TokenScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TokenScope::ReadPosts => "Read posts, including your drafts",
+            TokenScope::WritePosts => "Create, edit and delete your posts",
+            TokenScope::ManageTags => "Create and edit tags",
+            TokenScope::Admin => "Full administrative access",
+        }
+    }
+
+    /// Lowest role allowed to hold the scope
+    pub fn required_role(&self) -> UserRole {
+        match self {
+            TokenScope::ReadPosts => UserRole::User,
+            TokenScope::WritePosts | TokenScope::ManageTags => UserRole::Author,
+            TokenScope::Admin => UserRole::Admin,
+        }
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Personal access token as listed to its owner; the secret itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    pub id: i64,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct CreateAccessTokenInput {
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 100, message = "Token name must be 1-100 characters")))]
+    pub name: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, message = "Select at least one scope")))]
+    pub scopes: Vec<TokenScope>,
+
+    #[cfg_attr(feature = "ssr", validate(range(min = 1, max = 365, message = "Expiry must be between 1 and 365 days")))]
+    pub expires_in_days: Option<i64>,
+}
+
+/// A freshly created token; `token` is shown to the user exactly once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewAccessToken {
+    pub token: String,
+    pub details: PersonalAccessToken,
+}
+
+/// Who may create an account
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RegistrationMode {
+    #[serde(rename = "open")]
+    Open,
+    #[serde(rename = "invite_only")]
+    InviteOnly,
+    #[serde(rename = "closed")]
+    Closed,
+}
+
+impl RegistrationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistrationMode::Open => "open",
+            RegistrationMode::InviteOnly => "invite_only",
+            RegistrationMode::Closed => "closed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(RegistrationMode::Open),
+            "invite_only" => Some(RegistrationMode::InviteOnly),
+            "closed" => Some(RegistrationMode::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// Invite code as listed to admins; the code itself is only shown on creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct InviteCode {
+    pub id: i64,
+    pub code_prefix: String,
+    pub role: UserRole,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct CreateInviteInput {
+    pub role: UserRole,
+
+    #[cfg_attr(feature = "ssr", validate(range(min = 1, max = 1000, message = "Max uses must be between 1 and 1000")))]
+    pub max_uses: Option<i32>,
+
+    #[cfg_attr(feature = "ssr", validate(range(min = 1, max = 365, message = "Expiry must be between 1 and 365 days")))]
+    pub expires_in_days: Option<i64>,
+
+    #[cfg_attr(feature = "ssr", validate(length(max = 200, message = "Note must be less than 200 characters")))]
+    pub note: Option<String>,
+}
+
+/// A freshly created invite; `code` and `url` are shown to the admin once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewInvite {
+    pub code: String,
+    pub url: String,
+    pub invite: InviteCode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(Validate))]
+pub struct StartImpersonationInput {
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, message = "Username is required")))]
+    pub username: String,
+
+    #[cfg_attr(feature = "ssr", validate(length(min = 1, max = 500, message = "Give a reason of up to 500 characters")))]
+    pub reason: String,
+}
+
+/// Shown in the banner while an admin is viewing the site as another user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpersonationStatus {
+    pub username: String,
+    pub display_name: String,
+    pub impersonator_username: String,
+}
+
+/// One entry of the staff audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub actor_username: Option<String>,
+    pub action: String,
+    pub target_username: Option<String>,
+    pub details: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[cfg(feature = "ssr")]
 lazy_static::lazy_static! {
-    static ref SLUG_REGEX: regex::Regex = regex::Regex::new(r"^[a-z0-9\-]+$").unwrap();
+    static ref SLUG_REGEX: regex::Regex = regex::Regex::new(r"^[a-z0-9\-]*$").unwrap();
     static ref USERNAME_REGEX: regex::Regex = regex::Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
 }
 
@@ -263,13 +743,32 @@ impl Post {
         self.body_html = crate::markdown::markdown_to_html(&self.body_markdown);
     }
 
-    /// Check if post is published
+    /// Check if post is live: its publication time has passed and it hasn't expired
     pub fn is_published(&self) -> bool {
-        self.published_at.is_some()
+        let now = Utc::now();
+        self.published_at.is_some_and(|at| at <= now) && self.unpublish_at.map_or(true, |at| at > now)
+    }
+
+    /// Check if post is set to go live in the future
+    pub fn is_scheduled(&self) -> bool {
+        self.published_at.is_some_and(|at| at > Utc::now())
     }
 
     /// Get formatted publication date
     pub fn formatted_date(&self) -> Option<String> {
         self.published_at.map(|dt| dt.format("%B %d, %Y").to_string())
     }
+
+    /// The author's summary, or the generated excerpt when there is none
+    pub fn description(&self) -> String {
+        match self.summary.trim() {
+            "" => self.excerpt.clone().unwrap_or_default(),
+            summary => summary.to_string(),
+        }
+    }
+
+    /// Reading time as shown to readers, e.g. "4 min read"
+    pub fn reading_time(&self) -> Option<String> {
+        self.reading_time_minutes.map(|minutes| format!("{} min read", minutes))
+    }
 }
@@ -0,0 +1,1027 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::AuthService,
+    config::{OAuthConfig, OAuthProviderConfig, OAuthProviderKind},
+    db::Db,
+    error::{log_error, AppError, AppResult},
+    models::{LinkedIdentity, OAuthProviderInfo, RegistrationMode},
+    security::{generate_secure_token, hash_token},
+    session::{
+        cookie_value, SessionService, SESSION_COOKIE_NAME, TWO_FACTOR_COOKIE_NAME, TWO_FACTOR_COOKIE_PATH,
+    },
+    settings::get_registration_mode,
+    two_factor::TwoFactorService,
+};
+#[cfg(feature = "ssr")]
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Redirect, Response},
+    Extension,
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use serde::Deserialize;
+#[cfg(feature = "ssr")]
+use std::{collections::HashMap, sync::Arc};
+#[cfg(feature = "ssr")]
+use tokio::sync::RwLock;
+
+#[cfg(feature = "ssr")]
+const STATE_TTL_MINUTES: i64 = 10;
+/// Binds a login's `state` to the browser that started it
+#[cfg(feature = "ssr")]
+const STATE_COOKIE_NAME: &str = "oauth_state";
+#[cfg(feature = "ssr")]
+const STATE_COOKIE_PATH: &str = "/auth/oauth";
+#[cfg(feature = "ssr")]
+const GITHUB_USER_URL: &str = "https://api.github.com/user";
+
+/// Resolved endpoints of a provider
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+struct ProviderEndpoints {
+    authorization_url: String,
+    token_url: String,
+    userinfo_url: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct DiscoveryDocument {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: serde_json::Value,
+    exp: i64,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct OidcUserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct GitHubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+/// Provider-independent view of the signed-in external account
+#[cfg(feature = "ssr")]
+#[derive(Debug)]
+struct ExternalProfile {
+    subject: String,
+    email: Option<String>,
+    email_verified: bool,
+    name: Option<String>,
+    username_hint: Option<String>,
+}
+
+/// What the callback did
+#[cfg(feature = "ssr")]
+pub enum OAuthOutcome {
+    SignedIn { user_id: i64 },
+    Linked,
+    /// The session's owner signed in again through one of their linked providers
+    Reauthenticated,
+    TwoFactorRequired { challenge_token: String, expires_at: DateTime<Utc> },
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+pub struct StartParams {
+    link: Option<bool>,
+    reauth: Option<bool>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+/// OAuth2 authorization-code + PKCE login against configured providers
+#[cfg(feature = "ssr")]
+pub struct OAuthService {
+    config: OAuthConfig,
+    http: reqwest::Client,
+    endpoints: RwLock<HashMap<String, ProviderEndpoints>>,
+}
+
+#[cfg(feature = "ssr")]
+impl OAuthService {
+    pub fn new(config: OAuthConfig) -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent("Taleji")
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| AppError::Config(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            http,
+            endpoints: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn providers(&self) -> Vec<OAuthProviderInfo> {
+        self.config
+            .providers
+            .iter()
+            .map(|p| OAuthProviderInfo {
+                name: p.name.clone(),
+                display_name: p.display_name.clone(),
+            })
+            .collect()
+    }
+
+    fn provider(&self, name: &str) -> AppResult<&OAuthProviderConfig> {
+        self.config
+            .providers
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown login provider '{}'", name)))
+    }
+
+    fn redirect_uri(&self, provider: &OAuthProviderConfig) -> String {
+        format!(
+            "{}/auth/oauth/{}/callback",
+            self.config.redirect_base_url.trim_end_matches('/'),
+            provider.name
+        )
+    }
+
+    /// Explicit endpoints from config, otherwise OIDC discovery (cached)
+    async fn endpoints(&self, provider: &OAuthProviderConfig) -> AppResult<ProviderEndpoints> {
+        if let (Some(authorization_url), Some(token_url)) =
+            (&provider.authorization_url, &provider.token_url)
+        {
+            return Ok(ProviderEndpoints {
+                authorization_url: authorization_url.clone(),
+                token_url: token_url.clone(),
+                userinfo_url: provider.userinfo_url.clone(),
+            });
+        }
+
+        if let Some(endpoints) = self.endpoints.read().await.get(&provider.name) {
+            return Ok(endpoints.clone());
+        }
+
+        let issuer = provider
+            .issuer
+            .as_deref()
+            .ok_or_else(|| AppError::Config(format!("No issuer for provider '{}'", provider.name)))?;
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+
+        let document: DiscoveryDocument = self
+            .http
+            .get(&discovery_url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        let endpoints = ProviderEndpoints {
+            authorization_url: document.authorization_endpoint,
+            token_url: document.token_endpoint,
+            userinfo_url: provider.userinfo_url.clone().or(document.userinfo_endpoint),
+        };
+        self.endpoints
+            .write()
+            .await
+            .insert(provider.name.clone(), endpoints.clone());
+
+        Ok(endpoints)
+    }
+
+    /// Start a login (or, with `link_user_id`, an account link, or with
+    /// `reauth_session_id`, a re-authentication of that session) and return
+    /// the provider URL to redirect to, along with the `state` for the
+    /// browser's state cookie
+    pub async fn authorization_url(
+        &self,
+        db: &Db,
+        provider_name: &str,
+        link_user_id: Option<i64>,
+        reauth_session_id: Option<&str>,
+    ) -> AppResult<(String, String)> {
+        let provider = self.provider(provider_name)?;
+        let endpoints = self.endpoints(provider).await?;
+
+        let state = generate_secure_token(32);
+        let code_verifier = generate_secure_token(32);
+        let nonce = generate_secure_token(16);
+
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_states (id, provider, code_verifier, nonce, link_user_id, reauth_session_id, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(hash_token(&state))
+        .bind(&provider.name)
+        .bind(&code_verifier)
+        .bind(&nonce)
+        .bind(link_user_id)
+        .bind(reauth_session_id)
+        .bind(Utc::now() + Duration::minutes(STATE_TTL_MINUTES))
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let scope = provider.scopes.join(" ");
+        let redirect_uri = self.redirect_uri(provider);
+        let code_challenge = pkce_challenge(&code_verifier);
+
+        let mut params = vec![
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", scope.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ];
+        if provider.kind == OAuthProviderKind::Oidc {
+            params.push(("nonce", nonce.as_str()));
+        }
+
+        let url = reqwest::Url::parse_with_params(&endpoints.authorization_url, &params)
+            .map_err(|e| AppError::Config(format!("Invalid authorization URL: {}", e)))?;
+        Ok((url.to_string(), state))
+    }
+
+    /// Handle the provider callback: verify state, exchange the code and
+    /// sign in, link or create the matching local account
+    pub async fn complete(&self, db: &Db, provider_name: &str, code: &str, state: &str) -> AppResult<OAuthOutcome> {
+        let provider = self.provider(provider_name)?;
+
+        let pending = sqlx::query_as::<_, (String, String, Option<i64>, Option<String>)>(
+            r#"
+            DELETE FROM oauth_states
+            WHERE id = $1 AND provider = $2 AND expires_at > NOW()
+            RETURNING code_verifier, nonce, link_user_id, reauth_session_id
+            "#,
+        )
+        .bind(hash_token(state))
+        .bind(&provider.name)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let (code_verifier, nonce, link_user_id, reauth_session_id) = pending.ok_or_else(|| {
+            AppError::Validation("Login request expired, please try again".to_string())
+        })?;
+
+        let endpoints = self.endpoints(provider).await?;
+        let tokens = self.exchange_code(provider, &endpoints, code, &code_verifier).await?;
+        let profile = match provider.kind {
+            OAuthProviderKind::GitHub => self.github_profile(&endpoints, &tokens.access_token).await?,
+            OAuthProviderKind::Oidc => self.oidc_profile(provider, &endpoints, &tokens, &nonce).await?,
+        };
+
+        let identity = sqlx::query_as::<_, (i64,)>(
+            r#"
+            UPDATE user_identities SET last_login_at = NOW(), email = COALESCE($3, email)
+            WHERE provider = $1 AND subject = $2
+            RETURNING user_id
+            "#,
+        )
+        .bind(&provider.name)
+        .bind(&profile.subject)
+        .bind(&profile.email)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if let Some(link_user_id) = link_user_id {
+            match identity {
+                Some((owner,)) if owner != link_user_id => {
+                    return Err(AppError::Validation(
+                        "That account is already linked to another user".to_string(),
+                    ));
+                }
+                Some(_) => {}
+                None => self.link_identity(db, link_user_id, provider, &profile).await?,
+            }
+            tracing::info!(provider = %provider.name, "Linked external identity to user {}", link_user_id);
+            return Ok(OAuthOutcome::Linked);
+        }
+
+        if let Some(session_id) = reauth_session_id {
+            let confirmed = match identity {
+                Some((owner,)) => SessionService::new().mark_reauthenticated(db, &session_id, owner).await?,
+                None => false,
+            };
+            if !confirmed {
+                return Err(AppError::Validation(
+                    "That account is not linked to the signed-in user".to_string(),
+                ));
+            }
+            return Ok(OAuthOutcome::Reauthenticated);
+        }
+
+        let user_id = match identity {
+            Some((user_id,)) => user_id,
+            None => self.create_user(db, provider, &profile).await?,
+        };
+
+        let auth_service = AuthService::new();
+        let user = auth_service.get_user_by_id(db, user_id).await?;
+
+        // A provider login must not bypass the account's own second factor
+        let two_factor = TwoFactorService::new();
+        if two_factor.is_enabled(db, user.id).await? {
+            let (challenge_token, expires_at) = two_factor.create_challenge(db, user.id, false).await?;
+            return Ok(OAuthOutcome::TwoFactorRequired { challenge_token, expires_at });
+        }
+
+        tracing::info!(provider = %provider.name, "User logged in via OAuth: {}", user.username);
+        Ok(OAuthOutcome::SignedIn { user_id: user.id })
+    }
+
+    async fn exchange_code(
+        &self,
+        provider: &OAuthProviderConfig,
+        endpoints: &ProviderEndpoints,
+        code: &str,
+        code_verifier: &str,
+    ) -> AppResult<TokenResponse> {
+        let redirect_uri = self.redirect_uri(provider);
+
+        self.http
+            .post(&endpoints.token_url)
+            .header(header::ACCEPT, "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("client_id", provider.client_id.as_str()),
+                ("client_secret", provider.client_secret.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)
+    }
+
+    /// Read the ID token claims. The token came straight from the token
+    /// endpoint over TLS, so per OIDC Core 3.1.3.7 we check issuer, audience,
+    /// expiry and nonce instead of the signature.
+    async fn oidc_profile(
+        &self,
+        provider: &OAuthProviderConfig,
+        endpoints: &ProviderEndpoints,
+        tokens: &TokenResponse,
+        nonce: &str,
+    ) -> AppResult<ExternalProfile> {
+        if let Some(id_token) = &tokens.id_token {
+            let claims: IdTokenClaims = decode_jwt_payload(id_token)?;
+
+            let audience_ok = match &claims.aud {
+                serde_json::Value::String(aud) => *aud == provider.client_id,
+                serde_json::Value::Array(auds) => auds.iter().any(|a| a == provider.client_id.as_str()),
+                _ => false,
+            };
+            let issuer_ok = provider
+                .issuer
+                .as_deref()
+                .map_or(true, |iss| iss.trim_end_matches('/') == claims.iss.trim_end_matches('/'));
+
+            if !audience_ok || !issuer_ok || claims.exp < Utc::now().timestamp() || claims.nonce.as_deref() != Some(nonce) {
+                tracing::warn!(provider = %provider.name, "Rejected ID token");
+                return Err(AppError::Unauthorized);
+            }
+
+            return Ok(ExternalProfile {
+                subject: claims.sub,
+                email: claims.email,
+                email_verified: claims.email_verified,
+                name: claims.name,
+                username_hint: claims.preferred_username,
+            });
+        }
+
+        let userinfo_url = endpoints.userinfo_url.as_deref().ok_or_else(|| {
+            AppError::Config(format!("Provider '{}' returned no ID token and has no userinfo endpoint", provider.name))
+        })?;
+
+        let info: OidcUserInfo = self
+            .http
+            .get(userinfo_url)
+            .bearer_auth(&tokens.access_token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        Ok(ExternalProfile {
+            subject: info.sub,
+            email: info.email,
+            email_verified: info.email_verified,
+            name: info.name,
+            username_hint: info.preferred_username,
+        })
+    }
+
+    /// Read the GitHub user from the configured user API (GitHub Enterprise
+    /// or a test double), defaulting to api.github.com
+    async fn github_profile(&self, endpoints: &ProviderEndpoints, access_token: &str) -> AppResult<ExternalProfile> {
+        let user_url = endpoints
+            .userinfo_url
+            .as_deref()
+            .unwrap_or(GITHUB_USER_URL)
+            .trim_end_matches('/');
+
+        let user: GitHubUser = self
+            .http
+            .get(user_url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        let emails: Vec<GitHubEmail> = self
+            .http
+            .get(format!("{}/emails", user_url))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        let primary = emails.into_iter().find(|e| e.primary && e.verified);
+
+        Ok(ExternalProfile {
+            subject: user.id.to_string(),
+            email_verified: primary.is_some(),
+            email: primary.map(|e| e.email),
+            name: user.name,
+            username_hint: Some(user.login),
+        })
+    }
+
+    async fn link_identity(&self, db: &Db, user_id: i64, provider: &OAuthProviderConfig, profile: &ExternalProfile) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            "#,
+        )
+        .bind(user_id)
+        .bind(&provider.name)
+        .bind(&profile.subject)
+        .bind(&profile.email)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    /// Create an account for a first-time provider login. An existing account
+    /// with the same email is never linked here; its owner has to sign in and
+    /// link the provider from the account page.
+    async fn create_user(&self, db: &Db, provider: &OAuthProviderConfig, profile: &ExternalProfile) -> AppResult<i64> {
+        if let Some(email) = profile.email.as_deref() {
+            let taken = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(email) = LOWER($1))",
+            )
+            .bind(email)
+            .fetch_one(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+            if taken {
+                return Err(AppError::Validation(
+                    "An account with this email already exists; sign in and link this provider from your account page".to_string(),
+                ));
+            }
+        }
+
+        // Social login can't bypass invite-only or closed registration
+        if get_registration_mode(db).await? != RegistrationMode::Open {
+            return Err(AppError::Forbidden(
+                "New accounts need an invitation; register first, then link this provider".to_string(),
+            ));
+        }
+
+        let email = profile.email.clone().ok_or_else(|| {
+            AppError::Validation("Your account doesn't share an email address with us".to_string())
+        })?;
+
+        let base = profile
+            .username_hint
+            .as_deref()
+            .or_else(|| email.split('@').next())
+            .unwrap_or("user");
+        let username = self.available_username(db, base).await?;
+        let display_name = profile.name.clone().unwrap_or_else(|| username.clone());
+
+        // Provider accounts get an unguessable password; a reset email sets a real one
+        let password_hash = AuthService::new().hash_password(&generate_secure_token(32))?;
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let (user_id,) = sqlx::query_as::<_, (i64,)>(
+            r#"
+            INSERT INTO users (username, email, password_hash, display_name, role, email_verified)
+            VALUES ($1, $2, $3, $4, 'user', $5)
+            RETURNING id
+            "#,
+        )
+        .bind(&username)
+        .bind(&email)
+        .bind(&password_hash)
+        .bind(&display_name)
+        .bind(profile.email_verified)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                AppError::Validation("An account with this email already exists; sign in and link it from your account page".to_string())
+            }
+            _ => AppError::Database(e),
+        })?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            "#,
+        )
+        .bind(user_id)
+        .bind(&provider.name)
+        .bind(&profile.subject)
+        .bind(&profile.email)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(provider = %provider.name, "User registered via OAuth: {}", username);
+        Ok(user_id)
+    }
+
+    /// Turn a provider username into a free local one
+    async fn available_username(&self, db: &Db, base: &str) -> AppResult<String> {
+        let mut base: String = base
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .take(40)
+            .collect();
+        while base.len() < 3 {
+            base.push('_');
+        }
+
+        for attempt in 0..20 {
+            let candidate = if attempt == 0 {
+                base.clone()
+            } else {
+                format!("{}_{}", base, attempt + 1)
+            };
+            let taken = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)")
+                .bind(&candidate)
+                .fetch_one(&**db)
+                .await
+                .map_err(AppError::Database)?;
+            if !taken {
+                return Ok(candidate);
+            }
+        }
+
+        Ok(format!("{}_{}", base, rand::random::<u32>()))
+    }
+
+    pub async fn linked_identities(&self, db: &Db, user_id: i64) -> AppResult<Vec<LinkedIdentity>> {
+        sqlx::query_as::<_, LinkedIdentity>(
+            r#"
+            SELECT id, provider, email, created_at, last_login_at
+            FROM user_identities WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    pub async fn unlink_identity(&self, db: &Db, user_id: i64, identity_id: i64) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM user_identities WHERE id = $1 AND user_id = $2")
+            .bind(identity_id)
+            .bind(user_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Linked account not found".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn http_error(e: reqwest::Error) -> AppError {
+    tracing::error!("OAuth provider request failed: {}", e);
+    AppError::Internal("Login provider request failed".to_string())
+}
+
+#[cfg(feature = "ssr")]
+fn pkce_challenge(code_verifier: &str) -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use sha2::{Digest, Sha256};
+
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[cfg(feature = "ssr")]
+fn decode_jwt_payload<T: serde::de::DeserializeOwned>(token: &str) -> AppResult<T> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| {
+            tracing::warn!("Malformed ID token");
+            AppError::Unauthorized
+        })
+}
+
+/// `GET /auth/oauth/:provider` — redirect to the provider's consent screen
+#[cfg(feature = "ssr")]
+pub async fn start_handler(
+    Path(provider): Path<String>,
+    Query(params): Query<StartParams>,
+    Extension(db): Extension<Db>,
+    Extension(oauth): Extension<Arc<OAuthService>>,
+    headers: HeaderMap,
+) -> Response {
+    // Linking and re-authenticating require the user's own browser session,
+    // not an impersonation
+    let mut link_user_id = None;
+    let mut reauth_session_id = None;
+    let link = params.link.unwrap_or(false);
+    let reauth = params.reauth.unwrap_or(false);
+    if link || reauth {
+        let token = cookie_value(&headers, SESSION_COOKIE_NAME);
+        let user = match &token {
+            Some(token) => SessionService::new().resolve_session(&db, token).await.ok().flatten(),
+            None => None,
+        };
+        let user = user.and_then(|(user, impersonator_id)| impersonator_id.is_none().then_some(user));
+        match (user, token) {
+            (Some(user), Some(token)) => {
+                if link {
+                    link_user_id = Some(user.id);
+                } else {
+                    reauth_session_id = Some(hash_token(&token));
+                }
+            }
+            _ => return Redirect::to("/login").into_response(),
+        }
+    }
+
+    match oauth.authorization_url(&db, &provider, link_user_id, reauth_session_id.as_deref()).await {
+        Ok((url, state)) => {
+            let cookie = SessionService::new().flow_cookie(
+                STATE_COOKIE_NAME,
+                &state,
+                STATE_COOKIE_PATH,
+                Duration::minutes(STATE_TTL_MINUTES),
+            );
+            ([(header::SET_COOKIE, cookie)], Redirect::to(&url)).into_response()
+        }
+        Err(e) => {
+            log_error(&e, "Failed to start OAuth login");
+            Redirect::to("/login?error=oauth").into_response()
+        }
+    }
+}
+
+/// `GET /auth/oauth/:provider/callback` — finish the login and set the session cookie
+#[cfg(feature = "ssr")]
+pub async fn callback_handler(
+    Path(provider): Path<String>,
+    Query(params): Query<CallbackParams>,
+    Extension(db): Extension<Db>,
+    Extension(oauth): Extension<Arc<OAuthService>>,
+    headers: HeaderMap,
+) -> Response {
+    let sessions = SessionService::new();
+    let clear_state = sessions.flow_cookie(STATE_COOKIE_NAME, "", STATE_COOKIE_PATH, Duration::zero());
+
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        tracing::warn!(provider = %provider, error = ?params.error, "OAuth login cancelled or failed");
+        return ([(header::SET_COOKIE, clear_state)], Redirect::to("/login?error=oauth")).into_response();
+    };
+
+    // A callback started in another browser (login CSRF) has no matching cookie
+    if cookie_value(&headers, STATE_COOKIE_NAME).as_deref() != Some(state.as_str()) {
+        tracing::warn!(provider = %provider, "OAuth state does not match this browser");
+        return ([(header::SET_COOKIE, clear_state)], Redirect::to("/login?error=oauth")).into_response();
+    }
+
+    let outcome = oauth.complete(&db, &provider, &code, &state).await;
+
+    let mut response = match outcome {
+        Ok(OAuthOutcome::SignedIn { user_id }) => {
+            match sessions.create_session(&db, user_id, false, &headers).await {
+                Ok((token, _)) => (
+                    [(header::SET_COOKIE, sessions.session_cookie(&token, None))],
+                    Redirect::to("/"),
+                )
+                    .into_response(),
+                Err(e) => {
+                    log_error(&e, "Failed to start session after OAuth login");
+                    Redirect::to("/login?error=oauth").into_response()
+                }
+            }
+        }
+        Ok(OAuthOutcome::Linked) => Redirect::to("/account/security").into_response(),
+        Ok(OAuthOutcome::Reauthenticated) => {
+            Redirect::to("/account/settings?reauthenticated=true").into_response()
+        }
+        // The challenge stays out of the URL, where it would end up in
+        // history and referrers
+        Ok(OAuthOutcome::TwoFactorRequired { challenge_token, expires_at }) => {
+            let cookie = sessions.flow_cookie(
+                TWO_FACTOR_COOKIE_NAME,
+                &challenge_token,
+                TWO_FACTOR_COOKIE_PATH,
+                expires_at - Utc::now(),
+            );
+            ([(header::SET_COOKIE, cookie)], Redirect::to("/login?two_factor=pending")).into_response()
+        }
+        Err(e) => {
+            log_error(&e, "OAuth callback failed");
+            Redirect::to("/login?error=oauth").into_response()
+        }
+    };
+
+    // Appended, so a session cookie set above is kept
+    if let Ok(value) = HeaderValue::from_str(&clear_state) {
+        response.headers_mut().append(header::SET_COOKIE, value);
+    }
+    response
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+    use serde_json::json;
+
+    /// Serve a stand-in for a provider's user API on a random local port
+    async fn mock_issuer(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn provider(name: &str, kind: OAuthProviderKind, base: &str) -> OAuthProviderConfig {
+        OAuthProviderConfig {
+            name: name.to_string(),
+            display_name: name.to_string(),
+            kind,
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            issuer: Some(base.to_string()),
+            authorization_url: None,
+            token_url: None,
+            userinfo_url: None,
+            scopes: vec![],
+        }
+    }
+
+    fn service(provider: &OAuthProviderConfig) -> OAuthService {
+        OAuthService::new(OAuthConfig {
+            redirect_base_url: "http://localhost:3000".to_string(),
+            providers: vec![provider.clone()],
+        })
+        .unwrap()
+    }
+
+    /// An ID token as a token endpoint would return it; the signature is
+    /// never checked, see `oidc_profile`
+    fn id_token(claims: serde_json::Value) -> TokenResponse {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "RS256", "typ": "JWT" }).to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        TokenResponse {
+            access_token: "access-token".to_string(),
+            id_token: Some(format!("{}.{}.signature", header, payload)),
+        }
+    }
+
+    fn claims(issuer: &str) -> serde_json::Value {
+        json!({
+            "iss": issuer,
+            "sub": "abc",
+            "aud": "client",
+            "exp": Utc::now().timestamp() + 300,
+            "nonce": "nonce",
+            "email": "user@example.com",
+            "email_verified": true,
+        })
+    }
+
+    /// Profile from an ID token, without any network access
+    async fn profile_from(claims: serde_json::Value) -> AppResult<ExternalProfile> {
+        let oidc = provider("sso", OAuthProviderKind::Oidc, "https://issuer.example.com");
+        let endpoints = ProviderEndpoints {
+            authorization_url: "https://issuer.example.com/authorize".to_string(),
+            token_url: "https://issuer.example.com/token".to_string(),
+            userinfo_url: None,
+        };
+        service(&oidc).oidc_profile(&oidc, &endpoints, &id_token(claims), "nonce").await
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_accepts_a_valid_id_token() {
+        let profile = profile_from(claims("https://issuer.example.com/")).await.unwrap();
+        assert_eq!(profile.subject, "abc");
+        assert_eq!(profile.email.as_deref(), Some("user@example.com"));
+        assert!(profile.email_verified);
+
+        let mut listed = claims("https://issuer.example.com");
+        listed["aud"] = json!(["other", "client"]);
+        assert!(profile_from(listed).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_rejects_a_wrong_nonce() {
+        let mut claims = claims("https://issuer.example.com");
+        claims["nonce"] = json!("replayed");
+        assert!(matches!(profile_from(claims.clone()).await, Err(AppError::Unauthorized)));
+
+        claims.as_object_mut().unwrap().remove("nonce");
+        assert!(matches!(profile_from(claims).await, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_rejects_a_wrong_audience() {
+        let mut claims = claims("https://issuer.example.com");
+        claims["aud"] = json!("another-client");
+        assert!(matches!(profile_from(claims.clone()).await, Err(AppError::Unauthorized)));
+
+        claims["aud"] = json!(["another-client"]);
+        assert!(matches!(profile_from(claims).await, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_rejects_an_expired_token() {
+        let mut claims = claims("https://issuer.example.com");
+        claims["exp"] = json!(Utc::now().timestamp() - 60);
+        assert!(matches!(profile_from(claims).await, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_rejects_a_wrong_issuer() {
+        let claims = claims("https://evil.example.com");
+        assert!(matches!(profile_from(claims).await, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_rejects_a_malformed_token() {
+        let oidc = provider("sso", OAuthProviderKind::Oidc, "https://issuer.example.com");
+        let endpoints = ProviderEndpoints {
+            authorization_url: String::new(),
+            token_url: String::new(),
+            userinfo_url: None,
+        };
+        let tokens = TokenResponse { access_token: String::new(), id_token: Some("not-a-jwt".to_string()) };
+        let result = service(&oidc).oidc_profile(&oidc, &endpoints, &tokens, "nonce").await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn github_profile_uses_configured_user_api() {
+        let base = mock_issuer(
+            Router::new()
+                .route("/api/v3/user", get(|headers: HeaderMap| async move {
+                    assert_eq!(headers[header::AUTHORIZATION], "Bearer access-token");
+                    Json(json!({ "id": 42, "login": "octocat", "name": "The Octocat" }))
+                }))
+                .route("/api/v3/user/emails", get(|| async {
+                    Json(json!([
+                        { "email": "old@example.com", "primary": false, "verified": true },
+                        { "email": "octo@example.com", "primary": true, "verified": true },
+                    ]))
+                })),
+        )
+        .await;
+
+        let mut github = provider("github", OAuthProviderKind::GitHub, &base);
+        github.authorization_url = Some(format!("{}/login/oauth/authorize", base));
+        github.token_url = Some(format!("{}/login/oauth/access_token", base));
+        github.userinfo_url = Some(format!("{}/api/v3/user", base));
+        let oauth = service(&github);
+
+        let endpoints = oauth.endpoints(&github).await.unwrap();
+        let profile = oauth.github_profile(&endpoints, "access-token").await.unwrap();
+
+        assert_eq!(profile.subject, "42");
+        assert_eq!(profile.email.as_deref(), Some("octo@example.com"));
+        assert!(profile.email_verified);
+        assert_eq!(profile.username_hint.as_deref(), Some("octocat"));
+    }
+
+    #[tokio::test]
+    async fn oidc_profile_falls_back_to_discovered_userinfo() {
+        let userinfo = mock_issuer(Router::new().route("/userinfo", get(|| async {
+            Json(json!({ "sub": "abc", "email": "user@example.com", "email_verified": true, "name": "User" }))
+        })))
+        .await;
+        let discovery = json!({
+            "authorization_endpoint": format!("{}/authorize", userinfo),
+            "token_endpoint": format!("{}/token", userinfo),
+            "userinfo_endpoint": format!("{}/userinfo", userinfo),
+        });
+        let issuer = mock_issuer(Router::new().route(
+            "/.well-known/openid-configuration",
+            get(move || {
+                let discovery = discovery.clone();
+                async move { Json(discovery) }
+            }),
+        ))
+        .await;
+
+        let oidc = provider("sso", OAuthProviderKind::Oidc, &issuer);
+        let oauth = service(&oidc);
+
+        let endpoints = oauth.endpoints(&oidc).await.unwrap();
+        let tokens = TokenResponse { access_token: "access-token".to_string(), id_token: None };
+        let profile = oauth.oidc_profile(&oidc, &endpoints, &tokens, "nonce").await.unwrap();
+
+        assert_eq!(profile.subject, "abc");
+        assert_eq!(profile.email.as_deref(), Some("user@example.com"));
+        assert!(profile.email_verified);
+    }
+}
@@ -0,0 +1,1149 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::*;
+use crate::models::{
+    AccountSettings, ActiveSession, ChangeEmailInput, ChangePasswordInput, CreateAccessTokenInput,
+    LinkedIdentity, LockedAccount, NewAccessToken, PersonalAccessToken, TokenScope, TotpEnrollment,
+    TwoFactorStatus, UpdateProfileInput, UserProfile, UserRole,
+};
+use crate::pages::auth::{get_current_user, get_oauth_providers};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    access_tokens::AccessTokenService,
+    db::Db,
+    auth::AuthService,
+    error::{AppError, log_error, server_error, validation::validate_input},
+    guards::{ensure_role, require_account_owner, require_role, require_user},
+    lockout::LoginThrottle,
+    mailer::SharedMailer,
+    oauth::OAuthService,
+    privacy::PrivacyService,
+    security::client_ip,
+    session::{current_session_id, describe_session, end_session, request_headers, start_session, SessionService},
+    settings::{set_setting, REQUIRE_TWO_FACTOR_FOR_PRIVILEGED},
+    two_factor::TwoFactorService,
+};
+
+#[server(GetAccountSettings, "/api")]
+pub async fn get_account_settings() -> Result<AccountSettings, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_user().await.map_err(server_error)?;
+
+    let pending_email = AuthService::new().pending_email(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to load pending email change");
+        server_error(e)
+    })?;
+
+    Ok(AccountSettings {
+        username: user.username,
+        display_name: user.display_name,
+        bio: user.bio,
+        avatar_url: user.avatar_url,
+        email: user.email,
+        email_verified: user.email_verified,
+        pending_email,
+    })
+}
+
+#[server(UpdateProfile, "/api")]
+pub async fn update_profile(input: UpdateProfileInput) -> Result<UserProfile, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    // An empty avatar field clears the picture rather than failing URL validation
+    let input = UpdateProfileInput {
+        avatar_url: input.avatar_url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty()),
+        ..input
+    };
+    validate_input(&input).map_err(server_error)?;
+
+    let user = AuthService::new().update_profile(&db, user.id, input).await.map_err(|e| {
+        log_error(&e, "Failed to update profile");
+        server_error(e)
+    })?;
+
+    Ok(UserProfile::from(user))
+}
+
+#[server(ChangePassword, "/api")]
+pub async fn change_password(input: ChangePasswordInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    let session_id = current_session_id();
+    AuthService::new().change_password(&db, &user, input, session_id.as_deref()).await.map_err(|e| {
+        log_error(&e, "Failed to change password");
+        server_error(e)
+    })?;
+
+    // Every other session was just ended; keep this browser signed in
+    start_session(&db, user.id, false).await.map_err(server_error)?;
+    Ok(())
+}
+
+#[server(ChangeEmail, "/api")]
+pub async fn change_email(input: ChangeEmailInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    let session_id = current_session_id();
+    AuthService::new()
+        .request_email_change(&db, mailer.as_ref(), &user, input, session_id.as_deref())
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to start email change");
+            server_error(e)
+        })
+}
+
+/// Deactivate the current account and schedule it for deletion; returns the purge date
+#[server(DeleteAccount, "/api")]
+pub async fn delete_account(password: String) -> Result<String, ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    let session_id = current_session_id();
+    let purge_after = PrivacyService::new()
+        .request_deletion(&db, mailer.as_ref(), &user, &password, session_id.as_deref())
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to delete account");
+            server_error(e)
+        })?;
+
+    end_session(&db).await.map_err(server_error)?;
+    Ok(purge_after.format("%Y-%m-%d").to_string())
+}
+
+#[server(GetTwoFactorStatus, "/api")]
+pub async fn get_two_factor_status() -> Result<TwoFactorStatus, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_user().await.map_err(server_error)?;
+
+    TwoFactorService::new().status(&db, &user).await.map_err(|e| {
+        log_error(&e, "Failed to load two-factor status");
+        server_error(e)
+    })
+}
+
+#[server(BeginTotpEnrollment, "/api")]
+pub async fn begin_totp_enrollment() -> Result<TotpEnrollment, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    TwoFactorService::new().begin_enrollment(&db, &user).await.map_err(|e| {
+        log_error(&e, "Failed to start two-factor enrollment");
+        server_error(e)
+    })
+}
+
+#[server(ConfirmTotpEnrollment, "/api")]
+pub async fn confirm_totp_enrollment(code: String) -> Result<Vec<String>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    TwoFactorService::new().confirm_enrollment(&db, &user, &code).await.map_err(|e| {
+        log_error(&e, "Failed to confirm two-factor enrollment");
+        server_error(e)
+    })
+}
+
+#[server(RegenerateRecoveryCodes, "/api")]
+pub async fn regenerate_recovery_codes(code: String) -> Result<Vec<String>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let two_factor = TwoFactorService::new();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    if !two_factor.verify_code(&db, &user, &code).await.map_err(server_error)? {
+        return Err(server_error(AppError::Validation("Invalid verification code".to_string())));
+    }
+
+    two_factor.regenerate_recovery_codes(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to regenerate recovery codes");
+        server_error(e)
+    })
+}
+
+#[server(DisableTwoFactor, "/api")]
+pub async fn disable_two_factor(password: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+    let two_factor = TwoFactorService::new();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    let session_id = current_session_id();
+    auth_service
+        .confirm_identity(&db, &user, &password, session_id.as_deref())
+        .await
+        .map_err(server_error)?;
+    if two_factor.is_required_for(&db, &user.role).await.map_err(server_error)? {
+        return Err(server_error(AppError::Forbidden(
+            "Two-factor authentication is required for your role".to_string(),
+        )));
+    }
+
+    two_factor.disable(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to disable two-factor");
+        server_error(e)
+    })
+}
+
+/// Admin policy: require two-factor for authors and admins
+#[server(SetTwoFactorPolicy, "/api")]
+pub async fn set_two_factor_policy(required: bool) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    // Not `require_role`: an admin without two-factor must still be able to
+    // switch the policy off
+    let user = require_user().await.map_err(server_error)?;
+    ensure_role(&user, &UserRole::Admin).map_err(server_error)?;
+    if required && !TwoFactorService::new().is_enabled(&db, user.id).await.map_err(server_error)? {
+        return Err(server_error(AppError::Validation(
+            "Enable two-factor authentication on your own account before requiring it".to_string(),
+        )));
+    }
+
+    set_setting(&db, REQUIRE_TWO_FACTOR_FOR_PRIVILEGED, &required.to_string(), user.id)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to update two-factor policy");
+            server_error(e)
+        })
+}
+
+#[server(GetLinkedIdentities, "/api")]
+pub async fn get_linked_identities() -> Result<Vec<LinkedIdentity>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let oauth = expect_context::<std::sync::Arc<OAuthService>>();
+    let user = require_user().await.map_err(server_error)?;
+
+    oauth.linked_identities(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to load linked identities");
+        server_error(e)
+    })
+}
+
+#[server(UnlinkIdentity, "/api")]
+pub async fn unlink_identity(identity_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let oauth = expect_context::<std::sync::Arc<OAuthService>>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    oauth.unlink_identity(&db, user.id, identity_id).await.map_err(|e| {
+        log_error(&e, "Failed to unlink identity");
+        server_error(e)
+    })
+}
+
+#[server(GetActiveSessions, "/api")]
+pub async fn get_active_sessions() -> Result<Vec<ActiveSession>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_user().await.map_err(server_error)?;
+
+    let sessions = SessionService::new().list_sessions(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to load sessions");
+        server_error(e)
+    })?;
+
+    let current = current_session_id();
+    Ok(sessions
+        .into_iter()
+        .map(|session| describe_session(session, current.as_deref()))
+        .collect())
+}
+
+#[server(RevokeSession, "/api")]
+pub async fn revoke_session(session_id: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    // Revoking the session in use is a logout
+    if current_session_id().as_deref() == Some(session_id.as_str()) {
+        return end_session(&db).await.map_err(|e| {
+            log_error(&e, "Failed to end session");
+            server_error(e)
+        });
+    }
+
+    SessionService::new()
+        .revoke_session(&db, user.id, &session_id)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to revoke session");
+            server_error(e)
+        })
+}
+
+#[server(RevokeOtherSessions, "/api")]
+pub async fn revoke_other_sessions() -> Result<u64, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    SessionService::new()
+        .revoke_other_sessions(&db, user.id, current_session_id().as_deref())
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to revoke other sessions");
+            server_error(e)
+        })
+}
+
+#[server(GetAccessTokens, "/api")]
+pub async fn get_access_tokens() -> Result<Vec<PersonalAccessToken>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_user().await.map_err(server_error)?;
+
+    AccessTokenService::new().list(&db, user.id).await.map_err(|e| {
+        log_error(&e, "Failed to load access tokens");
+        server_error(e)
+    })
+}
+
+#[server(CreateAccessToken, "/api")]
+pub async fn create_access_token(input: CreateAccessTokenInput) -> Result<NewAccessToken, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    AccessTokenService::new().create(&db, &user, input).await.map_err(|e| {
+        log_error(&e, "Failed to create access token");
+        server_error(e)
+    })
+}
+
+#[server(RevokePersonalAccessToken, "/api")]
+pub async fn revoke_personal_access_token(token_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    AccessTokenService::new().revoke(&db, user.id, token_id).await.map_err(|e| {
+        log_error(&e, "Failed to revoke access token");
+        server_error(e)
+    })
+}
+
+#[server(GetLockedAccounts, "/api")]
+pub async fn get_locked_accounts() -> Result<Vec<LockedAccount>, ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    LoginThrottle::new().locked_accounts(&db).await.map_err(|e| {
+        log_error(&e, "Failed to load locked accounts");
+        server_error(e)
+    })
+}
+
+#[server(UnlockUserAccount, "/api")]
+pub async fn unlock_user_account(user_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    let ip = client_ip(&request_headers());
+    LoginThrottle::new().unlock(&db, user_id, user.id, ip).await.map_err(|e| {
+        log_error(&e, "Failed to unlock account");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn AccountSettingsPage() -> impl IntoView {
+    let update_email = create_action(|input: &ChangeEmailInput| {
+        let input = input.clone();
+        async move { change_email(input).await }
+    });
+    let settings = create_resource(
+        move || update_email.version().get(),
+        |_| async { get_account_settings().await },
+    );
+
+    view! {
+        <Title text="Account Settings - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="account-page">
+            <h1>"Account Settings"</h1>
+            <p><A href="/account/security">"Security settings"</A></p>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || settings.get().map(|res| match res {
+                    Ok(settings) => view! {
+                        <ProfileSettings settings=settings.clone() />
+                        <ConfirmIdentity />
+                        <PasswordSettings />
+                        <EmailSettings settings=settings action=update_email />
+                        <DataAndDeletion />
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn ProfileSettings(settings: AccountSettings) -> impl IntoView {
+    let (display_name, set_display_name) = create_signal(settings.display_name);
+    let (bio, set_bio) = create_signal(settings.bio.unwrap_or_default());
+    let (avatar_url, set_avatar_url) = create_signal(settings.avatar_url.unwrap_or_default());
+
+    let save = create_action(|input: &UpdateProfileInput| {
+        let input = input.clone();
+        async move { update_profile(input).await }
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        save.dispatch(UpdateProfileInput {
+            display_name: Some(display_name.get()),
+            bio: Some(bio.get()),
+            avatar_url: Some(avatar_url.get()),
+        });
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Profile"</h2>
+            <p class="meta">{format!("Signed in as @{}", settings.username)}</p>
+            <form on:submit=on_submit>
+                <div class="form-group">
+                    <label for="display-name">"Display name"</label>
+                    <input
+                        type="text"
+                        id="display-name"
+                        required
+                        maxlength="100"
+                        prop:value=display_name
+                        on:input=move |ev| set_display_name.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="bio">"Bio"</label>
+                    <textarea
+                        id="bio"
+                        rows="4"
+                        maxlength="500"
+                        prop:value=bio
+                        on:input=move |ev| set_bio.set(event_target_value(&ev))
+                    >
+                        {bio.get_untracked()}
+                    </textarea>
+                </div>
+                <div class="form-group">
+                    <label for="avatar-url">"Avatar URL"</label>
+                    <input
+                        type="url"
+                        id="avatar-url"
+                        placeholder="https://"
+                        prop:value=avatar_url
+                        on:input=move |ev| set_avatar_url.set(event_target_value(&ev))
+                    />
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || save.pending().get()>
+                    "Save profile"
+                </button>
+            </form>
+            {move || save.value().get().map(|res| match res {
+                Ok(_) => view! { <p class="success-message">"Profile saved."</p> }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+/// Lets users who signed up through a login provider, and so don't know a
+/// password, confirm it's them before the changes below
+#[component]
+fn ConfirmIdentity() -> impl IntoView {
+    let query = use_query_map();
+    let confirmed = query.with_untracked(|q| q.get("reauthenticated").is_some_and(|v| v == "true"));
+    let identities = create_resource(|| (), |_| async { get_linked_identities().await });
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || identities.get().and_then(Result::ok).filter(|ids| !ids.is_empty()).map(|ids| view! {
+                <section class="account-section">
+                    <h2>"Confirm It's You"</h2>
+                    {if confirmed {
+                        view! {
+                            <p class="success-message">
+                                "Confirmed. For the next few minutes you can leave the current password empty below."
+                            </p>
+                        }.into_view()
+                    } else {
+                        view! {
+                            <p>"No password? Sign in again with a linked account, then leave the current password empty below."</p>
+                        }.into_view()
+                    }}
+                    {ids.into_iter().map(|identity| view! {
+                        <a
+                            href=format!("/auth/oauth/{}?reauth=true", identity.provider)
+                            rel="external"
+                            class="btn oauth-button"
+                        >
+                            {format!("Confirm with {}", identity.provider)}
+                        </a>
+                    }).collect_view()}
+                </section>
+            })}
+        </Transition>
+    }
+}
+
+#[component]
+fn PasswordSettings() -> impl IntoView {
+    let (current_password, set_current_password) = create_signal(String::new());
+    let (new_password, set_new_password) = create_signal(String::new());
+    let (confirm_password, set_confirm_password) = create_signal(String::new());
+
+    let save = create_action(|input: &ChangePasswordInput| {
+        let input = input.clone();
+        async move { change_password(input).await }
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        save.dispatch(ChangePasswordInput {
+            current_password: current_password.get(),
+            new_password: new_password.get(),
+            confirm_password: confirm_password.get(),
+        });
+    };
+
+    // Don't leave passwords sitting in the form once they've been accepted
+    create_effect(move |_| {
+        if let Some(Ok(())) = save.value().get() {
+            set_current_password.set(String::new());
+            set_new_password.set(String::new());
+            set_confirm_password.set(String::new());
+        }
+    });
+
+    view! {
+        <section class="account-section">
+            <h2>"Password"</h2>
+            <p>"Changing your password signs you out on every other device."</p>
+            <form on:submit=on_submit>
+                <div class="form-group">
+                    <label for="current-password">"Current password"</label>
+                    <input
+                        type="password"
+                        id="current-password"
+                        autocomplete="current-password"
+                        prop:value=current_password
+                        on:input=move |ev| set_current_password.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="new-password">"New password"</label>
+                    <input
+                        type="password"
+                        id="new-password"
+                        required
+                        minlength="8"
+                        autocomplete="new-password"
+                        prop:value=new_password
+                        on:input=move |ev| set_new_password.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="confirm-new-password">"Confirm new password"</label>
+                    <input
+                        type="password"
+                        id="confirm-new-password"
+                        required
+                        autocomplete="new-password"
+                        prop:value=confirm_password
+                        on:input=move |ev| set_confirm_password.set(event_target_value(&ev))
+                    />
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || save.pending().get()>
+                    "Change password"
+                </button>
+            </form>
+            {move || save.value().get().map(|res| match res {
+                Ok(()) => view! { <p class="success-message">"Password changed."</p> }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn EmailSettings(
+    settings: AccountSettings,
+    action: Action<ChangeEmailInput, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let (new_email, set_new_email) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        action.dispatch(ChangeEmailInput {
+            new_email: new_email.get(),
+            password: password.get(),
+        });
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Email"</h2>
+            <p>
+                {format!("Your email address is {}", settings.email)}
+                " "
+                {if settings.email_verified {
+                    view! { <span class="badge">"Verified"</span> }.into_view()
+                } else {
+                    view! { <span class="badge">"Unverified"</span> }.into_view()
+                }}
+            </p>
+            {settings.pending_email.map(|pending| view! {
+                <p class="meta">
+                    {format!("We sent a confirmation link to {}. Your address changes once you open it.", pending)}
+                </p>
+            })}
+            <form on:submit=on_submit>
+                <div class="form-group">
+                    <label for="new-email">"New email address"</label>
+                    <input
+                        type="email"
+                        id="new-email"
+                        required
+                        prop:value=new_email
+                        on:input=move |ev| set_new_email.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="email-password">"Current password"</label>
+                    <input
+                        type="password"
+                        id="email-password"
+                        autocomplete="current-password"
+                        prop:value=password
+                        on:input=move |ev| set_password.set(event_target_value(&ev))
+                    />
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || action.pending().get()>
+                    "Change email"
+                </button>
+            </form>
+            {move || action.value().get().map(|res| match res {
+                Ok(()) => view! {
+                    <p class="success-message">"Check your new inbox for a confirmation link."</p>
+                }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn DataAndDeletion() -> impl IntoView {
+    let delete = create_server_action::<DeleteAccount>();
+
+    view! {
+        <section class="account-section">
+            <h2>"Your Data"</h2>
+            <p>"Download a copy of your profile, posts, revisions, sessions and other account records."</p>
+            <a href="/account/export" rel="external" class="btn" download>"Download my data"</a>
+        </section>
+
+        <section class="account-section danger-zone">
+            <h2>"Delete Account"</h2>
+            <p>
+                "Your account is deactivated straight away and permanently deleted after a grace period. "
+                "Until then you can restore it from the link we email you. "
+                "Posts you wrote stay published but are no longer credited to you."
+            </p>
+            {move || match delete.value().get() {
+                Some(Ok(purge_date)) => view! {
+                    <p class="success-message">
+                        {format!("Your account has been deactivated and will be deleted on {}.", purge_date)}
+                    </p>
+                }.into_view(),
+                result => view! {
+                    {result.and_then(Result::err).map(|e| view! {
+                        <p class="error-message">{e.to_string()}</p>
+                    })}
+                    <ActionForm action=delete>
+                        <div class="form-group">
+                            <label for="delete-password">"Current password"</label>
+                            <input
+                                type="password"
+                                id="delete-password"
+                                name="password"
+                                autocomplete="current-password"
+                            />
+                        </div>
+                        <button type="submit" class="btn btn-danger" disabled=move || delete.pending().get()>
+                            "Delete my account"
+                        </button>
+                    </ActionForm>
+                }.into_view(),
+            }}
+        </section>
+    }
+}
+
+#[component]
+pub fn AccountSecurityPage() -> impl IntoView {
+    let current_user = create_resource(|| (), |_| async { get_current_user().await });
+    let is_admin = move || {
+        current_user
+            .get()
+            .and_then(Result::ok)
+            .flatten()
+            .is_some_and(|user| user.role == UserRole::Admin)
+    };
+
+    view! {
+        <Title text="Account Security - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="account-page">
+            <h1>"Account Security"</h1>
+            <p><A href="/account/settings">"Profile and account settings"</A></p>
+            <TwoFactorSettings />
+            <ActiveSessions />
+            <AccessTokens />
+            <LinkedAccounts />
+            <Transition fallback=|| ()>
+                <Show when=is_admin>
+                    <TwoFactorPolicySettings />
+                    <LockedAccounts />
+                </Show>
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn ActiveSessions() -> impl IntoView {
+    let revoke = create_server_action::<RevokeSession>();
+    let revoke_others = create_server_action::<RevokeOtherSessions>();
+    let sessions = create_resource(
+        move || (revoke.version().get(), revoke_others.version().get()),
+        |_| async { get_active_sessions().await },
+    );
+
+    view! {
+        <section class="account-section">
+            <h2>"Active Sessions"</h2>
+            <p>"Browsers and devices currently signed in to your account."</p>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || sessions.get().map(|res| match res {
+                    Ok(sessions) => view! {
+                        <ul class="active-sessions">
+                            {sessions.into_iter().map(|session| view! {
+                                <li class:current-session=session.is_current>
+                                    <div>
+                                        <strong>{format!("{} on {}", session.browser, session.os)}</strong>
+                                        " "
+                                        <span class="meta">{session.device}</span>
+                                        <Show when=move || session.is_current>
+                                            " "
+                                            <span class="badge">"This device"</span>
+                                        </Show>
+                                    </div>
+                                    <div class="meta">
+                                        {session.ip_address.unwrap_or_else(|| "Unknown IP".to_string())}
+                                        {format!(
+                                            " · signed in {} · last active {}",
+                                            session.created_at.format("%Y-%m-%d"),
+                                            session.last_accessed.format("%Y-%m-%d %H:%M UTC")
+                                        )}
+                                    </div>
+                                    <Show when=move || !session.is_current>
+                                        <ActionForm action=revoke class="inline-form">
+                                            <input type="hidden" name="session_id" value=session.id.clone() />
+                                            <button type="submit" class="btn">"Revoke"</button>
+                                        </ActionForm>
+                                    </Show>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+            <ActionForm action=revoke_others class="inline-form">
+                <button type="submit" class="btn">"Sign out all other sessions"</button>
+            </ActionForm>
+            {move || revoke_others.value().get().map(|res| match res {
+                Ok(count) => view! {
+                    <p class="success-message">{format!("Signed out {} other session(s).", count)}</p>
+                }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn AccessTokens() -> impl IntoView {
+    let create = create_server_action::<CreateAccessToken>();
+    let revoke = create_server_action::<RevokePersonalAccessToken>();
+    let tokens = create_resource(
+        move || (create.version().get(), revoke.version().get()),
+        |_| async { get_access_tokens().await },
+    );
+
+    let (name, set_name) = create_signal(String::new());
+    let (expires_in_days, set_expires_in_days) = create_signal(Some(90i64));
+    let scopes = create_rw_signal(vec![TokenScope::ReadPosts]);
+
+    let toggle_scope = move |scope: TokenScope, checked: bool| {
+        scopes.update(|scopes| {
+            scopes.retain(|s| *s != scope);
+            if checked {
+                scopes.push(scope);
+            }
+        });
+    };
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        create.dispatch(CreateAccessToken {
+            input: CreateAccessTokenInput {
+                name: name.get(),
+                scopes: scopes.get(),
+                expires_in_days: expires_in_days.get(),
+            },
+        });
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Personal Access Tokens"</h2>
+            <p>"Tokens let scripts and integrations use the API with " <code>"Authorization: Bearer"</code> "."</p>
+
+            {move || create.value().get().map(|res| match res {
+                Ok(created) => view! {
+                    <div class="success-message">
+                        <p>"Copy your new token now. It won't be shown again."</p>
+                        <code class="token-secret">{created.token}</code>
+                    </div>
+                }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+
+            <form on:submit=on_submit class="token-form">
+                <div class="form-group">
+                    <label for="token-name">"Name"</label>
+                    <input
+                        type="text"
+                        id="token-name"
+                        placeholder="e.g. Publishing script"
+                        required
+                        prop:value=name
+                        on:input=move |ev| set_name.set(event_target_value(&ev))
+                    />
+                </div>
+                <fieldset class="form-group">
+                    <legend>"Scopes"</legend>
+                    {TokenScope::ALL.into_iter().map(|scope| view! {
+                        <label class="checkbox-label">
+                            <input
+                                type="checkbox"
+                                prop:checked=move || scopes.with(|s| s.contains(&scope))
+                                on:change=move |ev| toggle_scope(scope, event_target_checked(&ev))
+                            />
+                            <code>{scope.as_str()}</code>
+                            " "
+                            {scope.description()}
+                        </label>
+                    }).collect_view()}
+                </fieldset>
+                <div class="form-group">
+                    <label for="token-expiry">"Expires"</label>
+                    <select
+                        id="token-expiry"
+                        on:change=move |ev| set_expires_in_days.set(event_target_value(&ev).parse().ok())
+                    >
+                        <option value="30">"In 30 days"</option>
+                        <option value="90" selected>"In 90 days"</option>
+                        <option value="365">"In a year"</option>
+                        <option value="never">"Never"</option>
+                    </select>
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || create.pending().get()>
+                    "Create token"
+                </button>
+            </form>
+
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || tokens.get().map(|res| match res {
+                    Ok(tokens) if tokens.is_empty() => view! {
+                        <p class="empty">"You have no access tokens."</p>
+                    }.into_view(),
+                    Ok(tokens) => view! {
+                        <ul class="access-tokens">
+                            {tokens.into_iter().map(|token| view! {
+                                <li>
+                                    <div>
+                                        <strong>{token.name}</strong>
+                                        " "
+                                        <code>{format!("{}…", token.token_prefix)}</code>
+                                    </div>
+                                    <div class="meta">
+                                        {token.scopes.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")}
+                                        {format!(
+                                            " · expires {} · last used {}",
+                                            token.expires_at
+                                                .map(|at| at.format("%Y-%m-%d").to_string())
+                                                .unwrap_or_else(|| "never".to_string()),
+                                            token.last_used_at
+                                                .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+                                                .unwrap_or_else(|| "never".to_string())
+                                        )}
+                                    </div>
+                                    <ActionForm action=revoke class="inline-form">
+                                        <input type="hidden" name="token_id" value=token.id />
+                                        <button type="submit" class="btn">"Revoke"</button>
+                                    </ActionForm>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn LinkedAccounts() -> impl IntoView {
+    let unlink = create_server_action::<UnlinkIdentity>();
+    let identities = create_resource(
+        move || unlink.version().get(),
+        |_| async { get_linked_identities().await },
+    );
+    let providers = create_resource(|| (), |_| async { get_oauth_providers().await });
+
+    view! {
+        <section class="account-section">
+            <h2>"Linked Accounts"</h2>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || identities.get().map(|res| match res {
+                    Ok(identities) if identities.is_empty() => view! {
+                        <p class="empty">"No external accounts are linked."</p>
+                    }.into_view(),
+                    Ok(identities) => view! {
+                        <ul class="linked-accounts">
+                            {identities.into_iter().map(|identity| view! {
+                                <li>
+                                    <strong>{identity.provider}</strong>
+                                    " "
+                                    {identity.email.unwrap_or_default()}
+                                    <ActionForm action=unlink class="inline-form">
+                                        <input type="hidden" name="identity_id" value=identity.id />
+                                        <button type="submit" class="btn">"Unlink"</button>
+                                    </ActionForm>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+            <Transition fallback=|| ()>
+                {move || providers.get().and_then(Result::ok).map(|providers| {
+                    providers.into_iter().map(|provider| view! {
+                        <a
+                            href=format!("/auth/oauth/{}?link=true", provider.name)
+                            rel="external"
+                            class="btn oauth-button"
+                        >
+                            {format!("Connect {}", provider.display_name)}
+                        </a>
+                    }).collect_view()
+                })}
+            </Transition>
+        </section>
+    }
+}
+
+#[component]
+fn TwoFactorPolicySettings() -> impl IntoView {
+    let set_policy = create_server_action::<SetTwoFactorPolicy>();
+
+    view! {
+        <section class="account-section">
+            <h2>"Site Policy"</h2>
+            <p>"Decide whether authors and admins must use two-factor authentication."</p>
+            <ActionForm action=set_policy class="inline-form">
+                <button type="submit" name="required" value="true" class="btn btn-primary">
+                    "Require for authors and admins"
+                </button>
+                <button type="submit" name="required" value="false" class="btn">
+                    "Make optional"
+                </button>
+            </ActionForm>
+            {move || set_policy.value().get().map(|res| match res {
+                Ok(()) => view! { <p class="success-message">"Policy updated."</p> }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn LockedAccounts() -> impl IntoView {
+    let unlock = create_server_action::<UnlockUserAccount>();
+    let accounts = create_resource(
+        move || unlock.version().get(),
+        |_| async { get_locked_accounts().await },
+    );
+
+    view! {
+        <section class="account-section">
+            <h2>"Locked Accounts"</h2>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || accounts.get().map(|res| match res {
+                    Ok(accounts) if accounts.is_empty() => view! {
+                        <p class="empty">"No accounts are locked."</p>
+                    }.into_view(),
+                    Ok(accounts) => view! {
+                        <ul class="locked-accounts">
+                            {accounts.into_iter().map(|account| view! {
+                                <li>
+                                    <strong>{account.username}</strong>
+                                    " "
+                                    {account.email}
+                                    <span class="meta">
+                                        {format!(
+                                            "{} failed attempts, locked until {}",
+                                            account.failed_login_attempts,
+                                            account.locked_until.format("%Y-%m-%d %H:%M UTC")
+                                        )}
+                                    </span>
+                                    <ActionForm action=unlock class="inline-form">
+                                        <input type="hidden" name="user_id" value=account.user_id />
+                                        <button type="submit" class="btn">"Unlock"</button>
+                                    </ActionForm>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn TwoFactorSettings() -> impl IntoView {
+    let begin = create_server_action::<BeginTotpEnrollment>();
+    let confirm = create_server_action::<ConfirmTotpEnrollment>();
+    let regenerate = create_server_action::<RegenerateRecoveryCodes>();
+    let disable = create_server_action::<DisableTwoFactor>();
+
+    let status = create_resource(
+        move || (confirm.version().get(), disable.version().get()),
+        |_| async { get_two_factor_status().await },
+    );
+
+    // Freshly issued recovery codes, shown once
+    let recovery_codes = move || {
+        confirm
+            .value()
+            .get()
+            .and_then(Result::ok)
+            .or_else(|| regenerate.value().get().and_then(Result::ok))
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Two-Factor Authentication"</h2>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || status.get().map(|res| match res {
+                    Ok(status) if status.enabled => view! {
+                        <p class="success-message">
+                            {format!(
+                                "Two-factor authentication is on. {} recovery codes left.",
+                                status.recovery_codes_remaining
+                            )}
+                        </p>
+
+                        <ActionForm action=regenerate class="inline-form">
+                            <input type="text" name="code" placeholder="Authenticator code" required />
+                            <button type="submit" class="btn">"New recovery codes"</button>
+                        </ActionForm>
+
+                        <Show when=move || !status.required_by_policy>
+                            <ActionForm action=disable class="inline-form">
+                                <input type="password" name="password" placeholder="Current password" />
+                                <button type="submit" class="btn">"Turn off"</button>
+                            </ActionForm>
+                        </Show>
+                    }.into_view(),
+                    Ok(status) => view! {
+                        <Show when=move || status.required_by_policy>
+                            <p class="error-message">
+                                "Your role requires two-factor authentication. Set it up to keep publishing."
+                            </p>
+                        </Show>
+
+                        {move || match begin.value().get() {
+                            Some(Ok(enrollment)) => view! {
+                                <p>"Scan this code with your authenticator app, then enter the 6-digit code it shows."</p>
+                                <div class="totp-qr" inner_html=enrollment.qr_svg></div>
+                                <p class="totp-secret">"Or enter this key manually: " <code>{enrollment.secret}</code></p>
+                                <ActionForm action=confirm class="inline-form">
+                                    <input type="text" name="code" inputmode="numeric" placeholder="123456" required />
+                                    <button type="submit" class="btn btn-primary">"Confirm"</button>
+                                </ActionForm>
+                            }.into_view(),
+                            _ => view! {
+                                <p>"Protect your account with a code from an authenticator app."</p>
+                                <ActionForm action=begin>
+                                    <button type="submit" class="btn btn-primary">"Set up two-factor"</button>
+                                </ActionForm>
+                            }.into_view(),
+                        }}
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+
+            {move || recovery_codes().map(|codes| view! {
+                <div class="recovery-codes">
+                    <p>"Save these recovery codes somewhere safe. Each one works once."</p>
+                    <ul>
+                        {codes.into_iter().map(|code| view! { <li><code>{code}</code></li> }).collect_view()}
+                    </ul>
+                </div>
+            })}
+
+            {move || {
+                [confirm.value().get(), regenerate.value().get()]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::err)
+                    .chain(disable.value().get().and_then(Result::err))
+                    .chain(begin.value().get().and_then(Result::err))
+                    .map(|e| view! { <p class="error-message">{e.to_string()}</p> })
+                    .collect_view()
+            }}
+        </section>
+    }
+}
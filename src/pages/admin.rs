@@ -0,0 +1,463 @@
+use leptos::*;
+use leptos_meta::*;
+use crate::models::{
+    AuditLogEntry, CreateInviteInput, ImpersonationStatus, InviteCode, NewInvite, RegistrationMode,
+    StartImpersonationInput, UserRole,
+};
+use crate::pages::auth::get_registration_mode;
+use crate::pages::taxonomy::{CategoryAdmin, TagAdmin};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    audit::AuditService,
+    auth::AuthService,
+    db::Db,
+    error::{AppError, log_error, server_error, validation::validate_input},
+    guards::{ensure_role, ensure_two_factor_policy, require_account_owner, require_role},
+    impersonation::ImpersonationService,
+    invites::InviteService,
+    session::{
+        cookie_value, current_caller, request_headers, set_cookie, start_session, SessionService,
+        SESSION_COOKIE_NAME,
+    },
+    settings::{set_setting, REGISTRATION_MODE},
+};
+
+#[cfg(feature = "ssr")]
+const AUDIT_LOG_PAGE_SIZE: i64 = 100;
+
+#[server(SetRegistrationMode, "/api")]
+pub async fn set_registration_mode(mode: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    let mode = RegistrationMode::parse(&mode)
+        .ok_or_else(|| server_error(AppError::Validation("Unknown registration mode".to_string())))?;
+
+    set_setting(&db, REGISTRATION_MODE, mode.as_str(), user.id)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to update registration mode");
+            server_error(e)
+        })
+}
+
+#[server(GetInvites, "/api")]
+pub async fn get_invites() -> Result<Vec<InviteCode>, ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    InviteService::new().list(&db).await.map_err(|e| {
+        log_error(&e, "Failed to load invites");
+        server_error(e)
+    })
+}
+
+#[server(CreateInvite, "/api")]
+pub async fn create_invite(input: CreateInviteInput) -> Result<NewInvite, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    InviteService::new().create(&db, &user, input).await.map_err(|e| {
+        log_error(&e, "Failed to create invite");
+        server_error(e)
+    })
+}
+
+#[server(RevokeInvite, "/api")]
+pub async fn revoke_invite(invite_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    InviteService::new().revoke(&db, invite_id).await.map_err(|e| {
+        log_error(&e, "Failed to revoke invite");
+        server_error(e)
+    })
+}
+
+/// Switch the current browser to an impersonation session for another user
+#[server(StartImpersonation, "/api")]
+pub async fn start_impersonation(input: StartImpersonationInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    // An impersonation session can't be used to start another one
+    let admin = require_account_owner().await.map_err(server_error)?;
+    ensure_role(&admin, &UserRole::Admin).map_err(server_error)?;
+    ensure_two_factor_policy(&admin).await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    let headers = request_headers();
+    let token = ImpersonationService::new()
+        .start(&db, &admin, &input, &headers)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to start impersonation");
+            server_error(e)
+        })?;
+
+    // The admin's own session is replaced and recreated when they stop
+    let sessions = SessionService::new();
+    if let Some(own_token) = cookie_value(&headers, SESSION_COOKIE_NAME) {
+        sessions.delete_session(&db, &own_token).await.map_err(server_error)?;
+    }
+    set_cookie(&sessions.session_cookie(&token, None)).map_err(server_error)?;
+
+    Ok(())
+}
+
+/// Leave an impersonation session and sign back in as the admin
+#[server(StopImpersonation, "/api")]
+pub async fn stop_impersonation() -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let headers = request_headers();
+
+    let token = cookie_value(&headers, SESSION_COOKIE_NAME)
+        .ok_or_else(|| server_error(AppError::Unauthorized))?;
+
+    let admin_id = ImpersonationService::new()
+        .stop(&db, &token, &headers)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Failed to stop impersonation");
+            server_error(e)
+        })?;
+
+    start_session(&db, admin_id, false).await.map_err(server_error)?;
+    Ok(())
+}
+
+/// Who is being impersonated, and by whom, when the current session is an impersonation
+#[server(GetImpersonationStatus, "/api")]
+pub async fn get_impersonation_status() -> Result<Option<ImpersonationStatus>, ServerFnError> {
+    let db = expect_context::<Db>();
+
+    let Some(caller) = current_caller().await.map_err(server_error)? else {
+        return Ok(None);
+    };
+    let Some(impersonator_id) = caller.impersonator_id else {
+        return Ok(None);
+    };
+
+    let impersonator = AuthService::new()
+        .get_user_by_id(&db, impersonator_id)
+        .await
+        .map_err(server_error)?;
+
+    Ok(Some(ImpersonationStatus {
+        username: caller.user.username,
+        display_name: caller.user.display_name,
+        impersonator_username: impersonator.username,
+    }))
+}
+
+#[server(GetAuditLog, "/api")]
+pub async fn get_audit_log() -> Result<Vec<AuditLogEntry>, ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    AuditService::new().recent(&db, AUDIT_LOG_PAGE_SIZE).await.map_err(|e| {
+        log_error(&e, "Failed to load audit log");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn AdminPage() -> impl IntoView {
+    view! {
+        <Title text="Administration - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="account-page">
+            <h1>"Administration"</h1>
+            <RegistrationSettings />
+            <Invitations />
+            <CategoryAdmin />
+            <TagAdmin />
+            <Impersonation />
+            <AuditLog />
+        </div>
+    }
+}
+
+#[component]
+fn RegistrationSettings() -> impl IntoView {
+    let set_mode = create_server_action::<SetRegistrationMode>();
+    let mode = create_resource(
+        move || set_mode.version().get(),
+        |_| async { get_registration_mode().await },
+    );
+
+    view! {
+        <section class="account-section">
+            <h2>"Registration"</h2>
+            <Transition fallback=|| ()>
+                {move || mode.get().and_then(Result::ok).map(|mode| view! {
+                    <p>
+                        {match mode {
+                            RegistrationMode::Open => "Anyone can create an account.",
+                            RegistrationMode::InviteOnly => "New accounts need an invite code.",
+                            RegistrationMode::Closed => "Registration is closed.",
+                        }}
+                    </p>
+                })}
+            </Transition>
+            <ActionForm action=set_mode class="inline-form">
+                <button type="submit" name="mode" value="open" class="btn">"Open"</button>
+                <button type="submit" name="mode" value="invite_only" class="btn">"Invite only"</button>
+                <button type="submit" name="mode" value="closed" class="btn">"Closed"</button>
+            </ActionForm>
+            {move || set_mode.value().get().map(|res| match res {
+                Ok(()) => view! { <p class="success-message">"Registration mode updated."</p> }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn Invitations() -> impl IntoView {
+    let create = create_server_action::<CreateInvite>();
+    let revoke = create_server_action::<RevokeInvite>();
+    let invites = create_resource(
+        move || (create.version().get(), revoke.version().get()),
+        |_| async { get_invites().await },
+    );
+
+    let (role, set_role) = create_signal(UserRole::User);
+    let (max_uses, set_max_uses) = create_signal(Some(1i32));
+    let (expires_in_days, set_expires_in_days) = create_signal(Some(14i64));
+    let (note, set_note) = create_signal(String::new());
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        create.dispatch(CreateInvite {
+            input: CreateInviteInput {
+                role: role.get(),
+                max_uses: max_uses.get(),
+                expires_in_days: expires_in_days.get(),
+                note: Some(note.get()),
+            },
+        });
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Invitations"</h2>
+
+            {move || create.value().get().map(|res| match res {
+                Ok(created) => view! {
+                    <div class="success-message">
+                        <p>"Share this link. The code won't be shown again."</p>
+                        <code class="token-secret">{created.url}</code>
+                    </div>
+                }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+
+            <form on:submit=on_submit class="token-form">
+                <div class="form-group">
+                    <label for="invite-role">"Role"</label>
+                    <select
+                        id="invite-role"
+                        on:change=move |ev| set_role.set(match event_target_value(&ev).as_str() {
+                            "author" => UserRole::Author,
+                            _ => UserRole::User,
+                        })
+                    >
+                        <option value="user" selected>"Reader"</option>
+                        <option value="author">"Author"</option>
+                    </select>
+                </div>
+                <div class="form-group">
+                    <label for="invite-uses">"Uses"</label>
+                    <select
+                        id="invite-uses"
+                        on:change=move |ev| set_max_uses.set(event_target_value(&ev).parse().ok())
+                    >
+                        <option value="1" selected>"Single use"</option>
+                        <option value="10">"10 people"</option>
+                        <option value="100">"100 people"</option>
+                        <option value="unlimited">"Unlimited"</option>
+                    </select>
+                </div>
+                <div class="form-group">
+                    <label for="invite-expiry">"Expires"</label>
+                    <select
+                        id="invite-expiry"
+                        on:change=move |ev| set_expires_in_days.set(event_target_value(&ev).parse().ok())
+                    >
+                        <option value="1">"In a day"</option>
+                        <option value="14" selected>"In two weeks"</option>
+                        <option value="90">"In 90 days"</option>
+                        <option value="never">"Never"</option>
+                    </select>
+                </div>
+                <div class="form-group">
+                    <label for="invite-note">"Note"</label>
+                    <input
+                        type="text"
+                        id="invite-note"
+                        placeholder="Who is this for?"
+                        prop:value=note
+                        on:input=move |ev| set_note.set(event_target_value(&ev))
+                    />
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || create.pending().get()>
+                    "Create invite"
+                </button>
+            </form>
+
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || invites.get().map(|res| match res {
+                    Ok(invites) if invites.is_empty() => view! {
+                        <p class="empty">"No active invites."</p>
+                    }.into_view(),
+                    Ok(invites) => view! {
+                        <ul class="access-tokens">
+                            {invites.into_iter().map(|invite| view! {
+                                <li>
+                                    <div>
+                                        <code>{format!("{}…", invite.code_prefix)}</code>
+                                        " "
+                                        <strong>{invite.role.to_string()}</strong>
+                                        " "
+                                        {invite.note.unwrap_or_default()}
+                                    </div>
+                                    <div class="meta">
+                                        {format!(
+                                            "used {}{} · expires {}",
+                                            invite.uses,
+                                            invite.max_uses.map(|max| format!(" of {}", max)).unwrap_or_default(),
+                                            invite.expires_at
+                                                .map(|at| at.format("%Y-%m-%d").to_string())
+                                                .unwrap_or_else(|| "never".to_string())
+                                        )}
+                                    </div>
+                                    <ActionForm action=revoke class="inline-form">
+                                        <input type="hidden" name="invite_id" value=invite.id />
+                                        <button type="submit" class="btn">"Revoke"</button>
+                                    </ActionForm>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn Impersonation() -> impl IntoView {
+    let (username, set_username) = create_signal(String::new());
+    let (reason, set_reason) = create_signal(String::new());
+
+    let start = create_action(|input: &StartImpersonationInput| {
+        let input = input.clone();
+        async move { start_impersonation(input).await }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = start.value().get() {
+            // Full reload so everything renders as the impersonated user
+            window().location().set_href("/").ok();
+        }
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        start.dispatch(StartImpersonationInput {
+            username: username.get(),
+            reason: reason.get(),
+        });
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"View as User"</h2>
+            <p>
+                "Browse the site as another user to reproduce what they see. "
+                "Password, email and security changes are blocked, and the session is recorded in the audit log."
+            </p>
+            <form on:submit=on_submit class="token-form">
+                <div class="form-group">
+                    <label for="impersonate-username">"Username"</label>
+                    <input
+                        type="text"
+                        id="impersonate-username"
+                        required
+                        prop:value=username
+                        on:input=move |ev| set_username.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="impersonate-reason">"Reason"</label>
+                    <input
+                        type="text"
+                        id="impersonate-reason"
+                        required
+                        maxlength="500"
+                        placeholder="Support ticket or explanation"
+                        prop:value=reason
+                        on:input=move |ev| set_reason.set(event_target_value(&ev))
+                    />
+                </div>
+                <button type="submit" class="btn btn-primary" disabled=move || start.pending().get()>
+                    "View as user"
+                </button>
+            </form>
+            {move || start.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn AuditLog() -> impl IntoView {
+    let entries = create_resource(|| (), |_| async { get_audit_log().await });
+
+    view! {
+        <section class="account-section">
+            <h2>"Audit Log"</h2>
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || entries.get().map(|res| match res {
+                    Ok(entries) if entries.is_empty() => view! {
+                        <p class="empty">"Nothing recorded yet."</p>
+                    }.into_view(),
+                    Ok(entries) => view! {
+                        <ul class="audit-log">
+                            {entries.into_iter().map(|entry| view! {
+                                <li>
+                                    <div>
+                                        <strong>{entry.actor_username.unwrap_or_else(|| "deleted user".to_string())}</strong>
+                                        " "
+                                        <code>{entry.action}</code>
+                                        " "
+                                        {entry.target_username.unwrap_or_default()}
+                                    </div>
+                                    <div class="meta">
+                                        {format!(
+                                            "{} · {}",
+                                            entry.created_at.format("%Y-%m-%d %H:%M UTC"),
+                                            entry.ip_address.unwrap_or_else(|| "Unknown IP".to_string())
+                                        )}
+                                    </div>
+                                    {entry.details.map(|details| view! { <div>{details}</div> })}
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
@@ -1,49 +1,432 @@
 use leptos::*;
 use leptos_router::*;
 use leptos_meta::*;
-use crate::models::{LoginInput, RegisterInput, AuthResponse};
+use crate::models::{
+    LoginInput, LoginOutcome, ApiLoginOutcome, RegisterInput, AuthResponse, UserProfile,
+    ForgotPasswordInput, ResetPasswordInput, TwoFactorLoginInput, OAuthProviderInfo,
+    RegistrationMode, MagicLinkInput,
+};
 
 #[cfg(feature = "ssr")]
 use crate::{
     db::Db,
-    auth::AuthService,
-    error::{AppError, log_error, validation::validate_input},
+    auth::{AuthService, LoginStep},
+    error::{AppError, log_error, server_error, validation::validate_input},
+    guards::require_account_owner,
+    impersonation::ImpersonationService,
+    lockout::LoginThrottle,
+    magic_link::MagicLinkService,
+    mailer::SharedMailer,
+    oauth::OAuthService,
+    privacy::PrivacyService,
+    security::client_ip,
+    session::{
+        bearer_token, cookie_value, current_user, end_session, request_headers, set_cookie, start_session,
+        SessionService, SESSION_COOKIE_NAME, TWO_FACTOR_COOKIE_NAME, TWO_FACTOR_COOKIE_PATH,
+    },
 };
 
 #[server(LoginUser, "/api")]
-pub async fn login_user(input: LoginInput) -> Result<AuthResponse, ServerFnError> {
+pub async fn login_user(input: LoginInput) -> Result<LoginOutcome, ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let auth_service = AuthService::new();
+
+    // Validate input
+    validate_input(&input).map_err(server_error)?;
+
+    let remember_me = input.remember_me.unwrap_or(false);
+    let ip = client_ip(&request_headers());
+    let step = auth_service
+        .login_user(&db, mailer.as_ref(), input, ip)
+        .await
+        .map_err(|e| {
+            log_error(&e, "User login failed");
+            server_error(e)
+        })?;
+
+    match step {
+        LoginStep::Authenticated(user) => {
+            start_session(&db, user.id, remember_me).await.map_err(|e| {
+                log_error(&e, "Failed to start session");
+                server_error(e)
+            })?;
+
+            tracing::info!("User authenticated: {}", user.username);
+            Ok(LoginOutcome::Authenticated)
+        }
+        LoginStep::TwoFactorRequired { challenge_token, expires_at } => {
+            Ok(LoginOutcome::TwoFactorRequired { challenge_token, expires_at })
+        }
+    }
+}
+
+#[server(RequestMagicLink, "/api")]
+pub async fn request_magic_link(input: MagicLinkInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+
+    validate_input(&input).map_err(server_error)?;
+
+    let ip = client_ip(&request_headers());
+    MagicLinkService::new()
+        .request(&db, mailer.as_ref(), input, ip)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Magic link request failed");
+            server_error(e)
+        })
+}
+
+#[server(SignInWithMagicLink, "/api")]
+pub async fn sign_in_with_magic_link(token: String) -> Result<LoginOutcome, ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    let (step, remember_me) = auth_service
+        .login_with_magic_link(&db, &token)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Magic link login failed");
+            server_error(e)
+        })?;
+
+    match step {
+        LoginStep::Authenticated(user) => {
+            start_session(&db, user.id, remember_me).await.map_err(|e| {
+                log_error(&e, "Failed to start session");
+                server_error(e)
+            })?;
+            Ok(LoginOutcome::Authenticated)
+        }
+        LoginStep::TwoFactorRequired { challenge_token, expires_at } => {
+            Ok(LoginOutcome::TwoFactorRequired { challenge_token, expires_at })
+        }
+    }
+}
+
+#[server(CompleteTwoFactorLogin, "/api")]
+pub async fn complete_two_factor_login(mut input: TwoFactorLoginInput) -> Result<(), ServerFnError> {
     let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
     let auth_service = AuthService::new();
 
     // Validate input
-    validate_input(&input).map_err(ServerFnError::from)?;
+    validate_input(&input).map_err(server_error)?;
+
+    // Provider logins leave their challenge in an HttpOnly cookie
+    let headers = request_headers();
+    let from_cookie = input.challenge_token.is_empty();
+    if from_cookie {
+        input.challenge_token = cookie_value(&headers, TWO_FACTOR_COOKIE_NAME).ok_or_else(|| {
+            server_error(AppError::Validation("Login request expired, please sign in again".to_string()))
+        })?;
+    }
+
+    let ip = client_ip(&headers);
+    let (user, remember_me) = auth_service
+        .complete_two_factor_login(&db, mailer.as_ref(), input, ip)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Two-factor login failed");
+            server_error(e)
+        })?;
 
-    let response = auth_service.login_user(&db, input).await.map_err(|e| {
-        log_error(&e, "User login failed");
-        ServerFnError::from(e)
+    if from_cookie {
+        let clear = SessionService::new().flow_cookie(
+            TWO_FACTOR_COOKIE_NAME,
+            "",
+            TWO_FACTOR_COOKIE_PATH,
+            chrono::Duration::zero(),
+        );
+        set_cookie(&clear).map_err(server_error)?;
+    }
+
+    start_session(&db, user.id, remember_me).await.map_err(|e| {
+        log_error(&e, "Failed to start session");
+        server_error(e)
     })?;
 
-    tracing::info!("User authenticated: {}", response.user.username);
-    Ok(response)
+    tracing::info!("User authenticated: {}", user.username);
+    Ok(())
 }
 
 #[server(RegisterUser, "/api")]
-pub async fn register_user(input: RegisterInput) -> Result<AuthResponse, ServerFnError> {
+pub async fn register_user(input: RegisterInput) -> Result<UserProfile, ServerFnError> {
     let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
     let auth_service = AuthService::new();
 
     // Validate input
-    validate_input(&input).map_err(ServerFnError::from)?;
+    validate_input(&input).map_err(server_error)?;
 
-    let response = auth_service.register_user(&db, input).await.map_err(|e| {
+    let user = auth_service.register_user(&db, mailer.as_ref(), input).await.map_err(|e| {
         log_error(&e, "User registration failed");
-        ServerFnError::from(e)
+        server_error(e)
+    })?;
+
+    start_session(&db, user.id, false).await.map_err(|e| {
+        log_error(&e, "Failed to start session");
+        server_error(e)
+    })?;
+
+    tracing::info!("User registered: {}", user.username);
+    Ok(UserProfile::from(user))
+}
+
+#[server(GetRegistrationMode, "/api")]
+pub async fn get_registration_mode() -> Result<RegistrationMode, ServerFnError> {
+    let db = expect_context::<Db>();
+
+    crate::settings::get_registration_mode(&db).await.map_err(|e| {
+        log_error(&e, "Failed to load registration mode");
+        server_error(e)
+    })
+}
+
+#[server(LogoutUser, "/api")]
+pub async fn logout_user() -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+
+    // Signing out of an impersonation session ends the impersonation too;
+    // whether the admin could be signed back in doesn't matter here
+    let headers = request_headers();
+    if let Some(token) = cookie_value(&headers, SESSION_COOKIE_NAME) {
+        match ImpersonationService::new().stop(&db, &token, &headers).await {
+            Ok(_) | Err(AppError::Validation(_) | AppError::Unauthorized | AppError::Forbidden(_)) => {}
+            Err(e) => log_error(&e, "Failed to record end of impersonation"),
+        }
+    }
+
+    end_session(&db).await.map_err(|e| {
+        log_error(&e, "User logout failed");
+        server_error(e)
+    })?;
+
+    Ok(())
+}
+
+/// Sign in with a password and receive an access/refresh token pair (API clients)
+#[server(ApiLogin, "/api")]
+pub async fn api_login(input: LoginInput) -> Result<ApiLoginOutcome, ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let auth_service = AuthService::new();
+
+    validate_input(&input).map_err(server_error)?;
+
+    let ip = client_ip(&request_headers());
+    let result: Result<ApiLoginOutcome, AppError> = async {
+        match auth_service.login_user(&db, mailer.as_ref(), input, ip).await? {
+            LoginStep::Authenticated(user) => Ok(ApiLoginOutcome::Authenticated(
+                auth_service.issue_api_tokens(&db, user).await?,
+            )),
+            LoginStep::TwoFactorRequired { challenge_token, expires_at } => {
+                Ok(ApiLoginOutcome::TwoFactorRequired { challenge_token, expires_at })
+            }
+        }
+    }
+    .await;
+
+    result.map_err(|e| {
+        log_error(&e, "API login failed");
+        server_error(e)
+    })
+}
+
+/// Finish an API client login with a TOTP or recovery code
+#[server(ApiCompleteTwoFactorLogin, "/api")]
+pub async fn api_complete_two_factor_login(input: TwoFactorLoginInput) -> Result<AuthResponse, ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let auth_service = AuthService::new();
+
+    validate_input(&input).map_err(server_error)?;
+
+    let ip = client_ip(&request_headers());
+    let result: Result<AuthResponse, AppError> = async {
+        let (user, _) = auth_service.complete_two_factor_login(&db, mailer.as_ref(), input, ip).await?;
+        auth_service.issue_api_tokens(&db, user).await
+    }
+    .await;
+
+    result.map_err(|e| {
+        log_error(&e, "API two-factor login failed");
+        server_error(e)
+    })
+}
+
+/// Exchange a refresh token for a new access/refresh token pair (API clients)
+#[server(RefreshAccessToken, "/api")]
+pub async fn refresh_access_token(refresh_token: String) -> Result<AuthResponse, ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    let response = auth_service.refresh_tokens(&db, &refresh_token).await.map_err(|e| {
+        log_error(&e, "Token refresh failed");
+        server_error(e)
     })?;
 
-    tracing::info!("User registered: {}", response.user.username);
     Ok(response)
 }
 
+/// Revoke the bearer access token of this request and, if given, its refresh token family
+#[server(RevokeApiToken, "/api")]
+pub async fn revoke_api_token(refresh_token: Option<String>) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    let result: Result<(), AppError> = async {
+        if let Some(token) = bearer_token(&request_headers()) {
+            let claims = auth_service.validate_token(&db, &token).await?;
+            auth_service.revoke_access_token(&db, &claims).await?;
+        }
+        if let Some(refresh_token) = refresh_token {
+            auth_service.revoke_refresh_token(&db, &refresh_token).await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    result.map_err(|e| {
+        log_error(&e, "Token revocation failed");
+        server_error(e)
+    })
+}
+
+/// Revoke every session, access token and refresh token of the current user
+#[server(SignOutEverywhere, "/api")]
+pub async fn sign_out_everywhere() -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    let result: Result<(), AppError> = async {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+        auth_service.sign_out_everywhere(&mut tx, user.id).await?;
+        tx.commit().await.map_err(AppError::Database)
+    }
+    .await;
+
+    result.map_err(|e| {
+        log_error(&e, "Sign out everywhere failed");
+        server_error(e)
+    })?;
+
+    end_session(&db).await.map_err(server_error)?;
+    Ok(())
+}
+
+#[server(GetOAuthProviders, "/api")]
+pub async fn get_oauth_providers() -> Result<Vec<OAuthProviderInfo>, ServerFnError> {
+    let oauth = expect_context::<std::sync::Arc<OAuthService>>();
+    Ok(oauth.providers())
+}
+
+#[server(GetCurrentUser, "/api")]
+pub async fn get_current_user() -> Result<Option<UserProfile>, ServerFnError> {
+    let user = current_user().await.map_err(|e| {
+        log_error(&e, "Failed to resolve current user");
+        server_error(e)
+    })?;
+
+    Ok(user.map(UserProfile::from))
+}
+
+#[server(RequestPasswordReset, "/api")]
+pub async fn request_password_reset(input: ForgotPasswordInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let auth_service = AuthService::new();
+
+    // Validate input
+    validate_input(&input).map_err(server_error)?;
+
+    auth_service
+        .request_password_reset(&db, mailer.as_ref(), &input.email)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Password reset request failed");
+            server_error(e)
+        })?;
+
+    Ok(())
+}
+
+#[server(ResetPassword, "/api")]
+pub async fn reset_password(input: ResetPasswordInput) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    // Validate input
+    validate_input(&input).map_err(server_error)?;
+
+    auth_service
+        .reset_password(&db, &input.token, &input.new_password)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Password reset failed");
+            server_error(e)
+        })?;
+
+    Ok(())
+}
+
+#[server(VerifyEmail, "/api")]
+pub async fn verify_email(token: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let auth_service = AuthService::new();
+
+    auth_service.verify_email(&db, &token).await.map_err(|e| {
+        log_error(&e, "Email verification failed");
+        server_error(e)
+    })?;
+
+    Ok(())
+}
+
+#[server(UnlockAccount, "/api")]
+pub async fn unlock_account(token: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+
+    LoginThrottle::new().unlock_with_token(&db, &token).await.map_err(|e| {
+        log_error(&e, "Account unlock failed");
+        server_error(e)
+    })?;
+
+    Ok(())
+}
+
+#[server(RestoreAccount, "/api")]
+pub async fn restore_account(token: String) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+
+    PrivacyService::new().restore(&db, &token).await.map_err(|e| {
+        log_error(&e, "Account restore failed");
+        server_error(e)
+    })
+}
+
+#[server(ResendVerificationEmail, "/api")]
+pub async fn resend_verification_email() -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let mailer = expect_context::<SharedMailer>();
+    let auth_service = AuthService::new();
+
+    let user = require_account_owner().await.map_err(server_error)?;
+
+    auth_service
+        .resend_verification_email(&db, mailer.as_ref(), &user)
+        .await
+        .map_err(|e| {
+            log_error(&e, "Resending verification email failed");
+            server_error(e)
+        })?;
+
+    Ok(())
+}
+
 #[component]
 pub fn LoginPage() -> impl IntoView {
     let (login_input, set_login_input) = create_signal(LoginInput {
@@ -52,8 +435,17 @@ pub fn LoginPage() -> impl IntoView {
         remember_me: Some(false),
     });
     
-    let (error_message, set_error_message) = create_signal(None::<String>);
+    // Social logins come back here with `?error=oauth`, or `?two_factor=pending`
+    // when the account still needs its second factor; that challenge is held
+    // in an HttpOnly cookie, which an empty token tells the server to use
+    let query = use_query_map();
+    let (error_message, set_error_message) = create_signal(query.with_untracked(|q| {
+        q.get("error").map(|_| "Signing in with that provider failed. Please try again.".to_string())
+    }));
     let (loading, set_loading) = create_signal(false);
+    let (challenge_token, set_challenge_token) =
+        create_signal(query.with_untracked(|q| q.get("two_factor").map(|_| String::new())));
+    let providers = create_resource(|| (), |_| async { get_oauth_providers().await });
 
     let login_action = create_action(move |input: &LoginInput| {
         let input = input.clone();
@@ -62,18 +454,12 @@ pub fn LoginPage() -> impl IntoView {
             set_error_message.set(None);
             
             match login_user(input).await {
-                Ok(response) => {
-                    // Store token in localStorage and redirect
-                    web_sys::window()
-                        .and_then(|w| w.local_storage().ok().flatten())
-                        .map(|storage| {
-                            storage.set_item("auth_token", &response.token).ok();
-                            storage.set_item("user_data", &serde_json::to_string(&response.user).unwrap_or_default()).ok();
-                        });
-                    
-                    // Redirect to home page
-                    let navigate = use_navigate();
-                    navigate("/", Default::default());
+                Ok(LoginOutcome::Authenticated) => {
+                    // Full reload so the server renders with the new session cookie
+                    window().location().set_href("/").ok();
+                },
+                Ok(LoginOutcome::TwoFactorRequired { challenge_token, .. }) => {
+                    set_challenge_token.set(Some(challenge_token));
                 },
                 Err(e) => {
                     set_error_message.set(Some(e.to_string()));
@@ -97,59 +483,85 @@ pub fn LoginPage() -> impl IntoView {
                     </div>
                 </Show>
 
-                <form on:submit=move |ev| {
-                    ev.prevent_default();
-                    login_action.dispatch(login_input.get());
-                }>
-                    <div class="form-group">
-                        <label for="email_or_username">"Email or Username"</label>
-                        <input
-                            type="text"
-                            id="email_or_username"
-                            required
-                            prop:value=move || login_input.get().email_or_username
-                            on:input=move |ev| {
-                                let value = event_target_value(&ev);
-                                set_login_input.update(|input| input.email_or_username = value);
-                            }
-                        />
-                    </div>
+                <Show
+                    when=move || challenge_token.get().is_some()
+                    fallback=move || view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            login_action.dispatch(login_input.get());
+                        }>
+                            <div class="form-group">
+                                <label for="email_or_username">"Email or Username"</label>
+                                <input
+                                    type="text"
+                                    id="email_or_username"
+                                    required
+                                    prop:value=move || login_input.get().email_or_username
+                                    on:input=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_login_input.update(|input| input.email_or_username = value);
+                                    }
+                                />
+                            </div>
 
-                    <div class="form-group">
-                        <label for="password">"Password"</label>
-                        <input
-                            type="password"
-                            id="password"
-                            required
-                            prop:value=move || login_input.get().password
-                            on:input=move |ev| {
-                                let value = event_target_value(&ev);
-                                set_login_input.update(|input| input.password = value);
-                            }
-                        />
-                    </div>
+                            <div class="form-group">
+                                <label for="password">"Password"</label>
+                                <input
+                                    type="password"
+                                    id="password"
+                                    required
+                                    prop:value=move || login_input.get().password
+                                    on:input=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_login_input.update(|input| input.password = value);
+                                    }
+                                />
+                            </div>
 
-                    <div class="form-group checkbox">
-                        <input
-                            type="checkbox"
-                            id="remember_me"
-                            prop:checked=move || login_input.get().remember_me.unwrap_or(false)
-                            on:change=move |ev| {
-                                let checked = event_target_checked(&ev);
-                                set_login_input.update(|input| input.remember_me = Some(checked));
-                            }
-                        />
-                        <label for="remember_me">"Remember me"</label>
-                    </div>
+                            <div class="form-group checkbox">
+                                <input
+                                    type="checkbox"
+                                    id="remember_me"
+                                    prop:checked=move || login_input.get().remember_me.unwrap_or(false)
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        set_login_input.update(|input| input.remember_me = Some(checked));
+                                    }
+                                />
+                                <label for="remember_me">"Remember me"</label>
+                            </div>
 
-                    <button 
-                        type="submit" 
-                        class="btn btn-primary"
-                        disabled=move || loading.get()
-                    >
-                        {move || if loading.get() { "Logging in..." } else { "Login" }}
-                    </button>
-                </form>
+                            <button 
+                                type="submit" 
+                                class="btn btn-primary"
+                                disabled=move || loading.get()
+                            >
+                                {move || if loading.get() { "Logging in..." } else { "Login" }}
+                            </button>
+                        </form>
+                    }
+                >
+                    {move || view! {
+                        <TwoFactorForm challenge_token=challenge_token.get().unwrap_or_default() />
+                    }}
+                </Show>
+
+                <Transition fallback=|| ()>
+                    {move || providers.get().and_then(Result::ok).filter(|p| !p.is_empty()).map(|providers| view! {
+                        <div class="oauth-providers">
+                            <p class="oauth-divider">"or continue with"</p>
+                            {providers.into_iter().map(|provider| view! {
+                                <a
+                                    href=format!("/auth/oauth/{}", provider.name)
+                                    rel="external"
+                                    class="btn oauth-button"
+                                >
+                                    {provider.display_name}
+                                </a>
+                            }).collect_view()}
+                        </div>
+                    })}
+                </Transition>
 
                 <div class="auth-links">
                     <p>
@@ -158,6 +570,226 @@ pub fn LoginPage() -> impl IntoView {
                     </p>
                     <p>
                         <a href="/forgot-password">"Forgot your password?"</a>
+                        " · "
+                        <a href="/login/magic">"Email me a sign-in link"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn MagicLinkPage() -> impl IntoView {
+    let (email, set_email) = create_signal(String::new());
+    let (remember_me, set_remember_me) = create_signal(false);
+    let (error_message, set_error_message) = create_signal(None::<String>);
+    let (submitted, set_submitted) = create_signal(false);
+
+    let request_action = create_action(move |input: &MagicLinkInput| {
+        let input = input.clone();
+        async move {
+            set_error_message.set(None);
+            match request_magic_link(input).await {
+                Ok(()) => set_submitted.set(true),
+                Err(e) => set_error_message.set(Some(e.to_string())),
+            }
+        }
+    });
+
+    view! {
+        <Title text="Sign in with Email - Taleji" />
+        <Meta name="description" content="Sign in to Taleji without a password" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Sign in with Email"</h1>
+
+                <Show when=move || error_message.get().is_some()>
+                    <div class="error-message">
+                        {move || error_message.get().unwrap_or_default()}
+                    </div>
+                </Show>
+
+                <Show
+                    when=move || submitted.get()
+                    fallback=move || view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            request_action.dispatch(MagicLinkInput {
+                                email: email.get(),
+                                remember_me: Some(remember_me.get()),
+                            });
+                        }>
+                            <p>"We'll email you a link that signs you in, no password needed."</p>
+                            <div class="form-group">
+                                <label for="email">"Email"</label>
+                                <input
+                                    type="email"
+                                    id="email"
+                                    required
+                                    prop:value=move || email.get()
+                                    on:input=move |ev| set_email.set(event_target_value(&ev))
+                                />
+                            </div>
+
+                            <div class="form-group checkbox">
+                                <input
+                                    type="checkbox"
+                                    id="remember_me"
+                                    prop:checked=move || remember_me.get()
+                                    on:change=move |ev| set_remember_me.set(event_target_checked(&ev))
+                                />
+                                <label for="remember_me">"Remember me"</label>
+                            </div>
+
+                            <button
+                                type="submit"
+                                class="btn btn-primary"
+                                disabled=move || request_action.pending().get()
+                            >
+                                {move || if request_action.pending().get() { "Sending..." } else { "Send Sign-in Link" }}
+                            </button>
+                        </form>
+                    }
+                >
+                    <div class="success-message">
+                        "If an account exists for that address, a sign-in link is on its way."
+                    </div>
+                </Show>
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/login">"Sign in with a password"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Second step of a sign-in. The challenge stays in memory (or, after a
+/// provider login, in the challenge cookie when `challenge_token` is empty)
+/// rather than in the URL.
+#[component]
+fn TwoFactorForm(challenge_token: String) -> impl IntoView {
+    let challenge_token = store_value(challenge_token);
+    let (code, set_code) = create_signal(String::new());
+
+    let verify = create_action(move |input: &TwoFactorLoginInput| {
+        let input = input.clone();
+        async move {
+            let result = complete_two_factor_login(input).await;
+            if result.is_ok() {
+                // Full reload so the server renders with the new session cookie
+                window().location().set_href("/").ok();
+            }
+            result
+        }
+    });
+
+    view! {
+        {move || verify.value().get().and_then(Result::err).map(|e| view! {
+            <div class="error-message">{e.to_string()}</div>
+        })}
+        <form on:submit=move |ev| {
+            ev.prevent_default();
+            verify.dispatch(TwoFactorLoginInput {
+                challenge_token: challenge_token.get_value(),
+                code: code.get(),
+            });
+        }>
+            <p>"Enter the code from your authenticator app, or one of your recovery codes."</p>
+            <div class="form-group">
+                <label for="two_factor_code">"Verification Code"</label>
+                <input
+                    type="text"
+                    id="two_factor_code"
+                    inputmode="numeric"
+                    autocomplete="one-time-code"
+                    required
+                    prop:value=move || code.get()
+                    on:input=move |ev| set_code.set(event_target_value(&ev))
+                />
+            </div>
+
+            <button
+                type="submit"
+                class="btn btn-primary"
+                disabled=move || verify.pending().get()
+            >
+                {move || if verify.pending().get() { "Verifying..." } else { "Verify" }}
+            </button>
+        </form>
+    }
+}
+
+/// Landing page of an emailed sign-in link. Signing in takes a click so that
+/// mail scanners prefetching the link don't use it up.
+#[component]
+pub fn MagicLinkLoginPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+    let (error_message, set_error_message) = create_signal(None::<String>);
+    let (challenge_token, set_challenge_token) = create_signal(None::<String>);
+
+    let sign_in = create_action(move |token: &String| {
+        let token = token.clone();
+        async move {
+            set_error_message.set(None);
+            match sign_in_with_magic_link(token).await {
+                Ok(LoginOutcome::Authenticated) => {
+                    // Full reload so the server renders with the new session cookie
+                    window().location().set_href("/").ok();
+                }
+                Ok(LoginOutcome::TwoFactorRequired { challenge_token, .. }) => {
+                    set_challenge_token.set(Some(challenge_token));
+                }
+                Err(e) => set_error_message.set(Some(e.to_string())),
+            }
+        }
+    });
+
+    view! {
+        <Title text="Sign In - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Sign In"</h1>
+
+                <Show when=move || error_message.get().is_some()>
+                    <div class="error-message">
+                        {move || error_message.get().unwrap_or_default()}
+                    </div>
+                </Show>
+
+                <Show
+                    when=move || challenge_token.get().is_some()
+                    fallback=move || view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            sign_in.dispatch(token());
+                        }>
+                            <p>"Continue to sign in to your Taleji account."</p>
+                            <button
+                                type="submit"
+                                class="btn btn-primary"
+                                disabled=move || sign_in.pending().get()
+                            >
+                                {move || if sign_in.pending().get() { "Signing in..." } else { "Sign In" }}
+                            </button>
+                        </form>
+                    }
+                >
+                    {move || view! {
+                        <TwoFactorForm challenge_token=challenge_token.get().unwrap_or_default() />
+                    }}
+                </Show>
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/login/magic">"Request a new link"</a>
                     </p>
                 </div>
             </div>
@@ -167,13 +799,23 @@ pub fn LoginPage() -> impl IntoView {
 
 #[component]
 pub fn RegisterPage() -> impl IntoView {
+    // Invite links look like /register?invite=CODE
+    let query = use_query_map();
     let (register_input, set_register_input) = create_signal(RegisterInput {
         username: String::new(),
         email: String::new(),
         password: String::new(),
         confirm_password: String::new(),
         display_name: String::new(),
+        invite_code: query.with_untracked(|q| q.get("invite").cloned()),
     });
+    let registration_mode = create_resource(|| (), |_| async { get_registration_mode().await });
+    let mode = move || {
+        registration_mode
+            .get()
+            .and_then(Result::ok)
+            .unwrap_or(RegistrationMode::Open)
+    };
     
     let (error_message, set_error_message) = create_signal(None::<String>);
     let (loading, set_loading) = create_signal(false);
@@ -185,18 +827,9 @@ pub fn RegisterPage() -> impl IntoView {
             set_error_message.set(None);
             
             match register_user(input).await {
-                Ok(response) => {
-                    // Store token in localStorage and redirect
-                    web_sys::window()
-                        .and_then(|w| w.local_storage().ok().flatten())
-                        .map(|storage| {
-                            storage.set_item("auth_token", &response.token).ok();
-                            storage.set_item("user_data", &serde_json::to_string(&response.user).unwrap_or_default()).ok();
-                        });
-                    
-                    // Redirect to home page
-                    let navigate = use_navigate();
-                    navigate("/", Default::default());
+                Ok(_) => {
+                    // Full reload so the server renders with the new session cookie
+                    window().location().set_href("/").ok();
                 },
                 Err(e) => {
                     set_error_message.set(Some(e.to_string()));
@@ -220,10 +853,39 @@ pub fn RegisterPage() -> impl IntoView {
                     </div>
                 </Show>
 
+                <Transition fallback=|| ()>
+                    <Show when=move || mode() == RegistrationMode::Closed>
+                        <div class="error-message">
+                            "Registration is currently closed."
+                        </div>
+                    </Show>
+                </Transition>
+
                 <form on:submit=move |ev| {
                     ev.prevent_default();
                     register_action.dispatch(register_input.get());
                 }>
+                    <Transition fallback=|| ()>
+                        <Show when=move || {
+                            mode() == RegistrationMode::InviteOnly
+                                || register_input.with(|input| input.invite_code.is_some())
+                        }>
+                            <div class="form-group">
+                                <label for="invite_code">"Invite Code"</label>
+                                <input
+                                    type="text"
+                                    id="invite_code"
+                                    required=move || mode() == RegistrationMode::InviteOnly
+                                    prop:value=move || register_input.get().invite_code.unwrap_or_default()
+                                    on:input=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_register_input.update(|input| input.invite_code = Some(value));
+                                    }
+                                />
+                            </div>
+                        </Show>
+                    </Transition>
+
                     <div class="form-group">
                         <label for="username">"Username"</label>
                         <input
@@ -312,4 +974,324 @@ pub fn RegisterPage() -> impl IntoView {
             </div>
         </div>
     }
-}
\ No newline at end of file
+}
+
+#[component]
+pub fn ForgotPasswordPage() -> impl IntoView {
+    let (email, set_email) = create_signal(String::new());
+    let (error_message, set_error_message) = create_signal(None::<String>);
+    let (submitted, set_submitted) = create_signal(false);
+
+    let reset_action = create_action(move |input: &ForgotPasswordInput| {
+        let input = input.clone();
+        async move {
+            set_error_message.set(None);
+            match request_password_reset(input).await {
+                Ok(()) => set_submitted.set(true),
+                Err(e) => set_error_message.set(Some(e.to_string())),
+            }
+        }
+    });
+
+    view! {
+        <Title text="Forgot Password - Taleji" />
+        <Meta name="description" content="Reset your Taleji password" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Reset Password"</h1>
+
+                <Show when=move || error_message.get().is_some()>
+                    <div class="error-message">
+                        {move || error_message.get().unwrap_or_default()}
+                    </div>
+                </Show>
+
+                <Show
+                    when=move || submitted.get()
+                    fallback=move || view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            reset_action.dispatch(ForgotPasswordInput { email: email.get() });
+                        }>
+                            <div class="form-group">
+                                <label for="email">"Email"</label>
+                                <input
+                                    type="email"
+                                    id="email"
+                                    required
+                                    prop:value=move || email.get()
+                                    on:input=move |ev| set_email.set(event_target_value(&ev))
+                                />
+                            </div>
+
+                            <button
+                                type="submit"
+                                class="btn btn-primary"
+                                disabled=move || reset_action.pending().get()
+                            >
+                                {move || if reset_action.pending().get() { "Sending..." } else { "Send Reset Link" }}
+                            </button>
+                        </form>
+                    }
+                >
+                    <div class="success-message">
+                        "If an account exists for that address, a reset link is on its way."
+                    </div>
+                </Show>
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/login">"Back to login"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn ResetPasswordPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+
+    let (new_password, set_new_password) = create_signal(String::new());
+    let (confirm_password, set_confirm_password) = create_signal(String::new());
+    let (error_message, set_error_message) = create_signal(None::<String>);
+    let (completed, set_completed) = create_signal(false);
+
+    let reset_action = create_action(move |input: &ResetPasswordInput| {
+        let input = input.clone();
+        async move {
+            set_error_message.set(None);
+            match reset_password(input).await {
+                Ok(()) => set_completed.set(true),
+                Err(e) => set_error_message.set(Some(e.to_string())),
+            }
+        }
+    });
+
+    view! {
+        <Title text="Choose a New Password - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Choose a New Password"</h1>
+
+                <Show when=move || error_message.get().is_some()>
+                    <div class="error-message">
+                        {move || error_message.get().unwrap_or_default()}
+                    </div>
+                </Show>
+
+                <Show
+                    when=move || completed.get()
+                    fallback=move || view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            reset_action.dispatch(ResetPasswordInput {
+                                token: token(),
+                                new_password: new_password.get(),
+                                confirm_password: confirm_password.get(),
+                            });
+                        }>
+                            <div class="form-group">
+                                <label for="new_password">"New Password"</label>
+                                <input
+                                    type="password"
+                                    id="new_password"
+                                    required
+                                    prop:value=move || new_password.get()
+                                    on:input=move |ev| set_new_password.set(event_target_value(&ev))
+                                />
+                            </div>
+
+                            <div class="form-group">
+                                <label for="confirm_password">"Confirm Password"</label>
+                                <input
+                                    type="password"
+                                    id="confirm_password"
+                                    required
+                                    prop:value=move || confirm_password.get()
+                                    on:input=move |ev| set_confirm_password.set(event_target_value(&ev))
+                                />
+                            </div>
+
+                            <button
+                                type="submit"
+                                class="btn btn-primary"
+                                disabled=move || reset_action.pending().get()
+                            >
+                                {move || if reset_action.pending().get() { "Saving..." } else { "Set Password" }}
+                            </button>
+                        </form>
+                    }
+                >
+                    <div class="success-message">
+                        "Your password has been changed and all sessions were signed out."
+                    </div>
+                </Show>
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/login">"Back to login"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Landing page of an emailed verification link. Verifying takes a click so
+/// that mail scanners prefetching the link don't use it up.
+#[component]
+pub fn VerifyEmailPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+
+    let verify = create_server_action::<VerifyEmail>();
+    let resend = create_server_action::<ResendVerificationEmail>();
+
+    view! {
+        <Title text="Verify Email - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Email Verification"</h1>
+
+                {move || match verify.value().get() {
+                    None => view! {
+                        <ActionForm action=verify>
+                            <input type="hidden" name="token" value=token />
+                            <p>"Confirm this email address for your Taleji account."</p>
+                            <button type="submit" class="btn btn-primary" disabled=move || verify.pending().get()>
+                                {move || if verify.pending().get() { "Verifying..." } else { "Verify Email" }}
+                            </button>
+                        </ActionForm>
+                    }.into_view(),
+                    Some(Ok(())) => view! {
+                        <div class="success-message">
+                            "Thanks! Your email address is verified."
+                        </div>
+                    }.into_view(),
+                    Some(Err(e)) => view! {
+                        <div class="error-message">{e.to_string()}</div>
+                        <ActionForm action=resend>
+                            <button type="submit" class="btn btn-primary">
+                                "Send a new link"
+                            </button>
+                        </ActionForm>
+                        {move || resend.value().get().map(|res| match res {
+                            Ok(()) => view! {
+                                <p class="success-message">"A new verification link has been sent."</p>
+                            }.into_view(),
+                            Err(e) => view! {
+                                <p class="error-message">{e.to_string()}</p>
+                            }.into_view(),
+                        })}
+                    }.into_view(),
+                }}
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/">"Continue to Taleji"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn UnlockAccountPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+
+    // Unlock only on a button press, so link scanners can't spend the token
+    let unlock = create_server_action::<UnlockAccount>();
+
+    view! {
+        <Title text="Unlock Account - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Unlock Account"</h1>
+
+                {move || match unlock.value().get() {
+                    None => view! {
+                        <ActionForm action=unlock>
+                            <input type="hidden" name="token" value=token />
+                            <p>"Lift the sign-in lock on your Taleji account."</p>
+                            <button type="submit" class="btn btn-primary" disabled=move || unlock.pending().get()>
+                                {move || if unlock.pending().get() { "Unlocking..." } else { "Unlock Account" }}
+                            </button>
+                        </ActionForm>
+                    }.into_view(),
+                    Some(Ok(())) => view! {
+                        <div class="success-message">
+                            "Your account is unlocked. You can sign in again."
+                        </div>
+                    }.into_view(),
+                    Some(Err(e)) => view! {
+                        <div class="error-message">{e.to_string()}</div>
+                    }.into_view(),
+                }}
+
+                <div class="auth-links">
+                    <p>
+                        <a href="/login">"Sign in"</a>
+                        " · "
+                        <a href="/forgot-password">"Reset your password"</a>
+                    </p>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn RestoreAccountPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+
+    // Restore only on a button press, so link scanners can't spend the token
+    let restore = create_server_action::<RestoreAccount>();
+
+    view! {
+        <Title text="Restore Account - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="auth-container">
+            <div class="auth-card">
+                <h1>"Restore Account"</h1>
+
+                {move || match restore.value().get() {
+                    None => view! {
+                        <ActionForm action=restore>
+                            <input type="hidden" name="token" value=token />
+                            <p>"Cancel the deletion of your Taleji account."</p>
+                            <button type="submit" class="btn btn-primary" disabled=move || restore.pending().get()>
+                                {move || if restore.pending().get() { "Restoring..." } else { "Restore Account" }}
+                            </button>
+                        </ActionForm>
+                    }.into_view(),
+                    Some(Ok(())) => view! {
+                        <div class="success-message">
+                            "Your account is restored and will not be deleted. You can sign in again."
+                        </div>
+                    }.into_view(),
+                    Some(Err(e)) => view! {
+                        <div class="error-message">{e.to_string()}</div>
+                    }.into_view(),
+                }}
+
+                <div class="auth-links">
+                    <p><a href="/login">"Sign in"</a></p>
+                </div>
+            </div>
+        </div>
+    }
+}
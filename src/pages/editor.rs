@@ -0,0 +1,370 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::*;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use crate::models::{CreatePostInput, Post, Tag, UpdatePostInput};
+use crate::pages::preview::PreviewLinks;
+
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::AuthService,
+    db::Db,
+    error::{log_error, server_error, validation::validate_input},
+    guards::{ensure_owner_or_role, ensure_post_author_or_admin, ensure_role, require_scope},
+    models::{TokenScope, UserRole},
+    posts::PostService,
+    tags::TagService,
+};
+
+/// A post by slug, including drafts, with its tags, for its author or an admin
+#[server(GetPostForEdit, "/api")]
+pub async fn get_post_for_edit(slug: String) -> Result<(Post, Vec<Tag>), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ReadPosts).await.map_err(server_error)?;
+
+    let post = PostService::new().get_by_slug(&db, &slug).await.map_err(|e| {
+        log_error(&e, &format!("Failed to load post for editing: {}", slug));
+        server_error(e)
+    })?;
+
+    ensure_owner_or_role(&user, post.author_id, &UserRole::Admin).map_err(server_error)?;
+
+    let tags = TagService::new().for_post(&db, post.id).await.map_err(|e| {
+        log_error(&e, "Failed to load post tags");
+        server_error(e)
+    })?;
+    Ok((post, tags))
+}
+
+#[server(CreatePost, "/api")]
+pub async fn create_post(input: CreatePostInput) -> Result<Post, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_role(&user, &UserRole::Author).map_err(server_error)?;
+    AuthService::new().ensure_can_contribute(&user).map_err(server_error)?;
+    validate_input(&input).map_err(server_error)?;
+
+    PostService::new().create(&db, &user, input).await.map_err(|e| {
+        log_error(&e, "Failed to create post");
+        server_error(e)
+    })
+}
+
+#[server(UpdatePost, "/api")]
+pub async fn update_post(post_id: i64, input: UpdatePostInput) -> Result<Post, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_role(&user, &UserRole::Author).map_err(server_error)?;
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+    validate_input(&input).map_err(server_error)?;
+
+    PostService::new().update(&db, &user, post_id, input).await.map_err(|e| {
+        log_error(&e, "Failed to update post");
+        server_error(e)
+    })
+}
+
+#[server(DeletePost, "/api")]
+pub async fn delete_post(post_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_role(&user, &UserRole::Author).map_err(server_error)?;
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    PostService::new().delete(&db, post_id).await.map_err(|e| {
+        log_error(&e, "Failed to delete post");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn WritePage() -> impl IntoView {
+    view! {
+        <Title text="Write - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="editor-page">
+            <h1>"New Post"</h1>
+            <PostEditor post=None tags=Vec::new() />
+        </div>
+    }
+}
+
+#[component]
+pub fn EditPostPage() -> impl IntoView {
+    let params = use_params_map();
+    let slug = move || params.with(|m| m.get("slug").cloned().unwrap_or_default());
+
+    let post = create_resource(slug, |slug| async move { get_post_for_edit(slug).await });
+
+    view! {
+        <Title text="Edit Post - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="editor-page">
+            <h1>"Edit Post"</h1>
+            <Suspense fallback=move || view! { <p class="loading">"Loading post..."</p> }>
+                {move || post.get().map(|res| match res {
+                    Ok((post, tags)) => {
+                        let post_id = post.id;
+                        view! {
+                            <PostEditor post=Some(post) tags=tags />
+                            <PreviewLinks post_id=post_id />
+                        }.into_view()
+                    }
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Format used by `datetime-local` inputs; the editor works in UTC
+const SCHEDULE_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+fn parse_schedule(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, SCHEDULE_FORMAT).ok().map(|at| at.and_utc())
+}
+
+/// Tag names from the comma-separated tags field
+fn parse_tags(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect()
+}
+
+/// Form shared by the new-post and edit pages; `post` is `None` when writing a new post
+#[component]
+fn PostEditor(post: Option<Post>, tags: Vec<Tag>) -> impl IntoView {
+    let post_id = post.as_ref().map(|p| p.id);
+    let (title, set_title) = create_signal(post.as_ref().map(|p| p.title.clone()).unwrap_or_default());
+    let (slug, set_slug) = create_signal(post.as_ref().map(|p| p.slug.clone()).unwrap_or_default());
+    let (summary, set_summary) = create_signal(post.as_ref().map(|p| p.summary.clone()).unwrap_or_default());
+    let (body, set_body) = create_signal(post.as_ref().map(|p| p.body_markdown.clone()).unwrap_or_default());
+    let (published, set_published) = create_signal(post.as_ref().is_some_and(|p| p.published_at.is_some()));
+    let (publish_at, set_publish_at) = create_signal(
+        post.as_ref()
+            .filter(|p| p.is_scheduled())
+            .and_then(|p| p.published_at)
+            .map(|at| at.format(SCHEDULE_FORMAT).to_string())
+            .unwrap_or_default(),
+    );
+    let (unpublish_at, set_unpublish_at) = create_signal(
+        post.as_ref()
+            .and_then(|p| p.unpublish_at)
+            .map(|at| at.format(SCHEDULE_FORMAT).to_string())
+            .unwrap_or_default(),
+    );
+    let (tag_names, set_tag_names) = create_signal(
+        tags.into_iter().map(|t| t.name).collect::<Vec<_>>().join(", "),
+    );
+    let (change_summary, set_change_summary) = create_signal(String::new());
+    let history_href = post.as_ref().map(|p| format!("/post/{}/history", p.slug));
+
+    let save = create_action(move |_: &()| async move {
+        match post_id {
+            Some(post_id) => {
+                let slug = slug.get_untracked().trim().to_string();
+                update_post(post_id, UpdatePostInput {
+                    slug: (!slug.is_empty()).then_some(slug),
+                    title: Some(title.get_untracked()),
+                    summary: Some(summary.get_untracked()),
+                    body_markdown: Some(body.get_untracked()),
+                    published: Some(published.get_untracked()),
+                    publish_at: parse_schedule(&publish_at.get_untracked()),
+                    unpublish_at: parse_schedule(&unpublish_at.get_untracked()),
+                    tags: Some(parse_tags(&tag_names.get_untracked())),
+                    change_summary: Some(change_summary.get_untracked()),
+                })
+                .await
+            }
+            None => {
+                create_post(CreatePostInput {
+                    slug: slug.get_untracked(),
+                    title: title.get_untracked(),
+                    summary: summary.get_untracked(),
+                    body_markdown: body.get_untracked(),
+                    published: published.get_untracked(),
+                    publish_at: parse_schedule(&publish_at.get_untracked()),
+                    unpublish_at: parse_schedule(&unpublish_at.get_untracked()),
+                    tags: parse_tags(&tag_names.get_untracked()),
+                })
+                .await
+            }
+        }
+    });
+
+    let delete = create_action(move |post_id: &i64| {
+        let post_id = *post_id;
+        async move { delete_post(post_id).await }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(post)) = save.value().get() {
+            let target = if post.is_published() {
+                format!("/post/{}", post.slug)
+            } else {
+                format!("/post/{}/edit", post.slug)
+            };
+            use_navigate()(&target, Default::default());
+        }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = delete.value().get() {
+            use_navigate()("/", Default::default());
+        }
+    });
+
+    view! {
+        <form class="post-editor" on:submit=move |ev| {
+            ev.prevent_default();
+            save.dispatch(());
+        }>
+            {move || save.value().get().and_then(Result::err).map(|e| view! {
+                <div class="error-message">{e.to_string()}</div>
+            })}
+
+            <div class="form-group">
+                <label for="post-title">"Title"</label>
+                <input
+                    type="text"
+                    id="post-title"
+                    required
+                    maxlength="200"
+                    prop:value=title
+                    on:input=move |ev| set_title.set(event_target_value(&ev))
+                />
+            </div>
+
+            <div class="form-group">
+                <label for="post-slug">"Slug"</label>
+                <input
+                    type="text"
+                    id="post-slug"
+                    maxlength="100"
+                    pattern="[a-z0-9\\-]*"
+                    placeholder="Generated from the title"
+                    title="Lowercase letters, numbers and hyphens. Links to the old slug keep working after a change."
+                    prop:value=slug
+                    on:input=move |ev| set_slug.set(event_target_value(&ev))
+                />
+            </div>
+
+            <div class="form-group">
+                <label for="post-summary">"Summary"</label>
+                <textarea
+                    id="post-summary"
+                    rows="2"
+                    maxlength="500"
+                    placeholder="Taken from the opening paragraph when left blank"
+                    prop:value=summary
+                    on:input=move |ev| set_summary.set(event_target_value(&ev))
+                >
+                    {summary.get_untracked()}
+                </textarea>
+            </div>
+
+            <div class="form-group">
+                <label for="post-body">"Body (Markdown)"</label>
+                <textarea
+                    id="post-body"
+                    class="post-body-input"
+                    rows="20"
+                    required
+                    prop:value=body
+                    on:input=move |ev| set_body.set(event_target_value(&ev))
+                >
+                    {body.get_untracked()}
+                </textarea>
+            </div>
+
+            <div class="form-group">
+                <label for="post-tags">"Tags (comma separated)"</label>
+                <input
+                    type="text"
+                    id="post-tags"
+                    placeholder="rust, web, leptos"
+                    prop:value=tag_names
+                    on:input=move |ev| set_tag_names.set(event_target_value(&ev))
+                />
+            </div>
+
+            <div class="form-group checkbox">
+                <input
+                    type="checkbox"
+                    id="post-published"
+                    prop:checked=published
+                    on:change=move |ev| set_published.set(event_target_checked(&ev))
+                />
+                <label for="post-published">"Published"</label>
+            </div>
+
+            <Show when=move || published.get()>
+                <div class="editor-schedule">
+                    <div class="form-group">
+                        <label for="post-publish-at">"Publish at (UTC, leave empty for now)"</label>
+                        <input
+                            type="datetime-local"
+                            id="post-publish-at"
+                            prop:value=publish_at
+                            on:input=move |ev| set_publish_at.set(event_target_value(&ev))
+                        />
+                    </div>
+                    <div class="form-group">
+                        <label for="post-unpublish-at">"Unpublish at (UTC, optional)"</label>
+                        <input
+                            type="datetime-local"
+                            id="post-unpublish-at"
+                            prop:value=unpublish_at
+                            on:input=move |ev| set_unpublish_at.set(event_target_value(&ev))
+                        />
+                    </div>
+                </div>
+            </Show>
+
+            {post_id.map(|_| view! {
+                <div class="form-group">
+                    <label for="post-change-summary">"Change summary (optional)"</label>
+                    <input
+                        type="text"
+                        id="post-change-summary"
+                        maxlength="500"
+                        placeholder="What did you change?"
+                        prop:value=change_summary
+                        on:input=move |ev| set_change_summary.set(event_target_value(&ev))
+                    />
+                </div>
+            })}
+
+            <div class="editor-actions">
+                <button type="submit" class="btn btn-primary" disabled=move || save.pending().get()>
+                    {move || if save.pending().get() { "Saving..." } else { "Save" }}
+                </button>
+                {post_id.map(|post_id| view! {
+                    <button
+                        type="button"
+                        class="btn btn-danger"
+                        disabled=move || delete.pending().get()
+                        on:click=move |_| {
+                            if window().confirm_with_message("Delete this post permanently?").unwrap_or(false) {
+                                delete.dispatch(post_id);
+                            }
+                        }
+                    >
+                        "Delete"
+                    </button>
+                })}
+                {history_href.map(|href| view! {
+                    <a href=href class="history-link">"Revision history"</a>
+                })}
+            </div>
+            {move || delete.value().get().and_then(Result::err).map(|e| view! {
+                <div class="error-message">{e.to_string()}</div>
+            })}
+        </form>
+    }
+}
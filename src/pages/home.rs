@@ -4,7 +4,7 @@ use crate::models::Post;
 #[cfg(feature = "ssr")]
 use crate::db::Db;
 #[cfg(feature = "ssr")]
-use crate::error::{AppError, log_error};
+use crate::error::{AppError, log_error, server_error};
 
 #[server(GetPublishedPosts, "/api")]
 pub async fn get_published_posts() -> Result<Vec<Post>, ServerFnError> {
@@ -13,7 +13,8 @@ pub async fn get_published_posts() -> Result<Vec<Post>, ServerFnError> {
     let posts = sqlx::query_as::<_, Post>(
         r#"
         SELECT * FROM posts
-        WHERE published_at IS NOT NULL
+        WHERE published_at <= NOW()
+          AND (unpublish_at IS NULL OR unpublish_at > NOW())
         ORDER BY published_at DESC
         LIMIT 50
         "#
@@ -23,7 +24,7 @@ pub async fn get_published_posts() -> Result<Vec<Post>, ServerFnError> {
     .map_err(|e| {
         let app_error = AppError::Database(e);
         log_error(&app_error, "Failed to fetch published posts");
-        ServerFnError::from(app_error)
+        server_error(app_error)
     })?;
 
     tracing::info!("Retrieved {} published posts", posts.len());
@@ -42,24 +43,7 @@ pub fn HomePage() -> impl IntoView {
                     Ok(posts) if posts.is_empty() => view! {
                         <p class="empty">"No posts yet. Check back soon!"</p>
                     }.into_view(),
-                    Ok(posts) => view! {
-                        <ul class="post-list">
-                            {posts.into_iter().map(|p| {
-                                let date = p.published_at
-                                    .map(|d| d.format("%B %d, %Y").to_string())
-                                    .unwrap_or_default();
-                                view! {
-                                    <li class="post-item">
-                                        <a href=format!("/post/{}", p.slug) class="post-link">
-                                            <h2 class="post-title">{p.title}</h2>
-                                            <p class="post-date">{date}</p>
-                                            <p class="post-summary">{p.summary}</p>
-                                        </a>
-                                    </li>
-                                }
-                            }).collect_view()}
-                        </ul>
-                    }.into_view(),
+                    Ok(posts) => view! { <PostList posts=posts /> }.into_view(),
                     Err(e) => view! {
                         <p class="error">{format!("Error loading posts: {e}")}</p>
                     }.into_view()
@@ -68,3 +52,32 @@ pub fn HomePage() -> impl IntoView {
         </section>
     }
 }
+
+/// Linked titles, dates, reading times and summaries of posts; shared by the
+/// home, category and tag pages
+#[component]
+pub fn PostList(posts: Vec<Post>) -> impl IntoView {
+    view! {
+        <ul class="post-list">
+            {posts.into_iter().map(|p| {
+                let date = p.published_at
+                    .map(|d| d.format("%B %d, %Y").to_string())
+                    .unwrap_or_default();
+                let date = match p.reading_time() {
+                    Some(reading_time) => format!("{} · {}", date, reading_time),
+                    None => date,
+                };
+                let description = p.description();
+                view! {
+                    <li class="post-item">
+                        <a href=format!("/post/{}", p.slug) class="post-link">
+                            <h2 class="post-title">{p.title}</h2>
+                            <p class="post-date">{date}</p>
+                            <p class="post-summary">{description}</p>
+                        </a>
+                    </li>
+                }
+            }).collect_view()}
+        </ul>
+    }
+}
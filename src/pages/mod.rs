@@ -1,4 +1,27 @@
+use leptos::*;
+use leptos_router::*;
+
 pub mod home;
 pub mod post;
 pub mod auth;
 pub mod search;
+pub mod account;
+pub mod admin;
+pub mod editor;
+pub mod revisions;
+pub mod preview;
+pub mod taxonomy;
+
+/// Swap an outdated slug in the address bar for the current one. Page loads
+/// get a `301` from the server, which needs the route rendered with
+/// `SsrMode::Async` so the status is set before any HTML is sent; this
+/// covers client-side navigations, whose data comes from a server function.
+pub fn use_canonical_path(requested: impl Fn() -> String + 'static, canonical: Signal<Option<String>>) {
+    create_effect(move |_| {
+        if let Some(path) = canonical.get() {
+            if path != requested() {
+                use_navigate()(&path, NavigateOptions { replace: true, ..Default::default() });
+            }
+        }
+    });
+}
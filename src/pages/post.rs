@@ -1,12 +1,15 @@
 use leptos::*;
 use leptos_router::*;
 use leptos_meta::*;
-use crate::models::Post;
+use crate::models::{Post, UserRole};
+use crate::pages::{auth::get_current_user, use_canonical_path};
 
 #[cfg(feature = "ssr")]
 use crate::db::Db;
 #[cfg(feature = "ssr")]
-use crate::error::{AppError, log_error};
+use crate::error::{AppError, log_error, server_error};
+#[cfg(feature = "ssr")]
+use crate::slugs::permanent_redirect;
 
 #[server(GetPostBySlug, "/api")]
 pub async fn get_post_by_slug(slug: String) -> Result<Post, ServerFnError> {
@@ -18,13 +21,19 @@ pub async fn get_post_by_slug(slug: String) -> Result<Post, ServerFnError> {
     if slug.is_empty() || slug.len() > 100 {
         let error = AppError::Validation("Invalid slug format".to_string());
         log_error(&error, &format!("Invalid slug: {}", slug));
-        return Err(ServerFnError::from(error));
+        return Err(server_error(error));
     }
 
+    // The current slug wins; a former slug finds the renamed post
     let post = sqlx::query_as::<_, Post>(
         r#"
-        SELECT * FROM posts
-        WHERE slug = $1 AND published_at IS NOT NULL
+        SELECT p.* FROM posts p
+        LEFT JOIN post_slug_history h ON h.post_id = p.id AND h.old_slug = $1
+        WHERE (p.slug = $1 OR h.old_slug IS NOT NULL)
+          AND p.published_at <= NOW()
+          AND (p.unpublish_at IS NULL OR p.unpublish_at > NOW())
+        ORDER BY p.slug = $1 DESC
+        LIMIT 1
         "#
     )
     .bind(&slug)
@@ -36,9 +45,13 @@ pub async fn get_post_by_slug(slug: String) -> Result<Post, ServerFnError> {
             _ => AppError::Database(e),
         };
         log_error(&app_error, &format!("Failed to fetch post with slug: {}", slug));
-        ServerFnError::from(app_error)
+        server_error(app_error)
     })?;
 
+    if post.slug != slug {
+        permanent_redirect(&format!("/post/{}", post.slug));
+    }
+
     tracing::info!("Retrieved post: {} (slug: {})", post.title, post.slug);
     Ok(post)
 }
@@ -51,26 +64,33 @@ pub fn PostPage() -> impl IntoView {
     let post_res = create_resource(slug, |slug| async move {
         get_post_by_slug(slug).await
     });
+    use_canonical_path(
+        move || format!("/post/{}", slug()),
+        Signal::derive(move || post_res.get().and_then(Result::ok).map(|p| format!("/post/{}", p.slug))),
+    );
+    let current_user = create_resource(|| (), |_| async { get_current_user().await });
 
     view! {
         <article class="post-page">
             <Suspense fallback=move || view! { <p class="loading">"Loading post..."</p> }>
                 {move || post_res.get().map(|res| match res {
                     Ok(post) => {
-                        let date = post.published_at
-                            .map(|d| d.format("%B %d, %Y").to_string())
-                            .unwrap_or_default();
+                        let author_id = post.author_id;
+                        let can_edit = move || {
+                            current_user
+                                .get()
+                                .and_then(Result::ok)
+                                .flatten()
+                                .is_some_and(|user| Some(user.id) == author_id || user.role == UserRole::Admin)
+                        };
+                        let edit_href = format!("/post/{}/edit", post.slug);
                         view! {
-                            <Title text=post.title.clone() />
-                            <Meta name="description" content=post.summary.clone() />
-                            <div class="post-header">
-                                <h1>{post.title}</h1>
-                                <p class="post-meta">{date}</p>
-                                <p class="post-summary">{post.summary}</p>
-                            </div>
-                            <div class="post-body" inner_html=post.body_html></div>
+                            <PostContent post=post />
                             <div class="post-footer">
                                 <a href="/" class="back-link">"← Back to all posts"</a>
+                                <Show when=can_edit>
+                                    <a href=edit_href.clone() class="edit-link">"Edit post"</a>
+                                </Show>
                             </div>
                         }.into_view()
                     },
@@ -86,3 +106,27 @@ pub fn PostPage() -> impl IntoView {
         </article>
     }
 }
+
+/// Title, metadata and body of a post; shared by the post and draft preview pages
+#[component]
+pub fn PostContent(post: Post) -> impl IntoView {
+    let date = post.published_at
+        .map(|d| d.format("%B %d, %Y").to_string())
+        .unwrap_or_default();
+    let reading_time = post.reading_time();
+    let description = post.description();
+
+    view! {
+        <Title text=post.title.clone() />
+        <Meta name="description" content=description />
+        <div class="post-header">
+            <h1>{post.title}</h1>
+            <p class="post-meta">
+                <span>{date}</span>
+                {reading_time.map(|r| view! { <span class="reading-time">{r}</span> })}
+            </p>
+            {(!post.summary.trim().is_empty()).then(|| view! { <p class="post-summary">{post.summary}</p> })}
+        </div>
+        <div class="post-body" inner_html=post.body_html></div>
+    }
+}
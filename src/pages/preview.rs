@@ -0,0 +1,185 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::*;
+use crate::models::{Post, PreviewLink};
+use crate::pages::post::PostContent;
+
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{log_error, server_error},
+    guards::{ensure_post_author_or_admin, ensure_role, require_scope},
+    models::{TokenScope, UserRole},
+    previews::PreviewService,
+};
+
+/// A draft through its preview link. Anyone holding the link may view it.
+#[server(GetPostPreview, "/api")]
+pub async fn get_post_preview(token: String) -> Result<Post, ServerFnError> {
+    use axum::http::{HeaderName, HeaderValue};
+    use leptos_axum::ResponseOptions;
+
+    let db = expect_context::<Db>();
+
+    // Applies to the API response, and to the rendered page because its
+    // route renders with `SsrMode::Async`, before headers are sent
+    if let Some(response) = use_context::<ResponseOptions>() {
+        response.insert_header(HeaderName::from_static("x-robots-tag"), HeaderValue::from_static("noindex, nofollow"));
+    }
+
+    PreviewService::new().resolve(&db, &token).await.map_err(|e| {
+        log_error(&e, "Failed to open post preview");
+        server_error(e)
+    })
+}
+
+#[server(GetPreviewLinks, "/api")]
+pub async fn get_preview_links(post_id: i64) -> Result<Vec<PreviewLink>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ReadPosts).await.map_err(server_error)?;
+
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    PreviewService::new().list(&db, post_id).await.map_err(|e| {
+        log_error(&e, "Failed to load preview links");
+        server_error(e)
+    })
+}
+
+#[server(CreatePreviewLink, "/api")]
+pub async fn create_preview_link(post_id: i64, expires_in_days: i64) -> Result<PreviewLink, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_role(&user, &UserRole::Author).map_err(server_error)?;
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    PreviewService::new().create(&db, &user, post_id, expires_in_days).await.map_err(|e| {
+        log_error(&e, "Failed to create preview link");
+        server_error(e)
+    })
+}
+
+#[server(RevokePreviewLink, "/api")]
+pub async fn revoke_preview_link(post_id: i64, link_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    PreviewService::new().revoke(&db, post_id, link_id).await.map_err(|e| {
+        log_error(&e, "Failed to revoke preview link");
+        server_error(e)
+    })
+}
+
+#[server(RevokeAllPreviewLinks, "/api")]
+pub async fn revoke_all_preview_links(post_id: i64) -> Result<u64, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    PreviewService::new().revoke_all(&db, post_id).await.map_err(|e| {
+        log_error(&e, "Failed to revoke preview links");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn PostPreviewPage() -> impl IntoView {
+    let params = use_params_map();
+    let token = move || params.with(|m| m.get("token").cloned().unwrap_or_default());
+
+    let post_res = create_resource(token, |token| async move { get_post_preview(token).await });
+
+    view! {
+        <Meta name="robots" content="noindex, nofollow" />
+        <div class="preview-banner">"Draft preview — this post is not published yet"</div>
+        <article class="post-page">
+            <Suspense fallback=move || view! { <p class="loading">"Loading preview..."</p> }>
+                {move || post_res.get().map(|res| match res {
+                    Ok(post) => view! { <PostContent post=post /> }.into_view(),
+                    Err(e) => view! {
+                        <div class="error-page">
+                            <h1>"Preview unavailable"</h1>
+                            <p>{format!("Error: {e}")}</p>
+                            <a href="/" class="back-link">"← Back to all posts"</a>
+                        </div>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </article>
+    }
+}
+
+/// Editor panel for creating, copying and revoking a post's preview links
+#[component]
+pub fn PreviewLinks(post_id: i64) -> impl IntoView {
+    let create = create_server_action::<CreatePreviewLink>();
+    let revoke = create_server_action::<RevokePreviewLink>();
+    let revoke_all = create_server_action::<RevokeAllPreviewLinks>();
+    let links = create_resource(
+        move || (create.version().get(), revoke.version().get(), revoke_all.version().get()),
+        move |_| async move { get_preview_links(post_id).await },
+    );
+
+    view! {
+        <section class="account-section preview-links">
+            <h2>"Preview Links"</h2>
+            <p>"Anyone with a preview link can read this post before it is published."</p>
+
+            {move || create.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+
+            <ActionForm action=create class="inline-form">
+                <input type="hidden" name="post_id" value=post_id />
+                <select name="expires_in_days">
+                    <option value="1">"Expires in a day"</option>
+                    <option value="7" selected>"Expires in a week"</option>
+                    <option value="30">"Expires in 30 days"</option>
+                </select>
+                <button type="submit" class="btn btn-primary" disabled=move || create.pending().get()>
+                    "Create link"
+                </button>
+            </ActionForm>
+
+            <Suspense fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || links.get().map(|res| match res {
+                    Ok(links) if links.is_empty() => view! {
+                        <p class="empty">"No active preview links."</p>
+                    }.into_view(),
+                    Ok(links) => view! {
+                        <ul class="access-tokens">
+                            {links.into_iter().map(|link| view! {
+                                <li>
+                                    <code class="token-secret">{link.url}</code>
+                                    <div class="meta">
+                                        {format!(
+                                            "Created by {} · expires {}",
+                                            link.created_by_username.unwrap_or_else(|| "deleted user".to_string()),
+                                            link.expires_at.format("%Y-%m-%d %H:%M UTC")
+                                        )}
+                                    </div>
+                                    <ActionForm action=revoke class="inline-form">
+                                        <input type="hidden" name="post_id" value=post_id />
+                                        <input type="hidden" name="link_id" value=link.id />
+                                        <button type="submit" class="btn">"Revoke"</button>
+                                    </ActionForm>
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                        <ActionForm action=revoke_all class="inline-form">
+                            <input type="hidden" name="post_id" value=post_id />
+                            <button type="submit" class="btn btn-danger">"Revoke all links"</button>
+                        </ActionForm>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
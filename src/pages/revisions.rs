@@ -0,0 +1,218 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::*;
+use crate::models::{DiffOp, DiffSegment, Post, PostHistory, RevisionDiff};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{log_error, server_error},
+    guards::{ensure_owner_or_role, ensure_post_author_or_admin, ensure_role, require_scope},
+    models::{TokenScope, UserRole},
+    posts::PostService,
+    revisions::RevisionService,
+};
+
+#[server(GetPostHistory, "/api")]
+pub async fn get_post_history(slug: String) -> Result<PostHistory, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ReadPosts).await.map_err(server_error)?;
+
+    let post = PostService::new().get_by_slug(&db, &slug).await.map_err(|e| {
+        log_error(&e, &format!("Failed to load post history: {}", slug));
+        server_error(e)
+    })?;
+    ensure_owner_or_role(&user, post.author_id, &UserRole::Admin).map_err(server_error)?;
+
+    RevisionService::new().history(&db, &post).await.map_err(|e| {
+        log_error(&e, "Failed to load post history");
+        server_error(e)
+    })
+}
+
+#[server(GetRevisionDiff, "/api")]
+pub async fn get_revision_diff(post_id: i64, from: i32, to: i32) -> Result<RevisionDiff, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ReadPosts).await.map_err(server_error)?;
+
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    RevisionService::new().diff(&db, post_id, from, to).await.map_err(|e| {
+        log_error(&e, "Failed to diff post revisions");
+        server_error(e)
+    })
+}
+
+#[server(RestoreRevision, "/api")]
+pub async fn restore_revision(post_id: i64, revision_number: i32) -> Result<Post, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::WritePosts).await.map_err(server_error)?;
+
+    ensure_role(&user, &UserRole::Author).map_err(server_error)?;
+    ensure_post_author_or_admin(&db, &user, post_id).await.map_err(server_error)?;
+
+    RevisionService::new().restore(&db, &user, post_id, revision_number).await.map_err(|e| {
+        log_error(&e, "Failed to restore post revision");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn PostHistoryPage() -> impl IntoView {
+    let params = use_params_map();
+    let slug = move || params.with(|m| m.get("slug").cloned().unwrap_or_default());
+
+    let history = create_resource(slug, |slug| async move { get_post_history(slug).await });
+
+    view! {
+        <Title text="Revision History - Taleji" />
+        <Meta name="robots" content="noindex" />
+
+        <div class="editor-page">
+            <h1>"Revision History"</h1>
+            <Transition fallback=move || view! { <p class="loading">"Loading history..."</p> }>
+                {move || history.get().map(|res| match res {
+                    Ok(post_history) => view! {
+                        <RevisionHistory history=post_history on_restore=move || history.refetch() />
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Transition>
+        </div>
+    }
+}
+
+#[component]
+fn RevisionHistory<F>(history: PostHistory, on_restore: F) -> impl IntoView
+where
+    F: Fn() + Copy + 'static,
+{
+    let post_id = history.post_id;
+    let latest = history.revisions.first().map(|r| r.revision_number).unwrap_or(1);
+    let (from, set_from) = create_signal(history.revisions.get(1).map(|r| r.revision_number).unwrap_or(latest));
+    let (to, set_to) = create_signal(latest);
+
+    let diff = create_resource(
+        move || (from.get(), to.get()),
+        move |(from, to)| async move { get_revision_diff(post_id, from, to).await },
+    );
+
+    let restore = create_action(move |revision_number: &i32| {
+        let revision_number = *revision_number;
+        async move { restore_revision(post_id, revision_number).await }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(_)) = restore.value().get() {
+            on_restore();
+        }
+    });
+
+    let numbers: Vec<i32> = history.revisions.iter().map(|r| r.revision_number).collect();
+    let revision_options = move |selected: i32| {
+        numbers
+            .iter()
+            .map(|&n| view! {
+                <option value=n.to_string() selected=n == selected>{format!("Revision {n}")}</option>
+            })
+            .collect_view()
+    };
+
+    view! {
+        <p>
+            <a href=format!("/post/{}/edit", history.slug)>{history.title.clone()}</a>
+        </p>
+
+        {move || restore.value().get().and_then(Result::err).map(|e| view! {
+            <div class="error-message">{e.to_string()}</div>
+        })}
+
+        <ul class="revision-list">
+            {history.revisions.into_iter().map(|revision| {
+                let number = revision.revision_number;
+                view! {
+                    <li>
+                        <div>
+                            <strong>{format!("Revision {number}")}</strong>
+                            " "
+                            {revision.change_summary.unwrap_or_default()}
+                        </div>
+                        <div class="meta">
+                            {format!(
+                                "{} · {}",
+                                revision.created_at.format("%Y-%m-%d %H:%M UTC"),
+                                revision.created_by_username.unwrap_or_else(|| "deleted user".to_string())
+                            )}
+                        </div>
+                        <Show when=move || number != latest>
+                            <button
+                                type="button"
+                                class="btn btn-secondary"
+                                disabled=move || restore.pending().get()
+                                on:click=move |_| {
+                                    if window().confirm_with_message(&format!("Restore revision {number}?")).unwrap_or(false) {
+                                        restore.dispatch(number);
+                                    }
+                                }
+                            >
+                                "Restore"
+                            </button>
+                        </Show>
+                    </li>
+                }
+            }).collect_view()}
+        </ul>
+
+        <div class="revision-compare">
+            <div class="form-group">
+                <label for="diff-from">"Compare"</label>
+                <select
+                    id="diff-from"
+                    on:change=move |ev| if let Ok(n) = event_target_value(&ev).parse() { set_from.set(n) }
+                >
+                    {revision_options.clone()(from.get_untracked())}
+                </select>
+            </div>
+            <div class="form-group">
+                <label for="diff-to">"With"</label>
+                <select
+                    id="diff-to"
+                    on:change=move |ev| if let Ok(n) = event_target_value(&ev).parse() { set_to.set(n) }
+                >
+                    {revision_options(to.get_untracked())}
+                </select>
+            </div>
+        </div>
+
+        <Transition fallback=move || view! { <p class="loading">"Comparing..."</p> }>
+            {move || diff.get().map(|res| match res {
+                Ok(diff) => view! {
+                    <h3>"Title"</h3>
+                    <DiffView segments=diff.title />
+                    <h3>"Summary"</h3>
+                    <DiffView segments=diff.summary />
+                    <h3>"Body"</h3>
+                    <DiffView segments=diff.body_markdown />
+                }.into_view(),
+                Err(e) => view! {
+                    <p class="error">{format!("Error: {e}")}</p>
+                }.into_view(),
+            })}
+        </Transition>
+    }
+}
+
+#[component]
+fn DiffView(segments: Vec<DiffSegment>) -> impl IntoView {
+    view! {
+        <pre class="revision-diff">
+            {segments.into_iter().map(|segment| match segment.op {
+                DiffOp::Equal => view! { <span>{segment.text}</span> }.into_view(),
+                DiffOp::Insert => view! { <ins>{segment.text}</ins> }.into_view(),
+                DiffOp::Delete => view! { <del>{segment.text}</del> }.into_view(),
+            }).collect_view()}
+        </pre>
+    }
+}
@@ -16,7 +16,6 @@ pub struct SearchFilters {
     pub tag_ids: Vec<i64>,
     pub author_id: Option<i64>,
     pub featured_only: bool,
-    pub published_only: bool,
     pub sort_by: SearchSortBy,
     pub page: i32,
     pub per_page: i32,
@@ -49,7 +48,6 @@ impl Default for SearchFilters {
             tag_ids: Vec::new(),
             author_id: None,
             featured_only: false,
-            published_only: true,
             sort_by: SearchSortBy::Newest,
             page: 1,
             per_page: 20,
@@ -57,6 +55,15 @@ impl Default for SearchFilters {
     }
 }
 
+/// Posts that are live right now: publication time reached and not yet expired
+#[cfg(feature = "ssr")]
+const VISIBLE_POSTS: &str = "published_at <= NOW() AND (unpublish_at IS NULL OR unpublish_at > NOW())";
+
+/// Posts outside any deactivated category
+#[cfg(feature = "ssr")]
+const IN_ACTIVE_CATEGORY: &str =
+    "(category_id IS NULL OR category_id IN (SELECT id FROM categories WHERE is_active = true))";
+
 #[server(SearchPosts, "/api")]
 pub async fn search_posts(filters: SearchFilters) -> Result<SearchResults, ServerFnError> {
     let db = expect_context::<Db>();
@@ -87,11 +94,6 @@ pub async fn search_posts(filters: SearchFilters) -> Result<SearchResults, Serve
         from_parts.push("LEFT JOIN tags t ON pt.tag_id = t.id");
     }
 
-    // Published filter
-    if filters.published_only {
-        where_parts.push("p.published_at IS NOT NULL");
-    }
-
     // Featured filter
     if filters.featured_only {
         where_parts.push("p.featured = true");
@@ -150,10 +152,13 @@ pub async fn search_posts(filters: SearchFilters) -> Result<SearchResults, Serve
 
     query_sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset));
 
-    // Execute the search query with simplified approach for now
+    // Execute the search query with simplified approach for now. Drafts,
+    // scheduled and expired posts, and posts in deactivated categories, are
+    // never searchable.
     let posts = sqlx::query_as::<_, Post>(&format!(
-        "SELECT * FROM posts WHERE {} ORDER BY {} LIMIT {} OFFSET {}",
-        if filters.published_only { "published_at IS NOT NULL" } else { "1=1" },
+        "SELECT * FROM posts WHERE {} AND {} ORDER BY {} LIMIT {} OFFSET {}",
+        VISIBLE_POSTS,
+        IN_ACTIVE_CATEGORY,
         match filters.sort_by {
             SearchSortBy::Newest => "published_at DESC NULLS LAST, created_at DESC",
             SearchSortBy::Oldest => "published_at ASC NULLS LAST, created_at ASC", 
@@ -173,8 +178,9 @@ pub async fn search_posts(filters: SearchFilters) -> Result<SearchResults, Serve
 
     // Get total count
     let total_count = sqlx::query_scalar::<_, i64>(&format!(
-        "SELECT COUNT(*) FROM posts WHERE {}",
-        if filters.published_only { "published_at IS NOT NULL" } else { "1=1" }
+        "SELECT COUNT(*) FROM posts WHERE {} AND {}",
+        VISIBLE_POSTS,
+        IN_ACTIVE_CATEGORY
     ))
     .fetch_one(&**db)
     .await
@@ -391,11 +397,14 @@ pub fn SearchPage() -> impl IntoView {
                                         <article class="post-card">
                                             <h3>
                                                 <a href=format!("/post/{}", post_data.post.slug)>
-                                                    {post_data.post.title}
+                                                    {post_data.post.title.clone()}
                                                 </a>
                                             </h3>
-                                            <p class="post-summary">{post_data.post.summary}</p>
+                                            <p class="post-summary">{post_data.post.description()}</p>
                                             <div class="post-meta">
+                                                {post_data.post.reading_time().map(|r| view! {
+                                                    <span class="reading-time">{r}</span>
+                                                })}
                                                 <span class="views">{format!("{} views", post_data.post.views_count)}</span>
                                                 <span class="likes">{format!("{} likes", post_data.post.likes_count)}</span>
                                             </div>
@@ -0,0 +1,628 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::*;
+use crate::models::{Category, CategoryInput, Post, Tag, TagInput};
+use crate::pages::{home::PostList, use_canonical_path};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    categories::CategoryService,
+    db::Db,
+    error::{log_error, server_error, validation::validate_input},
+    guards::{ensure_role, require_role, require_scope},
+    models::{TokenScope, UserRole},
+    slugs::permanent_redirect,
+    tags::TagService,
+};
+
+const DEFAULT_CATEGORY_COLOR: &str = "#3B82F6";
+
+/// An active category and its live posts; former slugs redirect
+#[server(GetCategoryPage, "/api")]
+pub async fn get_category_page(slug: String) -> Result<(Category, Vec<Post>), ServerFnError> {
+    let db = expect_context::<Db>();
+    let service = CategoryService::new();
+
+    let category = service.resolve(&db, &slug).await.map_err(|e| {
+        log_error(&e, &format!("Failed to load category: {}", slug));
+        server_error(e)
+    })?;
+    if category.slug != slug {
+        permanent_redirect(&format!("/category/{}", category.slug));
+    }
+
+    let posts = service.posts(&db, category.id).await.map_err(|e| {
+        log_error(&e, "Failed to load category posts");
+        server_error(e)
+    })?;
+    Ok((category, posts))
+}
+
+/// A tag and its live posts; former and merged slugs redirect
+#[server(GetTagPage, "/api")]
+pub async fn get_tag_page(slug: String) -> Result<(Tag, Vec<Post>), ServerFnError> {
+    let db = expect_context::<Db>();
+    let service = TagService::new();
+
+    let tag = service.resolve(&db, &slug).await.map_err(|e| {
+        log_error(&e, &format!("Failed to load tag: {}", slug));
+        server_error(e)
+    })?;
+    if tag.slug != slug {
+        permanent_redirect(&format!("/tag/{}", tag.slug));
+    }
+
+    let posts = service.posts(&db, tag.id).await.map_err(|e| {
+        log_error(&e, "Failed to load tag posts");
+        server_error(e)
+    })?;
+    Ok((tag, posts))
+}
+
+#[server(GetAllCategories, "/api")]
+pub async fn get_all_categories() -> Result<Vec<Category>, ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    CategoryService::new().list_all(&db).await.map_err(|e| {
+        log_error(&e, "Failed to load categories");
+        server_error(e)
+    })
+}
+
+/// Create a category, or update it when `category_id` is given
+#[server(SaveCategory, "/api")]
+pub async fn save_category(category_id: Option<i64>, input: CategoryInput) -> Result<Category, ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    let service = CategoryService::new();
+    let result = match category_id {
+        Some(category_id) => service.update(&db, category_id, &input).await,
+        None => service.create(&db, &input).await,
+    };
+    result.map_err(|e| {
+        log_error(&e, "Failed to save category");
+        server_error(e)
+    })
+}
+
+#[server(DeleteCategory, "/api")]
+pub async fn delete_category(category_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    require_role(UserRole::Admin).await.map_err(server_error)?;
+
+    CategoryService::new().delete(&db, category_id).await.map_err(|e| {
+        log_error(&e, "Failed to delete category");
+        server_error(e)
+    })
+}
+
+#[server(GetAllTags, "/api")]
+pub async fn get_all_tags() -> Result<Vec<Tag>, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ManageTags).await.map_err(server_error)?;
+    ensure_role(&user, &UserRole::Admin).map_err(server_error)?;
+
+    TagService::new().list_all(&db).await.map_err(|e| {
+        log_error(&e, "Failed to load tags");
+        server_error(e)
+    })
+}
+
+/// Create a tag, or rename it when `tag_id` is given
+#[server(SaveTag, "/api")]
+pub async fn save_tag(tag_id: Option<i64>, input: TagInput) -> Result<Tag, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ManageTags).await.map_err(server_error)?;
+    ensure_role(&user, &UserRole::Admin).map_err(server_error)?;
+
+    validate_input(&input).map_err(server_error)?;
+
+    let service = TagService::new();
+    let result = match tag_id {
+        Some(tag_id) => service.update(&db, tag_id, &input).await,
+        None => service.create(&db, &input).await,
+    };
+    result.map_err(|e| {
+        log_error(&e, "Failed to save tag");
+        server_error(e)
+    })
+}
+
+#[server(DeleteTag, "/api")]
+pub async fn delete_tag(tag_id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ManageTags).await.map_err(server_error)?;
+    ensure_role(&user, &UserRole::Admin).map_err(server_error)?;
+
+    TagService::new().delete(&db, tag_id).await.map_err(|e| {
+        log_error(&e, "Failed to delete tag");
+        server_error(e)
+    })
+}
+
+#[server(MergeTags, "/api")]
+pub async fn merge_tags(source_id: i64, target_id: i64) -> Result<Tag, ServerFnError> {
+    let db = expect_context::<Db>();
+    let user = require_scope(TokenScope::ManageTags).await.map_err(server_error)?;
+    ensure_role(&user, &UserRole::Admin).map_err(server_error)?;
+
+    TagService::new().merge(&db, source_id, target_id).await.map_err(|e| {
+        log_error(&e, "Failed to merge tags");
+        server_error(e)
+    })
+}
+
+#[component]
+pub fn CategoryPage() -> impl IntoView {
+    let params = use_params_map();
+    let slug = move || params.with(|m| m.get("slug").cloned().unwrap_or_default());
+
+    let page = create_resource(slug, |slug| async move { get_category_page(slug).await });
+    use_canonical_path(
+        move || format!("/category/{}", slug()),
+        Signal::derive(move || page.get().and_then(Result::ok).map(|(c, _)| format!("/category/{}", c.slug))),
+    );
+
+    view! {
+        <section class="home">
+            <Suspense fallback=move || view! { <p class="loading">"Loading posts..."</p> }>
+                {move || page.get().map(|res| match res {
+                    Ok((category, posts)) => view! {
+                        <Title text=format!("{} - Taleji", category.name) />
+                        <h1>{category.name}</h1>
+                        {category.description.map(|d| view! { <p class="post-summary">{d}</p> })}
+                        {if posts.is_empty() {
+                            view! { <p class="empty">"No posts in this category yet."</p> }.into_view()
+                        } else {
+                            view! { <PostList posts=posts /> }.into_view()
+                        }}
+                    }.into_view(),
+                    Err(e) => view! {
+                        <div class="error-page">
+                            <h1>"Category not found"</h1>
+                            <p>{format!("Error: {e}")}</p>
+                            <a href="/" class="back-link">"← Back to all posts"</a>
+                        </div>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+pub fn TagPage() -> impl IntoView {
+    let params = use_params_map();
+    let slug = move || params.with(|m| m.get("slug").cloned().unwrap_or_default());
+
+    let page = create_resource(slug, |slug| async move { get_tag_page(slug).await });
+    use_canonical_path(
+        move || format!("/tag/{}", slug()),
+        Signal::derive(move || page.get().and_then(Result::ok).map(|(t, _)| format!("/tag/{}", t.slug))),
+    );
+
+    view! {
+        <section class="home">
+            <Suspense fallback=move || view! { <p class="loading">"Loading posts..."</p> }>
+                {move || page.get().map(|res| match res {
+                    Ok((tag, posts)) => view! {
+                        <Title text=format!("#{} - Taleji", tag.name) />
+                        <h1>{format!("#{}", tag.name)}</h1>
+                        {tag.description.map(|d| view! { <p class="post-summary">{d}</p> })}
+                        {if posts.is_empty() {
+                            view! { <p class="empty">"No posts with this tag yet."</p> }.into_view()
+                        } else {
+                            view! { <PostList posts=posts /> }.into_view()
+                        }}
+                    }.into_view(),
+                    Err(e) => view! {
+                        <div class="error-page">
+                            <h1>"Tag not found"</h1>
+                            <p>{format!("Error: {e}")}</p>
+                            <a href="/" class="back-link">"← Back to all posts"</a>
+                        </div>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+fn new_category_input() -> CategoryInput {
+    CategoryInput {
+        color: DEFAULT_CATEGORY_COLOR.to_string(),
+        is_active: true,
+        ..Default::default()
+    }
+}
+
+/// Admin panel for creating, editing, deactivating and deleting categories
+#[component]
+pub fn CategoryAdmin() -> impl IntoView {
+    let editing = create_rw_signal(None::<i64>);
+    let form = create_rw_signal(new_category_input());
+
+    let save = create_action(move |(category_id, input): &(Option<i64>, CategoryInput)| {
+        let (category_id, input) = (*category_id, input.clone());
+        async move { save_category(category_id, input).await }
+    });
+    let delete = create_action(move |category_id: &i64| {
+        let category_id = *category_id;
+        async move { delete_category(category_id).await }
+    });
+    let categories = create_resource(
+        move || (save.version().get(), delete.version().get()),
+        |_| async { get_all_categories().await },
+    );
+
+    create_effect(move |_| {
+        if let Some(Ok(_)) = save.value().get() {
+            editing.set(None);
+            form.set(new_category_input());
+        }
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        save.dispatch((editing.get_untracked(), form.get_untracked()));
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Categories"</h2>
+            <p>"Deactivated categories and their posts are hidden from readers and search."</p>
+
+            {move || save.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+            {move || delete.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+
+            <form on:submit=on_submit class="token-form">
+                <div class="form-group">
+                    <label for="category-name">"Name"</label>
+                    <input
+                        type="text"
+                        id="category-name"
+                        required
+                        maxlength="100"
+                        prop:value=move || form.with(|f| f.name.clone())
+                        on:input=move |ev| form.update(|f| f.name = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="category-slug">"Slug (optional)"</label>
+                    <input
+                        type="text"
+                        id="category-slug"
+                        maxlength="100"
+                        pattern="[a-z0-9\\-]*"
+                        prop:value=move || form.with(|f| f.slug.clone())
+                        on:input=move |ev| form.update(|f| f.slug = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="category-description">"Description"</label>
+                    <input
+                        type="text"
+                        id="category-description"
+                        maxlength="500"
+                        prop:value=move || form.with(|f| f.description.clone())
+                        on:input=move |ev| form.update(|f| f.description = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="category-color">"Color"</label>
+                    <input
+                        type="color"
+                        id="category-color"
+                        prop:value=move || form.with(|f| f.color.clone())
+                        on:input=move |ev| form.update(|f| f.color = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group checkbox">
+                    <input
+                        type="checkbox"
+                        id="category-active"
+                        prop:checked=move || form.with(|f| f.is_active)
+                        on:change=move |ev| form.update(|f| f.is_active = event_target_checked(&ev))
+                    />
+                    <label for="category-active">"Active"</label>
+                </div>
+                <div class="editor-actions">
+                    <button type="submit" class="btn btn-primary" disabled=move || save.pending().get()>
+                        {move || if editing.get().is_some() { "Save category" } else { "Add category" }}
+                    </button>
+                    <Show when=move || editing.get().is_some()>
+                        <button
+                            type="button"
+                            class="btn"
+                            on:click=move |_| {
+                                editing.set(None);
+                                form.set(new_category_input());
+                            }
+                        >
+                            "Cancel"
+                        </button>
+                    </Show>
+                </div>
+            </form>
+
+            <Transition fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || categories.get().map(|res| match res {
+                    Ok(categories) if categories.is_empty() => view! {
+                        <p class="empty">"No categories yet."</p>
+                    }.into_view(),
+                    Ok(categories) => view! {
+                        <ul class="taxonomy-list">
+                            {categories.into_iter().map(|category| {
+                                let id = category.id;
+                                let name = category.name.clone();
+                                let href = format!("/category/{}", category.slug);
+                                let input = CategoryInput {
+                                    name: category.name.clone(),
+                                    slug: category.slug.clone(),
+                                    description: category.description.clone().unwrap_or_default(),
+                                    color: category.color.clone(),
+                                    is_active: category.is_active,
+                                };
+                                view! {
+                                    <li class:inactive=!category.is_active>
+                                        <span class="swatch" style=format!("background-color: {}", category.color)></span>
+                                        <a href=href>{category.name}</a>
+                                        " "
+                                        <code>{category.slug}</code>
+                                        {(!category.is_active).then(|| view! { <span class="badge">"inactive"</span> })}
+                                        <button
+                                            type="button"
+                                            class="btn"
+                                            on:click=move |_| {
+                                                editing.set(Some(id));
+                                                form.set(input.clone());
+                                            }
+                                        >
+                                            "Edit"
+                                        </button>
+                                        <button
+                                            type="button"
+                                            class="btn btn-danger"
+                                            on:click=move |_| {
+                                                let message = format!("Delete the category '{name}'? Its posts become uncategorized.");
+                                                if window().confirm_with_message(&message).unwrap_or(false) {
+                                                    delete.dispatch(id);
+                                                }
+                                            }
+                                        >
+                                            "Delete"
+                                        </button>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Transition>
+        </section>
+    }
+}
+
+/// Admin panel for creating, renaming, merging and deleting tags
+#[component]
+pub fn TagAdmin() -> impl IntoView {
+    let editing = create_rw_signal(None::<i64>);
+    let form = create_rw_signal(TagInput::default());
+    let (merge_source, set_merge_source) = create_signal(None::<i64>);
+    let (merge_target, set_merge_target) = create_signal(None::<i64>);
+
+    let save = create_action(move |(tag_id, input): &(Option<i64>, TagInput)| {
+        let (tag_id, input) = (*tag_id, input.clone());
+        async move { save_tag(tag_id, input).await }
+    });
+    let delete = create_action(move |tag_id: &i64| {
+        let tag_id = *tag_id;
+        async move { delete_tag(tag_id).await }
+    });
+    let merge = create_action(move |(source_id, target_id): &(i64, i64)| {
+        let (source_id, target_id) = (*source_id, *target_id);
+        async move { merge_tags(source_id, target_id).await }
+    });
+    let tags = create_resource(
+        move || (save.version().get(), delete.version().get(), merge.version().get()),
+        |_| async { get_all_tags().await },
+    );
+
+    create_effect(move |_| {
+        if let Some(Ok(_)) = save.value().get() {
+            editing.set(None);
+            form.set(TagInput::default());
+        }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(_)) = merge.value().get() {
+            set_merge_source.set(None);
+            set_merge_target.set(None);
+        }
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        save.dispatch((editing.get_untracked(), form.get_untracked()));
+    };
+
+    let on_merge = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        if let (Some(source_id), Some(target_id)) = (merge_source.get_untracked(), merge_target.get_untracked()) {
+            let message = "Merge these tags? The first tag is removed and its posts and links move to the second.";
+            if window().confirm_with_message(message).unwrap_or(false) {
+                merge.dispatch((source_id, target_id));
+            }
+        }
+    };
+
+    let tag_options = move |selected: Option<i64>| {
+        tags.get()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tag| view! {
+                <option value=tag.id.to_string() selected=Some(tag.id) == selected>
+                    {format!("{} ({})", tag.name, tag.usage_count)}
+                </option>
+            })
+            .collect_view()
+    };
+
+    view! {
+        <section class="account-section">
+            <h2>"Tags"</h2>
+
+            {move || save.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+            {move || delete.value().get().and_then(Result::err).map(|e| view! {
+                <p class="error-message">{e.to_string()}</p>
+            })}
+
+            <form on:submit=on_submit class="token-form">
+                <div class="form-group">
+                    <label for="tag-name">"Name"</label>
+                    <input
+                        type="text"
+                        id="tag-name"
+                        required
+                        maxlength="50"
+                        prop:value=move || form.with(|f| f.name.clone())
+                        on:input=move |ev| form.update(|f| f.name = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="tag-slug">"Slug (optional)"</label>
+                    <input
+                        type="text"
+                        id="tag-slug"
+                        maxlength="100"
+                        pattern="[a-z0-9\\-]*"
+                        prop:value=move || form.with(|f| f.slug.clone())
+                        on:input=move |ev| form.update(|f| f.slug = event_target_value(&ev))
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="tag-description">"Description"</label>
+                    <input
+                        type="text"
+                        id="tag-description"
+                        maxlength="500"
+                        prop:value=move || form.with(|f| f.description.clone())
+                        on:input=move |ev| form.update(|f| f.description = event_target_value(&ev))
+                    />
+                </div>
+                <div class="editor-actions">
+                    <button type="submit" class="btn btn-primary" disabled=move || save.pending().get()>
+                        {move || if editing.get().is_some() { "Save tag" } else { "Add tag" }}
+                    </button>
+                    <Show when=move || editing.get().is_some()>
+                        <button
+                            type="button"
+                            class="btn"
+                            on:click=move |_| {
+                                editing.set(None);
+                                form.set(TagInput::default());
+                            }
+                        >
+                            "Cancel"
+                        </button>
+                    </Show>
+                </div>
+            </form>
+
+            <h3>"Merge tags"</h3>
+            {move || merge.value().get().map(|res| match res {
+                Ok(tag) => view! {
+                    <p class="success-message">{format!("Merged into '{}'.", tag.name)}</p>
+                }.into_view(),
+                Err(e) => view! { <p class="error-message">{e.to_string()}</p> }.into_view(),
+            })}
+            <form on:submit=on_merge class="inline-form">
+                <select
+                    required
+                    on:change=move |ev| set_merge_source.set(event_target_value(&ev).parse().ok())
+                >
+                    <option value="">"Merge…"</option>
+                    {move || tag_options(merge_source.get_untracked())}
+                </select>
+                <select
+                    required
+                    on:change=move |ev| set_merge_target.set(event_target_value(&ev).parse().ok())
+                >
+                    <option value="">"…into"</option>
+                    {move || tag_options(merge_target.get_untracked())}
+                </select>
+                <button type="submit" class="btn" disabled=move || merge.pending().get()>"Merge"</button>
+            </form>
+
+            <Transition fallback=move || view! { <p class="loading">"Loading..."</p> }>
+                {move || tags.get().map(|res| match res {
+                    Ok(tags) if tags.is_empty() => view! {
+                        <p class="empty">"No tags yet."</p>
+                    }.into_view(),
+                    Ok(tags) => view! {
+                        <ul class="taxonomy-list">
+                            {tags.into_iter().map(|tag| {
+                                let id = tag.id;
+                                let name = tag.name.clone();
+                                let href = format!("/tag/{}", tag.slug);
+                                let input = TagInput {
+                                    name: tag.name.clone(),
+                                    slug: tag.slug.clone(),
+                                    description: tag.description.clone().unwrap_or_default(),
+                                };
+                                view! {
+                                    <li>
+                                        <a href=href>{tag.name}</a>
+                                        " "
+                                        <code>{tag.slug}</code>
+                                        <span class="meta">{format!("{} published posts", tag.usage_count)}</span>
+                                        <button
+                                            type="button"
+                                            class="btn"
+                                            on:click=move |_| {
+                                                editing.set(Some(id));
+                                                form.set(input.clone());
+                                            }
+                                        >
+                                            "Edit"
+                                        </button>
+                                        <button
+                                            type="button"
+                                            class="btn btn-danger"
+                                            on:click=move |_| {
+                                                let message = format!("Delete the tag '{name}' and remove it from every post?");
+                                                if window().confirm_with_message(&message).unwrap_or(false) {
+                                                    delete.dispatch(id);
+                                                }
+                                            }
+                                        >
+                                            "Delete"
+                                        </button>
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! {
+                        <p class="error">{format!("Error: {e}")}</p>
+                    }.into_view(),
+                })}
+            </Transition>
+        </section>
+    }
+}
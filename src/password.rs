@@ -0,0 +1,200 @@
+#[cfg(feature = "ssr")]
+use crate::error::{AppError, AppResult};
+#[cfg(feature = "ssr")]
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2, Params, Version,
+};
+
+/// Algorithm used for newly hashed passwords
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordAlgorithm {
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+    Bcrypt {
+        cost: u32,
+    },
+}
+
+#[cfg(feature = "ssr")]
+impl PasswordAlgorithm {
+    /// Read the algorithm from `PASSWORD_HASH_ALGORITHM` ("argon2id" or "bcrypt")
+    pub fn from_env() -> Self {
+        fn env_u32(name: &str, default: u32) -> u32 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        match std::env::var("PASSWORD_HASH_ALGORITHM").as_deref() {
+            Ok("bcrypt") => PasswordAlgorithm::Bcrypt {
+                cost: env_u32("BCRYPT_COST", bcrypt::DEFAULT_COST),
+            },
+            // OWASP baseline for Argon2id: 19 MiB, 2 iterations, 1 lane
+            _ => PasswordAlgorithm::Argon2id {
+                memory_kib: env_u32("ARGON2_MEMORY_KIB", 19_456),
+                iterations: env_u32("ARGON2_ITERATIONS", 2),
+                parallelism: env_u32("ARGON2_PARALLELISM", 1),
+            },
+        }
+    }
+}
+
+/// Hashes and verifies passwords, recognising both Argon2 and legacy bcrypt hashes
+#[cfg(feature = "ssr")]
+pub struct PasswordService {
+    algorithm: PasswordAlgorithm,
+}
+
+#[cfg(feature = "ssr")]
+impl PasswordService {
+    pub fn new() -> Self {
+        Self::with_algorithm(PasswordAlgorithm::from_env())
+    }
+
+    pub fn with_algorithm(algorithm: PasswordAlgorithm) -> Self {
+        Self { algorithm }
+    }
+
+    /// Hash a password with the configured algorithm
+    pub fn hash(&self, password: &str) -> AppResult<String> {
+        match &self.algorithm {
+            PasswordAlgorithm::Argon2id { memory_kib, iterations, parallelism } => {
+                let params = Params::new(*memory_kib, *iterations, *parallelism, None).map_err(|e| {
+                    tracing::error!("Invalid Argon2 parameters: {}", e);
+                    AppError::Config("Invalid Argon2 parameters".to_string())
+                })?;
+                let salt = SaltString::generate(&mut OsRng);
+
+                Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password(password.as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+                    .map_err(|e| {
+                        tracing::error!("Failed to hash password: {}", e);
+                        AppError::Internal("Password hashing failed".to_string())
+                    })
+            }
+            PasswordAlgorithm::Bcrypt { cost } => bcrypt::hash(password, *cost).map_err(|e| {
+                tracing::error!("Failed to hash password: {}", e);
+                AppError::Internal("Password hashing failed".to_string())
+            }),
+        }
+    }
+
+    /// Verify a password against a stored hash of either supported format
+    pub fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        if is_bcrypt_hash(hash) {
+            return bcrypt::verify(password, hash).map_err(|e| {
+                tracing::error!("Failed to verify password: {}", e);
+                AppError::Internal("Password verification failed".to_string())
+            });
+        }
+
+        let parsed = PasswordHash::new(hash).map_err(|e| {
+            tracing::error!("Unrecognised password hash format: {}", e);
+            AppError::Internal("Password verification failed".to_string())
+        })?;
+
+        // Argon2 takes its parameters from the stored hash when verifying
+        Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok())
+    }
+
+    /// Whether a stored hash uses a different algorithm or weaker parameters
+    /// than the current configuration; stronger hashes are left alone
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        match &self.algorithm {
+            PasswordAlgorithm::Argon2id { memory_kib, iterations, parallelism } => {
+                let Ok(parsed) = PasswordHash::new(hash) else {
+                    return true;
+                };
+                if parsed.algorithm != argon2::Algorithm::Argon2id.ident() {
+                    return true;
+                }
+                match Params::try_from(&parsed) {
+                    Ok(params) => {
+                        params.m_cost() < *memory_kib
+                            || params.t_cost() < *iterations
+                            || params.p_cost() < *parallelism
+                    }
+                    Err(_) => true,
+                }
+            }
+            PasswordAlgorithm::Bcrypt { cost } => match hash.parse::<bcrypt::HashParts>() {
+                Ok(parts) if is_bcrypt_hash(hash) => parts.get_cost() < *cost,
+                _ => true,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for PasswordService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn is_bcrypt_hash(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn argon2(memory_kib: u32, iterations: u32, parallelism: u32) -> PasswordService {
+        PasswordService::with_algorithm(PasswordAlgorithm::Argon2id { memory_kib, iterations, parallelism })
+    }
+
+    #[test]
+    fn bcrypt_hash_is_upgraded_to_argon2() {
+        let hash = PasswordService::with_algorithm(PasswordAlgorithm::Bcrypt { cost: 4 })
+            .hash("hunter22")
+            .unwrap();
+        let service = argon2(1024, 1, 1);
+
+        assert!(service.verify("hunter22", &hash).unwrap());
+        assert!(service.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn equal_parameters_are_kept() {
+        let service = argon2(1024, 2, 1);
+        let hash = service.hash("hunter22").unwrap();
+
+        assert!(service.verify("hunter22", &hash).unwrap());
+        assert!(!service.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn weaker_parameters_are_rehashed() {
+        let hash = argon2(1024, 1, 1).hash("hunter22").unwrap();
+
+        assert!(argon2(2048, 1, 1).needs_rehash(&hash));
+        assert!(argon2(1024, 2, 1).needs_rehash(&hash));
+        assert!(argon2(1024, 1, 2).needs_rehash(&hash));
+    }
+
+    #[test]
+    fn stronger_parameters_are_not_downgraded() {
+        let hash = argon2(2048, 2, 2).hash("hunter22").unwrap();
+        assert!(!argon2(1024, 1, 1).needs_rehash(&hash));
+
+        let bcrypt_hash = PasswordService::with_algorithm(PasswordAlgorithm::Bcrypt { cost: 5 })
+            .hash("hunter22")
+            .unwrap();
+        assert!(!PasswordService::with_algorithm(PasswordAlgorithm::Bcrypt { cost: 4 }).needs_rehash(&bcrypt_hash));
+        assert!(PasswordService::with_algorithm(PasswordAlgorithm::Bcrypt { cost: 6 }).needs_rehash(&bcrypt_hash));
+    }
+}
@@ -0,0 +1,343 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    markdown::{markdown_to_html, text_stats},
+    models::{CreatePostInput, Post, UpdatePostInput, User},
+    revisions::append_revision,
+    slugs::{slugify, with_collision_suffix},
+    tags::{refresh_for_posts, refresh_usage_counts, set_post_tags},
+};
+#[cfg(feature = "ssr")]
+use sqlx::PgConnection;
+
+/// Map a unique violation on `posts.slug` to a validation error
+#[cfg(feature = "ssr")]
+fn slug_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            AppError::Validation("A post with that slug already exists".to_string())
+        }
+        _ => AppError::Database(e),
+    }
+}
+
+/// A free slug derived from the title. Former slugs count as taken so
+/// their redirects keep working.
+#[cfg(feature = "ssr")]
+async fn generate_slug(conn: &mut PgConnection, title: &str) -> AppResult<String> {
+    let base = slugify(title);
+    let base = if base.is_empty() { "post".to_string() } else { base };
+
+    let taken = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT slug FROM posts WHERE slug = $1 OR slug LIKE $1 || '-%'
+        UNION
+        SELECT old_slug FROM post_slug_history WHERE old_slug = $1 OR old_slug LIKE $1 || '-%'
+        "#,
+    )
+    .bind(&base)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(with_collision_suffix(&base, &taken))
+}
+
+/// Take a slug that is about to go live. A post may go back to one of its
+/// own former slugs, dropping that redirect; another post's former slug is
+/// refused so links to that post keep working.
+#[cfg(feature = "ssr")]
+async fn claim_slug(conn: &mut PgConnection, post_id: Option<i64>, slug: &str) -> AppResult<()> {
+    let holder = sqlx::query_scalar::<_, i64>("SELECT post_id FROM post_slug_history WHERE old_slug = $1 FOR UPDATE")
+        .bind(slug)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    match holder {
+        None => Ok(()),
+        Some(holder) if Some(holder) == post_id => {
+            sqlx::query("DELETE FROM post_slug_history WHERE old_slug = $1")
+                .bind(slug)
+                .execute(&mut *conn)
+                .await
+                .map_err(AppError::Database)?;
+            Ok(())
+        }
+        Some(_) => Err(AppError::Validation(
+            "That slug redirects to another post; choose a different one".to_string(),
+        )),
+    }
+}
+
+/// Keep a renamed post's previous slug as a redirect to it
+#[cfg(feature = "ssr")]
+async fn record_slug_change(conn: &mut PgConnection, post_id: i64, old_slug: &str, new_slug: &str) -> AppResult<()> {
+    claim_slug(conn, Some(post_id), new_slug).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO post_slug_history (old_slug, post_id) VALUES ($1, $2)
+        ON CONFLICT (old_slug) DO UPDATE SET post_id = EXCLUDED.post_id, created_at = NOW()
+        "#,
+    )
+    .bind(old_slug)
+    .bind(post_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+    Ok(())
+}
+
+/// Reject schedules where the post would expire before it goes live
+#[cfg(feature = "ssr")]
+fn check_schedule(post: &Post) -> AppResult<()> {
+    match (post.published_at, post.unpublish_at) {
+        (Some(publish_at), Some(unpublish_at)) if unpublish_at <= publish_at => Err(AppError::Validation(
+            "The unpublish time must be after the publish time".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Writing, editing and removing posts. Authorization is left to the caller.
+#[cfg(feature = "ssr")]
+pub struct PostService;
+
+#[cfg(feature = "ssr")]
+impl PostService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Create a post along with its first revision and its tags. A blank
+    /// slug is generated from the title.
+    pub async fn create(&self, db: &Db, author: &User, input: CreatePostInput) -> AppResult<Post> {
+        let body_html = markdown_to_html(&input.body_markdown);
+        let stats = text_stats(&input.body_markdown);
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let slug = match input.slug.trim() {
+            "" => generate_slug(&mut tx, &input.title).await?,
+            slug => {
+                claim_slug(&mut tx, None, slug).await?;
+                slug.to_string()
+            }
+        };
+
+        let post = sqlx::query_as::<_, Post>(
+            r#"
+            INSERT INTO posts (
+                slug, title, summary, body_markdown, body_html, author_id, published_at, unpublish_at,
+                word_count, reading_time_minutes, excerpt
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $7 THEN COALESCE($8, NOW()) END, $9, $10, $11, $12)
+            RETURNING *
+            "#,
+        )
+        .bind(&slug)
+        .bind(input.title.trim())
+        .bind(input.summary.trim())
+        .bind(&input.body_markdown)
+        .bind(&body_html)
+        .bind(author.id)
+        .bind(input.published)
+        .bind(input.publish_at)
+        .bind(input.unpublish_at)
+        .bind(stats.word_count)
+        .bind(stats.reading_time_minutes)
+        .bind(&stats.excerpt)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(slug_conflict)?;
+
+        check_schedule(&post)?;
+        set_post_tags(&mut tx, post.id, &input.tags).await?;
+        append_revision(&mut tx, &post, author.id, None).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(post_id = post.id, author_id = author.id, published = input.published, "Post created");
+        Ok(post)
+    }
+
+    /// Apply the fields present in `input` and record the result as a new
+    /// revision. Publishing without a date keeps a live post's publication
+    /// date and makes a scheduled one go live now; unpublishing clears it.
+    /// A changed slug leaves the old one behind as a redirect.
+    pub async fn update(&self, db: &Db, editor: &User, post_id: i64, input: UpdatePostInput) -> AppResult<Post> {
+        let body_html = input.body_markdown.as_deref().map(markdown_to_html);
+        let stats = input.body_markdown.as_deref().map(text_stats);
+
+        // The row lock also serializes revision numbering
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let old_slug = sqlx::query_scalar::<_, String>("SELECT slug FROM posts WHERE id = $1 FOR UPDATE")
+            .bind(post_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::Database)?
+            .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+        let new_slug = input.slug.as_deref().map(str::trim).filter(|s| *s != old_slug);
+        if let Some(new_slug) = new_slug {
+            record_slug_change(&mut tx, post_id, &old_slug, new_slug).await?;
+        }
+
+        let post = sqlx::query_as::<_, Post>(
+            r#"
+            UPDATE posts
+            SET slug = COALESCE($9, slug),
+                title = COALESCE($2, title),
+                summary = COALESCE($3, summary),
+                body_markdown = COALESCE($4, body_markdown),
+                body_html = COALESCE($5, body_html),
+                word_count = COALESCE($10, word_count),
+                reading_time_minutes = COALESCE($11, reading_time_minutes),
+                excerpt = COALESCE($12, excerpt),
+                published_at = CASE
+                    WHEN $6::boolean IS NULL THEN published_at
+                    WHEN NOT $6 THEN NULL
+                    WHEN $7::timestamptz IS NOT NULL THEN $7
+                    WHEN published_at IS NULL OR published_at > NOW() THEN NOW()
+                    ELSE published_at
+                END,
+                unpublish_at = CASE WHEN $6::boolean IS NULL THEN unpublish_at ELSE $8 END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(post_id)
+        .bind(input.title.as_deref().map(str::trim))
+        .bind(input.summary.as_deref().map(str::trim))
+        .bind(&input.body_markdown)
+        .bind(&body_html)
+        .bind(input.published)
+        .bind(input.publish_at)
+        .bind(input.unpublish_at)
+        .bind(new_slug)
+        .bind(stats.as_ref().map(|s| s.word_count))
+        .bind(stats.as_ref().map(|s| s.reading_time_minutes))
+        .bind(stats.as_ref().map(|s| s.excerpt.as_str()))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(slug_conflict)?;
+
+        check_schedule(&post)?;
+        // Publication may have changed, so recount even when the tags didn't
+        match &input.tags {
+            Some(names) => set_post_tags(&mut tx, post.id, names).await?,
+            None => refresh_for_posts(&mut tx, &[post.id]).await?,
+        }
+        let change_summary = input.change_summary.as_deref().map(str::trim).filter(|s| !s.is_empty());
+        append_revision(&mut tx, &post, editor.id, change_summary).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(post_id = post.id, editor_id = editor.id, "Post updated");
+        Ok(post)
+    }
+
+    pub async fn delete(&self, db: &Db, post_id: i64) -> AppResult<()> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let tag_ids = sqlx::query_scalar::<_, i64>("SELECT tag_id FROM post_tags WHERE post_id = $1")
+            .bind(post_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        let result = sqlx::query("DELETE FROM posts WHERE id = $1")
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Post not found".to_string()));
+        }
+
+        refresh_usage_counts(&mut tx, &tag_ids).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(post_id = post_id, "Post deleted");
+        Ok(())
+    }
+
+    /// Recompute word counts, reading times and excerpts of every post, for
+    /// posts saved before they were tracked. Returns the number of posts.
+    pub async fn refresh_text_stats(&self, db: &Db) -> AppResult<u64> {
+        let posts = sqlx::query_as::<_, (i64, String)>("SELECT id, body_markdown FROM posts ORDER BY id")
+            .fetch_all(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        for (post_id, body_markdown) in &posts {
+            let stats = text_stats(body_markdown);
+            sqlx::query("UPDATE posts SET word_count = $2, reading_time_minutes = $3, excerpt = $4 WHERE id = $1")
+                .bind(post_id)
+                .bind(stats.word_count)
+                .bind(stats.reading_time_minutes)
+                .bind(&stats.excerpt)
+                .execute(&**db)
+                .await
+                .map_err(AppError::Database)?;
+        }
+
+        tracing::info!(posts = posts.len(), "Post text stats refreshed");
+        Ok(posts.len() as u64)
+    }
+
+    /// Render the stored HTML of every post and revision again from its
+    /// markdown, so rows saved by older renderers are sanitized. Returns the
+    /// number of rows.
+    pub async fn rerender_html(&self, db: &Db) -> AppResult<u64> {
+        let mut rendered = 0;
+        for table in ["posts", "post_revisions"] {
+            let rows = sqlx::query_as::<_, (i64, String)>(&format!("SELECT id, body_markdown FROM {} ORDER BY id", table))
+                .fetch_all(&**db)
+                .await
+                .map_err(AppError::Database)?;
+
+            for (id, body_markdown) in &rows {
+                sqlx::query(&format!("UPDATE {} SET body_html = $2 WHERE id = $1", table))
+                    .bind(id)
+                    .bind(markdown_to_html(body_markdown))
+                    .execute(&**db)
+                    .await
+                    .map_err(AppError::Database)?;
+            }
+            rendered += rows.len() as u64;
+        }
+
+        tracing::info!(rows = rendered, "Post HTML re-rendered");
+        Ok(rendered)
+    }
+
+    /// A post by its current or a former slug whether or not it is published,
+    /// for editing
+    pub async fn get_by_slug(&self, db: &Db, slug: &str) -> AppResult<Post> {
+        sqlx::query_as::<_, Post>(
+            r#"
+            SELECT p.* FROM posts p
+            LEFT JOIN post_slug_history h ON h.post_id = p.id AND h.old_slug = $1
+            WHERE p.slug = $1 OR h.old_slug IS NOT NULL
+            ORDER BY p.slug = $1 DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(slug)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Post with slug '{}' not found", slug)))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for PostService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
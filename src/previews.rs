@@ -0,0 +1,191 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    jwt_keys::JwtKeyring,
+    models::{Post, PreviewLink, User},
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use serde::{Deserialize, Serialize};
+
+/// Marks a signed token as a preview link so session tokens can't be used as one
+#[cfg(feature = "ssr")]
+const PREVIEW_PURPOSE: &str = "post_preview";
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Serialize, Deserialize)]
+struct PreviewClaims {
+    sub: i64, // Post ID
+    lid: i64, // Preview link ID
+    purpose: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Columns read for each listed link
+#[cfg(feature = "ssr")]
+type PreviewLinkRow = (i64, Option<String>, DateTime<Utc>, DateTime<Utc>);
+
+/// Shareable links that let anyone holding them view a post before it is
+/// published. Links are signed and expire; each is backed by a row so the
+/// author can revoke it early.
+#[cfg(feature = "ssr")]
+pub struct PreviewService {
+    max_days: i64,
+    app_base_url: String,
+}
+
+#[cfg(feature = "ssr")]
+impl PreviewService {
+    pub fn new() -> Self {
+        Self {
+            max_days: std::env::var("PREVIEW_LINK_MAX_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        }
+    }
+
+    /// The links are not stored; signing the same claims again gives a
+    /// working link for as long as the row is valid
+    fn url_for(&self, post_id: i64, link_id: i64, created_at: DateTime<Utc>, expires_at: DateTime<Utc>) -> AppResult<String> {
+        let token = JwtKeyring::global()?.encode(&PreviewClaims {
+            sub: post_id,
+            lid: link_id,
+            purpose: PREVIEW_PURPOSE.to_string(),
+            iat: created_at.timestamp(),
+            exp: expires_at.timestamp(),
+        })?;
+        Ok(format!("{}/preview/{}", self.app_base_url, token))
+    }
+
+    pub async fn create(&self, db: &Db, creator: &User, post_id: i64, expires_in_days: i64) -> AppResult<PreviewLink> {
+        if !(1..=self.max_days).contains(&expires_in_days) {
+            return Err(AppError::Validation(format!(
+                "Preview links can last between 1 and {} days",
+                self.max_days
+            )));
+        }
+
+        let (id, created_at, expires_at) = sqlx::query_as::<_, (i64, DateTime<Utc>, DateTime<Utc>)>(
+            r#"
+            INSERT INTO post_preview_links (post_id, created_by, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING id, created_at, expires_at
+            "#,
+        )
+        .bind(post_id)
+        .bind(creator.id)
+        .bind(Utc::now() + Duration::days(expires_in_days))
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(post_id = post_id, link_id = id, user_id = creator.id, "Preview link created");
+
+        Ok(PreviewLink {
+            id,
+            url: self.url_for(post_id, id, created_at, expires_at)?,
+            created_by_username: Some(creator.username.clone()),
+            expires_at,
+            created_at,
+        })
+    }
+
+    /// Links for a post that still work, newest first
+    pub async fn list(&self, db: &Db, post_id: i64) -> AppResult<Vec<PreviewLink>> {
+        let rows = sqlx::query_as::<_, PreviewLinkRow>(
+            r#"
+            SELECT l.id, u.username, l.expires_at, l.created_at
+            FROM post_preview_links l
+            LEFT JOIN users u ON u.id = l.created_by
+            WHERE l.post_id = $1 AND l.revoked_at IS NULL AND l.expires_at > NOW()
+            ORDER BY l.created_at DESC
+            "#,
+        )
+        .bind(post_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        rows.into_iter()
+            .map(|(id, created_by_username, expires_at, created_at)| {
+                Ok(PreviewLink {
+                    id,
+                    url: self.url_for(post_id, id, created_at, expires_at)?,
+                    created_by_username,
+                    expires_at,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn revoke(&self, db: &Db, post_id: i64, link_id: i64) -> AppResult<()> {
+        let result = sqlx::query(
+            "UPDATE post_preview_links SET revoked_at = NOW() WHERE id = $1 AND post_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(link_id)
+        .bind(post_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Preview link not found".to_string()));
+        }
+
+        tracing::info!(post_id = post_id, link_id = link_id, "Preview link revoked");
+        Ok(())
+    }
+
+    /// Revoke every outstanding link for a post; returns how many were revoked
+    pub async fn revoke_all(&self, db: &Db, post_id: i64) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE post_preview_links SET revoked_at = NOW() WHERE post_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(post_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(post_id = post_id, count = result.rows_affected(), "Preview links revoked");
+        Ok(result.rows_affected())
+    }
+
+    /// The post behind a preview token, whatever its publication state
+    pub async fn resolve(&self, db: &Db, token: &str) -> AppResult<Post> {
+        let invalid = || AppError::NotFound("This preview link is invalid or has expired".to_string());
+
+        let claims = JwtKeyring::global()?.decode::<PreviewClaims>(token).map_err(|_| invalid())?;
+        if claims.purpose != PREVIEW_PURPOSE {
+            return Err(invalid());
+        }
+
+        sqlx::query_as::<_, Post>(
+            r#"
+            SELECT p.* FROM posts p
+            JOIN post_preview_links l ON l.post_id = p.id
+            WHERE l.id = $1 AND p.id = $2
+              AND l.revoked_at IS NULL AND l.expires_at > NOW()
+            "#,
+        )
+        .bind(claims.lid)
+        .bind(claims.sub)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(invalid)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for PreviewService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
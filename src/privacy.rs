@@ -0,0 +1,345 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::AuthService,
+    db::Db,
+    error::{log_error, AppError, AppResult},
+    mailer::{EmailMessage, Mailer},
+    models::User,
+    security::{generate_secure_token, hash_token},
+    session::{cookie_value, SessionService, SESSION_COOKIE_NAME},
+};
+#[cfg(feature = "ssr")]
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use serde_json::{Map, Value};
+
+/// Sections of a data export, each a query returning JSON text for user `$1`
+#[cfg(feature = "ssr")]
+const EXPORT_SECTIONS: &[(&str, &str)] = &[
+    (
+        "account",
+        r#"
+        SELECT row_to_json(u)::text FROM (
+            SELECT id, username, email, display_name, bio, avatar_url, role, email_verified,
+                   totp_enabled, created_at, updated_at
+            FROM users WHERE id = $1
+        ) u
+        "#,
+    ),
+    (
+        "posts",
+        r#"
+        SELECT COALESCE(json_agg(p ORDER BY p.created_at), '[]'::json)::text FROM (
+            SELECT p.id, p.slug, p.title, p.summary, p.body_markdown, p.published_at,
+                   c.name AS category,
+                   ARRAY(SELECT t.name FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                         WHERE pt.post_id = p.id ORDER BY t.name) AS tags,
+                   p.meta_title, p.meta_description, p.views_count, p.likes_count,
+                   p.created_at, p.updated_at
+            FROM posts p
+            LEFT JOIN categories c ON c.id = p.category_id
+            WHERE p.author_id = $1
+        ) p
+        "#,
+    ),
+    (
+        "post_revisions",
+        r#"
+        SELECT COALESCE(json_agg(r ORDER BY r.post_id, r.revision_number), '[]'::json)::text FROM (
+            SELECT r.post_id, r.revision_number, r.title, r.summary, r.body_markdown,
+                   r.change_summary, r.created_at
+            FROM post_revisions r
+            JOIN posts p ON p.id = r.post_id
+            WHERE p.author_id = $1 OR r.created_by = $1
+        ) r
+        "#,
+    ),
+    (
+        "sessions",
+        r#"
+        SELECT COALESCE(json_agg(s ORDER BY s.created_at), '[]'::json)::text FROM (
+            SELECT created_at, last_accessed, expires_at, user_agent, host(ip_address) AS ip_address
+            FROM user_sessions WHERE user_id = $1
+        ) s
+        "#,
+    ),
+    (
+        "linked_identities",
+        r#"
+        SELECT COALESCE(json_agg(i ORDER BY i.created_at), '[]'::json)::text FROM (
+            SELECT provider, subject, email, created_at, last_login_at
+            FROM user_identities WHERE user_id = $1
+        ) i
+        "#,
+    ),
+    (
+        "access_tokens",
+        r#"
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)::text FROM (
+            SELECT name, token_prefix, scopes, expires_at, last_used_at,
+                   host(last_used_ip) AS last_used_ip, revoked_at, created_at
+            FROM personal_access_tokens WHERE user_id = $1
+        ) t
+        "#,
+    ),
+    (
+        "login_attempts",
+        r#"
+        SELECT COALESCE(json_agg(a ORDER BY a.attempted_at), '[]'::json)::text FROM (
+            SELECT identifier, host(ip_address) AS ip_address, succeeded, attempted_at
+            FROM login_attempts WHERE user_id = $1
+        ) a
+        "#,
+    ),
+    (
+        "invite_redemptions",
+        r#"
+        SELECT COALESCE(json_agg(r ORDER BY r.redeemed_at), '[]'::json)::text FROM (
+            SELECT c.code_prefix, c.role, r.redeemed_at
+            FROM invite_redemptions r
+            JOIN invite_codes c ON c.id = r.invite_id
+            WHERE r.user_id = $1
+        ) r
+        "#,
+    ),
+];
+
+/// Personal data export and self-service account deletion.
+///
+/// Deleting an account deactivates it immediately; it is purged once the grace
+/// period has passed unless the owner restores it through the emailed link.
+#[cfg(feature = "ssr")]
+pub struct PrivacyService {
+    grace_days: i64,
+    deleted_posts_owner: Option<String>,
+    app_base_url: String,
+}
+
+#[cfg(feature = "ssr")]
+impl PrivacyService {
+    pub fn new() -> Self {
+        Self {
+            grace_days: std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(14),
+            deleted_posts_owner: std::env::var("DELETED_POSTS_OWNER")
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+            app_base_url: std::env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+        }
+    }
+
+    /// Everything stored about a user as one JSON document. Secrets such as
+    /// password hashes, TOTP seeds and token hashes are left out.
+    pub async fn export(&self, db: &Db, user_id: i64) -> AppResult<Value> {
+        let mut export = Map::new();
+        export.insert("format".to_string(), Value::from("taleji-export/1"));
+        export.insert("exported_at".to_string(), Value::from(Utc::now().to_rfc3339()));
+
+        for (section, query) in EXPORT_SECTIONS {
+            let json = sqlx::query_scalar::<_, Option<String>>(query)
+                .bind(user_id)
+                .fetch_one(&**db)
+                .await
+                .map_err(AppError::Database)?;
+
+            let value = match json {
+                Some(json) => serde_json::from_str(&json)
+                    .map_err(|e| AppError::Internal(format!("Invalid export section {}: {}", section, e)))?,
+                None => Value::Null,
+            };
+            export.insert(section.to_string(), value);
+        }
+
+        tracing::info!(user_id = user_id, "Personal data exported");
+        Ok(Value::Object(export))
+    }
+
+    /// Deactivate the account, sign it out everywhere and schedule the purge
+    pub async fn request_deletion(&self, db: &Db, mailer: &dyn Mailer, user: &User, password: &str, session_id: Option<&str>) -> AppResult<DateTime<Utc>> {
+        let auth_service = AuthService::new();
+        auth_service.confirm_identity(db, user, password, session_id).await?;
+
+        let token = generate_secure_token(32);
+        let purge_after = Utc::now() + Duration::days(self.grace_days);
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO account_deletions (user_id, restore_token, purge_after)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET restore_token = EXCLUDED.restore_token, requested_at = NOW(), purge_after = EXCLUDED.purge_after
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_token(&token))
+        .bind(purge_after)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        sqlx::query("UPDATE users SET is_active = false, updated_at = NOW() WHERE id = $1")
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        auth_service.sign_out_everywhere(&mut tx, user.id).await?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        // The deletion stands even if the restore link can't be delivered
+        if let Err(e) = mailer.send(&EmailMessage {
+            to: user.email.clone(),
+            subject: "Your Taleji account is scheduled for deletion".to_string(),
+            body: format!(
+                "Your Taleji account has been deactivated and will be permanently deleted on {}.\n\n\
+                 Changed your mind? Restore it before then by opening this link:\n{}/restore-account/{}",
+                purge_after.format("%Y-%m-%d"),
+                self.app_base_url,
+                token
+            ),
+        }) {
+            tracing::error!("Failed to send account deletion email: {}", e);
+        }
+
+        tracing::info!(user_id = user.id, purge_after = %purge_after, "Account deletion requested");
+        Ok(purge_after)
+    }
+
+    /// Redeem an emailed restore link during the grace period
+    pub async fn restore(&self, db: &Db, token: &str) -> AppResult<()> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "DELETE FROM account_deletions WHERE restore_token = $1 AND purge_after > NOW() RETURNING user_id",
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("Invalid or expired restore link".to_string()))?;
+
+        sqlx::query("UPDATE users SET is_active = true, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(user_id = user_id, "Account restored");
+        Ok(())
+    }
+
+    /// Purge accounts whose grace period has passed. Their posts go to the
+    /// `DELETED_POSTS_OWNER` account if one is configured; otherwise the
+    /// `author_id` foreign key leaves them unattributed.
+    pub async fn purge_due_deletions(&self, db: &Db) -> AppResult<u64> {
+        let due = sqlx::query_scalar::<_, i64>(
+            "SELECT user_id FROM account_deletions WHERE purge_after <= NOW()",
+        )
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if due.is_empty() {
+            return Ok(0);
+        }
+
+        let new_owner = match &self.deleted_posts_owner {
+            Some(username) => {
+                let owner = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = $1 AND is_active = true")
+                    .bind(username)
+                    .fetch_optional(&**db)
+                    .await
+                    .map_err(AppError::Database)?;
+                if owner.is_none() {
+                    tracing::warn!(username = %username, "DELETED_POSTS_OWNER not found; leaving posts unattributed");
+                }
+                owner
+            }
+            None => None,
+        };
+
+        let mut purged = 0;
+        for user_id in due {
+            let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+            if let Some(owner_id) = new_owner.filter(|owner_id| *owner_id != user_id) {
+                sqlx::query("UPDATE posts SET author_id = $2 WHERE author_id = $1")
+                    .bind(user_id)
+                    .bind(owner_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(AppError::Database)?;
+            }
+
+            // Remaining per-user rows go with the account through ON DELETE CASCADE / SET NULL
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+
+            tx.commit().await.map_err(AppError::Database)?;
+
+            tracing::info!(user_id = user_id, reassigned_to = ?new_owner, "Deleted account purged");
+            purged += 1;
+        }
+
+        Ok(purged)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for PrivacyService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `GET /account/export` — download the signed-in user's data as a JSON file
+#[cfg(feature = "ssr")]
+pub async fn export_handler(Extension(db): Extension<Db>, headers: HeaderMap) -> Response {
+    let session = match cookie_value(&headers, SESSION_COOKIE_NAME) {
+        Some(token) => SessionService::new().resolve_session(&db, &token).await.ok().flatten(),
+        None => None,
+    };
+    let user = match session {
+        Some((user, None)) => user,
+        // Admins viewing as the user don't get to download their data
+        Some((_, Some(_))) => return StatusCode::FORBIDDEN.into_response(),
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    };
+
+    match PrivacyService::new().export(&db, user.id).await {
+        Ok(export) => {
+            let filename = format!("taleji-export-{}-{}.json", user.username, Utc::now().format("%Y%m%d"));
+            (
+                [
+                    (header::CONTENT_TYPE, "application/json".to_string()),
+                    (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+                    (header::CACHE_CONTROL, "no-store".to_string()),
+                ],
+                serde_json::to_string_pretty(&export).unwrap_or_default(),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            log_error(&e, "Data export failed");
+            e.status_code().into_response()
+        }
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{log_error, AppError, AppResult},
+    tags::refresh_for_posts,
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "ssr")]
+use std::sync::Arc;
+
+/// Columns returned for each announced post
+#[cfg(feature = "ssr")]
+type AnnouncedRow = (i64, String, String, Option<DateTime<Utc>>);
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostEventKind {
+    Published,
+    Unpublished,
+}
+
+/// A post going live or being hidden again
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct PostEvent {
+    pub kind: PostEventKind,
+    pub post_id: i64,
+    pub slug: String,
+    pub title: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Reacts to publish events, e.g. by invalidating caches, refreshing feeds or
+/// sending notifications
+#[cfg(feature = "ssr")]
+pub trait PublishListener: Send + Sync {
+    fn on_event(&self, event: &PostEvent) -> AppResult<()>;
+}
+
+/// Writes events to the log
+#[cfg(feature = "ssr")]
+pub struct LogPublishListener;
+
+#[cfg(feature = "ssr")]
+impl PublishListener for LogPublishListener {
+    fn on_event(&self, event: &PostEvent) -> AppResult<()> {
+        tracing::info!(post_id = event.post_id, slug = %event.slug, kind = ?event.kind, "Post publication changed");
+        Ok(())
+    }
+}
+
+/// Fires publish events once a post's scheduled publish or unpublish time
+/// arrives, and for posts published or unpublished directly from the editor.
+/// Each change is announced once; a listener failure is logged and not retried.
+#[cfg(feature = "ssr")]
+pub struct PublishingService {
+    pub poll_seconds: u64,
+    listeners: Vec<Arc<dyn PublishListener>>,
+}
+
+#[cfg(feature = "ssr")]
+impl PublishingService {
+    pub fn new() -> Self {
+        Self {
+            poll_seconds: std::env::var("PUBLISH_POLL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs: &u64| secs > 0)
+                .unwrap_or(30),
+            listeners: vec![Arc::new(LogPublishListener)],
+        }
+    }
+
+    pub fn with_listener(mut self, listener: Arc<dyn PublishListener>) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Announce every post whose visibility changed since it was last
+    /// announced; returns the number of events fired
+    pub async fn dispatch_due(&self, db: &Db) -> AppResult<usize> {
+        // SKIP LOCKED keeps several server instances from announcing the same post
+        let published = sqlx::query_as::<_, AnnouncedRow>(
+            r#"
+            UPDATE posts SET announced_visible = true
+            WHERE id IN (
+                SELECT id FROM posts
+                WHERE NOT announced_visible
+                  AND published_at <= NOW()
+                  AND (unpublish_at IS NULL OR unpublish_at > NOW())
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, slug, title, published_at
+            "#,
+        )
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let unpublished = sqlx::query_as::<_, AnnouncedRow>(
+            r#"
+            UPDATE posts SET announced_visible = false
+            WHERE id IN (
+                SELECT id FROM posts
+                WHERE announced_visible
+                  AND (published_at IS NULL OR published_at > NOW() OR unpublish_at <= NOW())
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, slug, title, published_at
+            "#,
+        )
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        // Tag counts only include live posts. The posts are already marked as
+        // announced, so a failed recount must not swallow their events;
+        // `repair-tag-counts` fixes the counts.
+        let changed: Vec<i64> = published.iter().chain(&unpublished).map(|row| row.0).collect();
+        if !changed.is_empty() {
+            let recount = async {
+                let mut conn = db.acquire().await.map_err(AppError::Database)?;
+                refresh_for_posts(&mut conn, &changed).await
+            };
+            if let Err(e) = recount.await {
+                log_error(&e, "Failed to refresh tag counts of published posts");
+            }
+        }
+
+        let to_event = |kind: PostEventKind| {
+            move |(post_id, slug, title, published_at): AnnouncedRow| PostEvent { kind, post_id, slug, title, published_at }
+        };
+        let events: Vec<PostEvent> = published
+            .into_iter()
+            .map(to_event(PostEventKind::Published))
+            .chain(unpublished.into_iter().map(to_event(PostEventKind::Unpublished)))
+            .collect();
+
+        for event in &events {
+            for listener in &self.listeners {
+                if let Err(e) = listener.on_event(event) {
+                    tracing::error!(post_id = event.post_id, "Publish listener failed: {}", e);
+                }
+            }
+        }
+
+        Ok(events.len())
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for PublishingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
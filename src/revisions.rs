@@ -0,0 +1,210 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::{
+        DiffOp, DiffSegment, Post, PostHistory, PostRevision, RevisionDiff, RevisionInfo, UpdatePostInput, User,
+    },
+    posts::PostService,
+};
+#[cfg(feature = "ssr")]
+use similar::{ChangeTag, TextDiff};
+#[cfg(feature = "ssr")]
+use sqlx::PgConnection;
+
+/// Give up on a minimal diff after this long and fall back to a coarser one
+#[cfg(feature = "ssr")]
+const DIFF_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Record the current state of `post` as its next revision. Must run in the
+/// transaction that changed the post.
+#[cfg(feature = "ssr")]
+pub async fn append_revision(conn: &mut PgConnection, post: &Post, editor_id: i64, change_summary: Option<&str>) -> AppResult<i32> {
+    let revision_number = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO post_revisions
+            (post_id, title, summary, body_markdown, body_html, revision_number, change_summary, created_by)
+        SELECT $1, $2, $3, $4, $5, COALESCE(MAX(revision_number), 0) + 1, $6, $7
+        FROM post_revisions WHERE post_id = $1
+        RETURNING revision_number
+        "#,
+    )
+    .bind(post.id)
+    .bind(&post.title)
+    .bind(&post.summary)
+    .bind(&post.body_markdown)
+    .bind(&post.body_html)
+    .bind(change_summary)
+    .bind(editor_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(revision_number)
+}
+
+/// Word-level diff of two texts, with neighbouring words of the same kind merged
+#[cfg(feature = "ssr")]
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffSegment> {
+    let diff = TextDiff::configure().timeout(DIFF_TIMEOUT).diff_words(old, new);
+
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let op = match change.tag() {
+            ChangeTag::Equal => DiffOp::Equal,
+            ChangeTag::Insert => DiffOp::Insert,
+            ChangeTag::Delete => DiffOp::Delete,
+        };
+        match segments.last_mut() {
+            Some(last) if last.op == op => last.text.push_str(change.value()),
+            _ => segments.push(DiffSegment { op, text: change.value().to_string() }),
+        }
+    }
+    segments
+}
+
+/// History, comparison and restore of post revisions
+#[cfg(feature = "ssr")]
+pub struct RevisionService;
+
+#[cfg(feature = "ssr")]
+impl RevisionService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn history(&self, db: &Db, post: &Post) -> AppResult<PostHistory> {
+        let revisions = sqlx::query_as::<_, RevisionInfo>(
+            r#"
+            SELECT r.id, r.revision_number, r.change_summary, u.username AS created_by_username, r.created_at
+            FROM post_revisions r
+            LEFT JOIN users u ON u.id = r.created_by
+            WHERE r.post_id = $1
+            ORDER BY r.revision_number DESC
+            "#,
+        )
+        .bind(post.id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(PostHistory {
+            post_id: post.id,
+            slug: post.slug.clone(),
+            title: post.title.clone(),
+            revisions,
+        })
+    }
+
+    pub async fn get(&self, db: &Db, post_id: i64, revision_number: i32) -> AppResult<PostRevision> {
+        sqlx::query_as::<_, PostRevision>(
+            "SELECT * FROM post_revisions WHERE post_id = $1 AND revision_number = $2",
+        )
+        .bind(post_id)
+        .bind(revision_number)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Revision {} not found", revision_number)))
+    }
+
+    /// Compare any two revisions of a post
+    pub async fn diff(&self, db: &Db, post_id: i64, from: i32, to: i32) -> AppResult<RevisionDiff> {
+        let old = self.get(db, post_id, from).await?;
+        let new = self.get(db, post_id, to).await?;
+
+        Ok(RevisionDiff {
+            from_revision: from,
+            to_revision: to,
+            title: diff_words(&old.title, &new.title),
+            summary: diff_words(&old.summary, &new.summary),
+            body_markdown: diff_words(&old.body_markdown, &new.body_markdown),
+        })
+    }
+
+    /// Bring back an old revision's content as a new revision; publication
+    /// status is left alone
+    pub async fn restore(&self, db: &Db, editor: &User, post_id: i64, revision_number: i32) -> AppResult<Post> {
+        let revision = self.get(db, post_id, revision_number).await?;
+
+        let post = PostService::new()
+            .update(db, editor, post_id, UpdatePostInput {
+                slug: None,
+                title: Some(revision.title),
+                summary: Some(revision.summary),
+                body_markdown: Some(revision.body_markdown),
+                published: None,
+                publish_at: None,
+                unpublish_at: None,
+                tags: None,
+                change_summary: Some(format!("Restored revision {}", revision_number)),
+            })
+            .await?;
+
+        tracing::info!(post_id = post_id, revision_number = revision_number, editor_id = editor.id, "Post revision restored");
+        Ok(post)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for RevisionService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn ops(segments: &[DiffSegment]) -> Vec<(DiffOp, &str)> {
+        segments.iter().map(|s| (s.op, s.text.as_str())).collect()
+    }
+
+    #[test]
+    fn identical_texts_are_one_equal_segment() {
+        let segments = diff_words("the quick fox", "the quick fox");
+        assert_eq!(ops(&segments), vec![(DiffOp::Equal, "the quick fox")]);
+    }
+
+    #[test]
+    fn replaced_word_is_a_delete_and_an_insert() {
+        let segments = diff_words("the quick fox", "the slow fox");
+        assert_eq!(
+            ops(&segments),
+            vec![
+                (DiffOp::Equal, "the "),
+                (DiffOp::Delete, "quick"),
+                (DiffOp::Insert, "slow"),
+                (DiffOp::Equal, " fox"),
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbouring_changes_are_merged() {
+        let segments = diff_words("a b", "a x y z b");
+        assert_eq!(
+            ops(&segments),
+            vec![(DiffOp::Equal, "a "), (DiffOp::Insert, "x y z "), (DiffOp::Equal, "b")]
+        );
+    }
+
+    #[test]
+    fn segments_rebuild_both_texts() {
+        let (old, new) = ("Line one.\nLine two has words.", "Line one!\nLine two has more words.\n");
+        let segments = diff_words(old, new);
+        let side = |skip: DiffOp| {
+            segments.iter().filter(|s| s.op != skip).map(|s| s.text.as_str()).collect::<String>()
+        };
+        assert_eq!(side(DiffOp::Insert), old);
+        assert_eq!(side(DiffOp::Delete), new);
+    }
+
+    #[test]
+    fn empty_sides_are_all_inserted_or_deleted() {
+        assert_eq!(ops(&diff_words("", "new text")), vec![(DiffOp::Insert, "new text")]);
+        assert_eq!(ops(&diff_words("old text", "")), vec![(DiffOp::Delete, "old text")]);
+        assert!(diff_words("", "").is_empty());
+    }
+}
@@ -190,4 +190,36 @@ pub async fn request_id<B>(
     );
 
     Ok(response)
-}
\ No newline at end of file
+}
+
+/// Extract the client IP address from proxy headers, if it parses as one
+#[cfg(feature = "ssr")]
+pub fn client_ip(headers: &axum::http::HeaderMap) -> Option<std::net::IpAddr> {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.split(',').next())
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// Generate a random, URL-safe token with `num_bytes` bytes of entropy
+#[cfg(feature = "ssr")]
+pub fn generate_secure_token(num_bytes: usize) -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rand::RngCore;
+
+    let mut bytes = vec![0u8; num_bytes];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash a token for storage so that leaked rows can't be replayed
+#[cfg(feature = "ssr")]
+pub fn hash_token(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,581 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    access_tokens::AccessTokenService,
+    auth::AuthService,
+    db::Db,
+    error::{AppError, AppResult},
+    models::{ActiveSession, TokenScope, User, UserSession},
+    security::{client_ip, generate_secure_token, hash_token},
+};
+#[cfg(feature = "ssr")]
+use axum::http::{header, request::Parts, HeaderMap, HeaderValue};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use leptos::{expect_context, use_context};
+#[cfg(feature = "ssr")]
+use leptos_axum::ResponseOptions;
+#[cfg(feature = "ssr")]
+use sqlx::FromRow;
+
+/// Name of the cookie carrying the session token
+#[cfg(feature = "ssr")]
+pub const SESSION_COOKIE_NAME: &str = "taleji_session";
+
+/// Cookie carrying a provider login's pending second-factor challenge, only
+/// sent to server functions
+#[cfg(feature = "ssr")]
+pub const TWO_FACTOR_COOKIE_NAME: &str = "two_factor_challenge";
+#[cfg(feature = "ssr")]
+pub const TWO_FACTOR_COOKIE_PATH: &str = "/api";
+
+/// How long a provider re-authentication stands in for the password
+#[cfg(feature = "ssr")]
+const REAUTH_WINDOW_MINUTES: i32 = 5;
+
+#[cfg(feature = "ssr")]
+const SESSION_COLUMNS: &str =
+    "id, user_id, expires_at, created_at, last_accessed, user_agent, host(ip_address) AS ip_address";
+
+#[cfg(feature = "ssr")]
+#[derive(FromRow)]
+struct SessionUserRow {
+    #[sqlx(flatten)]
+    user: User,
+    impersonator_id: Option<i64>,
+}
+
+/// Cookie-backed sessions stored in the `user_sessions` table
+#[cfg(feature = "ssr")]
+pub struct SessionService {
+    session_ttl_hours: i64,
+    remember_me_days: i64,
+    secure_cookies: bool,
+}
+
+#[cfg(feature = "ssr")]
+impl SessionService {
+    pub fn new() -> Self {
+        Self {
+            session_ttl_hours: std::env::var("SESSION_TTL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24),
+            remember_me_days: std::env::var("SESSION_REMEMBER_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            secure_cookies: std::env::var("COOKIE_SECURE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+        }
+    }
+
+    /// Lifetime of a new session, depending on "remember me"
+    pub fn session_lifetime(&self, remember_me: bool) -> Duration {
+        if remember_me {
+            Duration::days(self.remember_me_days)
+        } else {
+            Duration::hours(self.session_ttl_hours)
+        }
+    }
+
+    /// Create a session row for a user and return the raw token for the cookie
+    pub async fn create_session(
+        &self,
+        db: &Db,
+        user_id: i64,
+        remember_me: bool,
+        headers: &HeaderMap,
+    ) -> AppResult<(String, UserSession)> {
+        let expires_at = Utc::now() + self.session_lifetime(remember_me);
+        let (token, session) = self.insert_session(db, user_id, None, expires_at, headers).await?;
+
+        tracing::info!(user_id = user_id, "Session created");
+        Ok((token, session))
+    }
+
+    /// Create a session in which `impersonator_id` acts as `user_id`
+    pub async fn create_impersonation_session(
+        &self,
+        db: &Db,
+        user_id: i64,
+        impersonator_id: i64,
+        lifetime: Duration,
+        headers: &HeaderMap,
+    ) -> AppResult<(String, UserSession)> {
+        let (token, session) = self
+            .insert_session(db, user_id, Some(impersonator_id), Utc::now() + lifetime, headers)
+            .await?;
+
+        tracing::info!(user_id = user_id, impersonator_id = impersonator_id, "Impersonation session created");
+        Ok((token, session))
+    }
+
+    async fn insert_session(
+        &self,
+        db: &Db,
+        user_id: i64,
+        impersonator_id: Option<i64>,
+        expires_at: DateTime<Utc>,
+        headers: &HeaderMap,
+    ) -> AppResult<(String, UserSession)> {
+        let token = generate_secure_token(32);
+
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| ua.chars().take(512).collect::<String>());
+        let ip_address = client_ip(headers).map(|ip| ip.to_string());
+
+        let session = sqlx::query_as::<_, UserSession>(&format!(
+            r#"
+            INSERT INTO user_sessions (id, user_id, expires_at, user_agent, ip_address, impersonator_id)
+            VALUES ($1, $2, $3, $4, $5::text::inet, $6)
+            RETURNING {}
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(hash_token(&token))
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(user_agent)
+        .bind(ip_address)
+        .bind(impersonator_id)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok((token, session))
+    }
+
+    /// Resolve the active user for a session token, refreshing `last_accessed`
+    pub async fn get_session_user(&self, db: &Db, token: &str) -> AppResult<Option<User>> {
+        Ok(self.resolve_session(db, token).await?.map(|(user, _)| user))
+    }
+
+    /// Like [`Self::get_session_user`], also returning the id of the admin
+    /// behind an impersonation session. Such a session stops working as soon
+    /// as the admin is deactivated or loses the role.
+    pub async fn resolve_session(&self, db: &Db, token: &str) -> AppResult<Option<(User, Option<i64>)>> {
+        let row = sqlx::query_as::<_, SessionUserRow>(
+            r#"
+            WITH touched AS (
+                UPDATE user_sessions SET last_accessed = NOW()
+                WHERE id = $1 AND expires_at > NOW()
+                RETURNING user_id, impersonator_id
+            )
+            SELECT u.*, t.impersonator_id FROM users u
+            JOIN touched t ON t.user_id = u.id
+            WHERE u.is_active = true
+              AND (t.impersonator_id IS NULL OR EXISTS (
+                  SELECT 1 FROM users a
+                  WHERE a.id = t.impersonator_id AND a.is_active = true AND a.role = 'admin'
+              ))
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(row.map(|row| (row.user, row.impersonator_id)))
+    }
+
+    /// Delete the session identified by a raw token
+    pub async fn delete_session(&self, db: &Db, token: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM user_sessions WHERE id = $1")
+            .bind(hash_token(token))
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    /// Mark a session as just re-authenticated by `user_id`; false when the
+    /// session isn't theirs or has expired
+    pub async fn mark_reauthenticated(&self, db: &Db, session_id: &str, user_id: i64) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_sessions SET reauthenticated_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND impersonator_id IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Whether the session's owner re-authenticated within the last few minutes
+    pub async fn recently_reauthenticated(&self, db: &Db, session_id: &str) -> AppResult<bool> {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM user_sessions
+                WHERE id = $1 AND reauthenticated_at > NOW() - make_interval(mins => $2)
+            )
+            "#,
+        )
+        .bind(session_id)
+        .bind(REAUTH_WINDOW_MINUTES)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    /// Unexpired sessions of a user, most recently used first; sessions opened
+    /// by an admin impersonating the user are left out
+    pub async fn list_sessions(&self, db: &Db, user_id: i64) -> AppResult<Vec<UserSession>> {
+        sqlx::query_as::<_, UserSession>(&format!(
+            r#"
+            SELECT {} FROM user_sessions
+            WHERE user_id = $1 AND expires_at > NOW() AND impersonator_id IS NULL
+            ORDER BY last_accessed DESC
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    /// Delete one of the user's own sessions by id
+    pub async fn revoke_session(&self, db: &Db, user_id: i64, session_id: &str) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM user_sessions WHERE id = $1 AND user_id = $2")
+            .bind(session_id)
+            .bind(user_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Session not found".to_string()));
+        }
+
+        tracing::info!(user_id = user_id, "Session revoked");
+        Ok(())
+    }
+
+    /// Delete every session of the user except `keep_session_id`; returns how many were removed
+    pub async fn revoke_other_sessions(&self, db: &Db, user_id: i64, keep_session_id: Option<&str>) -> AppResult<u64> {
+        let result = sqlx::query(
+            "DELETE FROM user_sessions WHERE user_id = $1 AND id IS DISTINCT FROM $2",
+        )
+        .bind(user_id)
+        .bind(keep_session_id)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(user_id = user_id, revoked = result.rows_affected(), "Other sessions revoked");
+        Ok(result.rows_affected())
+    }
+
+    /// Build the `Set-Cookie` value for a new session
+    pub fn session_cookie(&self, token: &str, max_age: Option<Duration>) -> String {
+        let mut cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax",
+            SESSION_COOKIE_NAME, token
+        );
+        if self.secure_cookies {
+            cookie.push_str("; Secure");
+        }
+        if let Some(max_age) = max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age.num_seconds()));
+        }
+        cookie
+    }
+
+    /// Build a short-lived HttpOnly cookie for a login flow, limited to `path`;
+    /// a zero `max_age` removes it
+    pub fn flow_cookie(&self, name: &str, value: &str, path: &str, max_age: Duration) -> String {
+        let mut cookie = format!(
+            "{}={}; Path={}; HttpOnly; SameSite=Lax; Max-Age={}",
+            name, value, path, max_age.num_seconds()
+        );
+        if self.secure_cookies {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+
+    /// Build the `Set-Cookie` value that removes the session cookie
+    pub fn clear_cookie(&self) -> String {
+        let mut cookie = format!(
+            "{}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
+            SESSION_COOKIE_NAME
+        );
+        if self.secure_cookies {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for SessionService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a cookie value from request headers
+#[cfg(feature = "ssr")]
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Headers of the request currently being handled, for server functions and SSR
+#[cfg(feature = "ssr")]
+pub fn request_headers() -> HeaderMap {
+    use_context::<Parts>()
+        .map(|parts| parts.headers)
+        .unwrap_or_default()
+}
+
+/// Append a `Set-Cookie` header to the current response
+#[cfg(feature = "ssr")]
+pub fn set_cookie(cookie: &str) -> AppResult<()> {
+    let value = HeaderValue::from_str(cookie)
+        .map_err(|e| AppError::Internal(format!("Invalid cookie value: {}", e)))?;
+    if let Some(response) = use_context::<ResponseOptions>() {
+        response.append_header(header::SET_COOKIE, value);
+    }
+    Ok(())
+}
+
+/// Start a session for a user on the current request and set the session cookie
+#[cfg(feature = "ssr")]
+pub async fn start_session(db: &Db, user_id: i64, remember_me: bool) -> AppResult<UserSession> {
+    let sessions = SessionService::new();
+    let (token, session) = sessions
+        .create_session(db, user_id, remember_me, &request_headers())
+        .await?;
+
+    // Without "remember me" the cookie lasts until the browser is closed
+    let max_age = remember_me.then(|| sessions.session_lifetime(true));
+    set_cookie(&sessions.session_cookie(&token, max_age))?;
+
+    Ok(session)
+}
+
+/// End the session of the current request and clear the session cookie
+#[cfg(feature = "ssr")]
+pub async fn end_session(db: &Db) -> AppResult<()> {
+    let sessions = SessionService::new();
+    if let Some(token) = cookie_value(&request_headers(), SESSION_COOKIE_NAME) {
+        sessions.delete_session(db, &token).await?;
+    }
+    set_cookie(&sessions.clear_cookie())
+}
+
+/// Id of the session behind the current request's cookie, if any
+#[cfg(feature = "ssr")]
+pub fn current_session_id() -> Option<String> {
+    cookie_value(&request_headers(), SESSION_COOKIE_NAME).map(|token| hash_token(&token))
+}
+
+/// Present a stored session for display, with its user agent broken down
+#[cfg(feature = "ssr")]
+pub fn describe_session(session: UserSession, current_session_id: Option<&str>) -> ActiveSession {
+    let (browser, os, device) = parse_user_agent(session.user_agent.as_deref().unwrap_or_default());
+    ActiveSession {
+        is_current: current_session_id == Some(session.id.as_str()),
+        id: session.id,
+        browser: browser.to_string(),
+        os: os.to_string(),
+        device: device.to_string(),
+        ip_address: session.ip_address,
+        created_at: session.created_at,
+        last_accessed: session.last_accessed,
+    }
+}
+
+/// Best-effort browser, operating system and device class from a `User-Agent`.
+///
+/// Order matters: most browsers include the tokens of the engines they
+/// descend from, so the more specific names are checked first.
+#[cfg(feature = "ssr")]
+pub fn parse_user_agent(ua: &str) -> (&'static str, &'static str, &'static str) {
+    let browser = if ua.contains("Edg/") || ua.contains("Edge/") {
+        "Edge"
+    } else if ua.contains("OPR/") || ua.contains("Opera") {
+        "Opera"
+    } else if ua.contains("Firefox/") || ua.contains("FxiOS/") {
+        "Firefox"
+    } else if ua.contains("Chrome/") || ua.contains("CriOS/") {
+        "Chrome"
+    } else if ua.contains("Safari/") {
+        "Safari"
+    } else if ua.contains("curl/") || ua.contains("reqwest") {
+        "API client"
+    } else {
+        "Unknown browser"
+    };
+
+    let os = if ua.contains("iPhone") || ua.contains("iPad") || ua.contains("iPod") {
+        "iOS"
+    } else if ua.contains("Android") {
+        "Android"
+    } else if ua.contains("Windows") {
+        "Windows"
+    } else if ua.contains("Mac OS X") || ua.contains("Macintosh") {
+        "macOS"
+    } else if ua.contains("CrOS") {
+        "ChromeOS"
+    } else if ua.contains("Linux") {
+        "Linux"
+    } else {
+        "Unknown OS"
+    };
+
+    // Android phones send a `Mobile` token; Android tablets leave it out
+    let device = if ua.contains("iPad") || ua.contains("Tablet") || (ua.contains("Android") && !ua.contains("Mobile")) {
+        "Tablet"
+    } else if ua.contains("Mobi") || ua.contains("iPhone") || ua.contains("iPod") {
+        "Mobile"
+    } else if ua.is_empty() {
+        "Unknown device"
+    } else {
+        "Desktop"
+    };
+
+    (browser, os, device)
+}
+
+/// Read an `Authorization: Bearer` token from request headers
+#[cfg(feature = "ssr")]
+pub fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// Who is making the current request
+#[cfg(feature = "ssr")]
+pub struct Caller {
+    pub user: User,
+    /// Scopes of the personal access token used, or `None` for a browser
+    /// session or JWT, which carry the user's full permissions
+    pub scopes: Option<Vec<TokenScope>>,
+    /// The admin behind an impersonation session
+    pub impersonator_id: Option<i64>,
+}
+
+#[cfg(feature = "ssr")]
+impl Caller {
+    /// Whether the request was authenticated with a personal access token
+    pub fn is_access_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// Whether an admin is viewing the site as this user
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonator_id.is_some()
+    }
+
+    /// Whether the credential allows `scope`; the admin scope allows everything
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        match &self.scopes {
+            None => true,
+            Some(scopes) => scopes.contains(&scope) || scopes.contains(&TokenScope::Admin),
+        }
+    }
+}
+
+/// The caller of the current request, if authenticated.
+///
+/// Browsers are identified by the session cookie and API clients by a bearer
+/// credential: either a JWT access token or a personal access token. Works
+/// from any `#[server]` function as well as during SSR rendering.
+#[cfg(feature = "ssr")]
+pub async fn current_caller() -> AppResult<Option<Caller>> {
+    let headers = request_headers();
+    let db = expect_context::<Db>();
+
+    if let Some(token) = cookie_value(&headers, SESSION_COOKIE_NAME) {
+        let session = SessionService::new().resolve_session(&db, &token).await?;
+        return Ok(session.map(|(user, impersonator_id)| Caller { user, scopes: None, impersonator_id }));
+    }
+
+    if let Some(token) = bearer_token(&headers) {
+        if AccessTokenService::is_access_token(&token) {
+            let (user, scopes) = AccessTokenService::new()
+                .authenticate(&db, &token, client_ip(&headers))
+                .await?;
+            return Ok(Some(Caller { user, scopes: Some(scopes), impersonator_id: None }));
+        }
+
+        let auth_service = AuthService::new();
+        let claims = auth_service.validate_token(&db, &token).await?;
+        return match auth_service.get_user_by_id(&db, claims.sub).await {
+            Ok(user) => Ok(Some(Caller { user, scopes: None, impersonator_id: None })),
+            Err(AppError::NotFound(_)) => Err(AppError::Unauthorized),
+            Err(e) => Err(e),
+        };
+    }
+
+    Ok(None)
+}
+
+/// The logged-in user for the current request, if any, however they authenticated
+#[cfg(feature = "ssr")]
+pub async fn current_user() -> AppResult<Option<User>> {
+    Ok(current_caller().await?.map(|caller| caller.user))
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::parse_user_agent;
+
+    #[test]
+    fn android_phone_is_mobile() {
+        let ua = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36";
+        assert_eq!(parse_user_agent(ua), ("Chrome", "Android", "Mobile"));
+    }
+
+    #[test]
+    fn android_tablet_without_mobile_token_is_tablet() {
+        let ua = "Mozilla/5.0 (Linux; Android 13; SM-X710) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+        assert_eq!(parse_user_agent(ua), ("Chrome", "Android", "Tablet"));
+    }
+
+    #[test]
+    fn firefox_on_android_tablet_is_tablet() {
+        let ua = "Mozilla/5.0 (Android 13; Tablet; rv:125.0) Gecko/125.0 Firefox/125.0";
+        assert_eq!(parse_user_agent(ua), ("Firefox", "Android", "Tablet"));
+    }
+
+    #[test]
+    fn apple_devices() {
+        let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+        let ipad = "Mozilla/5.0 (iPad; CPU OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+        assert_eq!(parse_user_agent(iphone), ("Safari", "iOS", "Mobile"));
+        assert_eq!(parse_user_agent(ipad), ("Safari", "iOS", "Tablet"));
+    }
+
+    #[test]
+    fn desktop_and_unknown() {
+        let edge = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0";
+        assert_eq!(parse_user_agent(edge), ("Edge", "Windows", "Desktop"));
+        assert_eq!(parse_user_agent(""), ("Unknown browser", "Unknown OS", "Unknown device"));
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::RegistrationMode,
+};
+
+/// Require authors and admins to enroll in two-factor authentication
+#[cfg(feature = "ssr")]
+pub const REQUIRE_TWO_FACTOR_FOR_PRIVILEGED: &str = "require_two_factor_for_privileged";
+
+/// Who may register: "open", "invite_only" or "closed"
+#[cfg(feature = "ssr")]
+pub const REGISTRATION_MODE: &str = "registration_mode";
+
+/// Read a site setting
+#[cfg(feature = "ssr")]
+pub async fn get_setting(db: &Db, key: &str) -> AppResult<Option<String>> {
+    sqlx::query_scalar::<_, String>("SELECT value FROM site_settings WHERE key = $1")
+        .bind(key)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)
+}
+
+/// Read a boolean site setting, falling back to `default` when unset or malformed
+#[cfg(feature = "ssr")]
+pub async fn get_bool_setting(db: &Db, key: &str, default: bool) -> AppResult<bool> {
+    Ok(get_setting(db, key)
+        .await?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default))
+}
+
+/// Create or replace a site setting
+#[cfg(feature = "ssr")]
+pub async fn set_setting(db: &Db, key: &str, value: &str, updated_by: i64) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO site_settings (key, value, updated_by, updated_at)
+        VALUES ($1, $2, $3, NOW())
+        ON CONFLICT (key) DO UPDATE
+        SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+        "#,
+    )
+    .bind(key)
+    .bind(value)
+    .bind(updated_by)
+    .execute(&**db)
+    .await
+    .map_err(AppError::Database)?;
+
+    tracing::info!(key = key, updated_by = updated_by, "Site setting updated");
+    Ok(())
+}
+
+/// Current registration mode; the `REGISTRATION_MODE` environment variable
+/// provides the default until an admin changes it
+#[cfg(feature = "ssr")]
+pub async fn get_registration_mode(db: &Db) -> AppResult<RegistrationMode> {
+    let stored = get_setting(db, REGISTRATION_MODE).await?;
+    Ok(stored
+        .or_else(|| std::env::var("REGISTRATION_MODE").ok())
+        .and_then(|v| RegistrationMode::parse(&v))
+        .unwrap_or(RegistrationMode::Open))
+}
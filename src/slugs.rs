@@ -0,0 +1,143 @@
+#[cfg(feature = "ssr")]
+use crate::error::{AppError, AppResult};
+
+/// Longest slug generated from a name, leaving room for a collision suffix
+#[cfg(feature = "ssr")]
+const MAX_GENERATED_SLUG_LENGTH: usize = 80;
+
+/// URL slug for a name: transliterated to ASCII, then lowercase letters and
+/// digits separated by hyphens, cut at a word boundary if very long
+#[cfg(feature = "ssr")]
+pub fn slugify(name: &str) -> String {
+    let ascii = deunicode::deunicode(name).to_lowercase();
+
+    let mut slug = String::new();
+    for word in ascii.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()) {
+        let extra = if slug.is_empty() { word.len() } else { word.len() + 1 };
+        if slug.len() + extra > MAX_GENERATED_SLUG_LENGTH {
+            if slug.is_empty() {
+                slug.push_str(&word[..MAX_GENERATED_SLUG_LENGTH]);
+            }
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(word);
+    }
+    slug
+}
+
+/// `base`, or `base-2`, `base-3`… when taken, whichever is free first
+#[cfg(feature = "ssr")]
+pub fn with_collision_suffix(base: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|s| s == base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_else(|| base.to_string())
+}
+
+/// The slug to store for an admin-entered name and slug: the slug as given
+/// when it is already well formed, otherwise one derived from the name when blank
+#[cfg(feature = "ssr")]
+pub fn slug_from_input(name: &str, slug: &str) -> AppResult<String> {
+    let slug = slug.trim();
+    let slug = if slug.is_empty() { slugify(name) } else { slug.to_string() };
+
+    if slug.is_empty() {
+        return Err(AppError::Validation("Name needs at least one letter or digit".to_string()));
+    }
+    if !is_well_formed(&slug) {
+        return Err(AppError::Validation(
+            "Slug can only contain lowercase letters, numbers, and hyphens".to_string(),
+        ));
+    }
+    Ok(slug)
+}
+
+/// Whether `slug` has the shape [`slugify`] produces, whatever its length:
+/// lowercase ASCII letters and digits in runs joined by single hyphens
+#[cfg(feature = "ssr")]
+fn is_well_formed(slug: &str) -> bool {
+    slug.split('-')
+        .all(|word| !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()))
+}
+
+/// Send the browser to the current address of a page requested by an old slug.
+///
+/// Page loads get a `301`; server function calls from the client keep their
+/// normal response and the page navigates itself, since a redirect there
+/// would swap the data for HTML.
+#[cfg(feature = "ssr")]
+pub fn permanent_redirect(path: &str) {
+    use axum::http::{header, request::Parts, HeaderValue, StatusCode};
+    use leptos::use_context;
+    use leptos_axum::ResponseOptions;
+
+    let (Some(request), Some(response)) = (use_context::<Parts>(), use_context::<ResponseOptions>()) else {
+        return;
+    };
+    let accepts_html = request
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+
+    if accepts_html {
+        if let Ok(location) = HeaderValue::from_str(path) {
+            response.set_status(StatusCode::MOVED_PERMANENTLY);
+            response.insert_header(header::LOCATION, location);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_transliterates_and_joins_words() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Crème brûlée -- à la carte "), "creme-brulee-a-la-carte");
+        assert_eq!(slugify("Rust 2024 edition"), "rust-2024-edition");
+        assert_eq!(slugify("?!"), "");
+    }
+
+    #[test]
+    fn slugify_cuts_long_names_at_a_word_boundary() {
+        let slug = slugify(&"word ".repeat(30));
+        assert!(slug.len() <= MAX_GENERATED_SLUG_LENGTH);
+        assert!(slug.ends_with("word"));
+
+        let long_word = "a".repeat(MAX_GENERATED_SLUG_LENGTH + 20);
+        assert_eq!(slugify(&long_word).len(), MAX_GENERATED_SLUG_LENGTH);
+    }
+
+    #[test]
+    fn with_collision_suffix_picks_the_first_free_slug() {
+        let taken = |slugs: &[&str]| slugs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(with_collision_suffix("post", &[]), "post");
+        assert_eq!(with_collision_suffix("post", &taken(&["post-2"])), "post");
+        assert_eq!(with_collision_suffix("post", &taken(&["post"])), "post-2");
+        assert_eq!(with_collision_suffix("post", &taken(&["post", "post-2", "post-4"])), "post-3");
+    }
+
+    #[test]
+    fn slug_from_input_accepts_long_well_formed_slugs() {
+        let slug = format!("{}-end", "word-".repeat(30).trim_end_matches('-'));
+        assert!(slug.len() > MAX_GENERATED_SLUG_LENGTH);
+        assert_eq!(slug_from_input("Name", &slug).unwrap(), slug);
+    }
+
+    #[test]
+    fn slug_from_input_derives_blank_slugs_and_rejects_malformed_ones() {
+        assert_eq!(slug_from_input("Release Notes", "  ").unwrap(), "release-notes");
+        for slug in ["Upper", "two--hyphens", "-leading", "trailing-", "sp ace", "ünï"] {
+            assert!(slug_from_input("Name", slug).is_err(), "{slug} should be rejected");
+        }
+        assert!(slug_from_input("!!", "").is_err());
+    }
+}
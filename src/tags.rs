@@ -0,0 +1,424 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::{Post, Tag, TagInput},
+    slugs::{slug_from_input, slugify},
+};
+#[cfg(feature = "ssr")]
+use sqlx::PgConnection;
+
+#[cfg(feature = "ssr")]
+pub const MAX_TAGS_PER_POST: usize = 10;
+#[cfg(feature = "ssr")]
+const MAX_TAG_NAME_LENGTH: usize = 50;
+
+/// Trim names, drop blanks and names that map to the same slug, and check limits
+#[cfg(feature = "ssr")]
+fn normalize_names(names: &[String]) -> AppResult<Vec<(String, String)>> {
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if name.chars().count() > MAX_TAG_NAME_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Tag names must be at most {} characters",
+                MAX_TAG_NAME_LENGTH
+            )));
+        }
+        let slug = slugify(name);
+        if slug.is_empty() {
+            return Err(AppError::Validation(format!("Tag '{}' needs at least one letter or digit", name)));
+        }
+        if !tags.iter().any(|(_, s)| *s == slug) {
+            tags.push((name.to_string(), slug));
+        }
+    }
+
+    if tags.len() > MAX_TAGS_PER_POST {
+        return Err(AppError::Validation(format!("A post can have at most {} tags", MAX_TAGS_PER_POST)));
+    }
+    Ok(tags)
+}
+
+/// Replace a post's tags with `names`, creating tags that don't exist yet.
+/// Recounts every tag added or removed.
+#[cfg(feature = "ssr")]
+pub async fn set_post_tags(conn: &mut PgConnection, post_id: i64, names: &[String]) -> AppResult<()> {
+    let tags = normalize_names(names)?;
+
+    let mut tag_ids = Vec::with_capacity(tags.len());
+    for (name, slug) in &tags {
+        // Names of renamed or merged tags lead to the tag they became
+        let lookup = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT t.id FROM tags t
+            LEFT JOIN tag_slug_history h ON h.tag_id = t.id AND h.old_slug = $2
+            WHERE t.slug = $2 OR LOWER(t.name) = LOWER($1) OR h.old_slug IS NOT NULL
+            ORDER BY t.slug = $2 DESC, h.old_slug IS NULL DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(name)
+        .bind(slug);
+
+        let tag_id = match lookup.fetch_optional(&mut *conn).await.map_err(AppError::Database)? {
+            Some(tag_id) => tag_id,
+            None => {
+                // Another writer may create the same tag concurrently; either way it exists afterwards
+                sqlx::query("INSERT INTO tags (name, slug) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                    .bind(name)
+                    .bind(slug)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(AppError::Database)?;
+
+                sqlx::query_scalar::<_, i64>("SELECT id FROM tags WHERE slug = $2 OR LOWER(name) = LOWER($1) LIMIT 1")
+                    .bind(name)
+                    .bind(slug)
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(AppError::Database)?
+            }
+        };
+        tag_ids.push(tag_id);
+    }
+
+    let removed = sqlx::query_scalar::<_, i64>(
+        "DELETE FROM post_tags WHERE post_id = $1 AND NOT (tag_id = ANY($2)) RETURNING tag_id",
+    )
+    .bind(post_id)
+    .bind(&tag_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    sqlx::query(
+        "INSERT INTO post_tags (post_id, tag_id) SELECT $1, UNNEST($2::bigint[]) ON CONFLICT DO NOTHING",
+    )
+    .bind(post_id)
+    .bind(&tag_ids)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    tag_ids.extend(removed);
+    refresh_usage_counts(conn, &tag_ids).await
+}
+
+/// Recompute `usage_count` for the given tags from the posts that are live now
+#[cfg(feature = "ssr")]
+pub async fn refresh_usage_counts(conn: &mut PgConnection, tag_ids: &[i64]) -> AppResult<()> {
+    if tag_ids.is_empty() {
+        return Ok(());
+    }
+
+    // Lock first so the count below runs on a snapshot that includes any
+    // concurrent writer that recounted the same tags before us. NO KEY keeps
+    // inserts into post_tags, whose foreign key takes a KEY SHARE lock, unblocked.
+    sqlx::query("SELECT id FROM tags WHERE id = ANY($1) ORDER BY id FOR NO KEY UPDATE")
+        .bind(tag_ids)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    sqlx::query(
+        r#"
+        UPDATE tags t SET usage_count = (
+            SELECT COUNT(*) FROM post_tags pt
+            JOIN posts p ON p.id = pt.post_id
+            WHERE pt.tag_id = t.id
+              AND p.published_at <= NOW()
+              AND (p.unpublish_at IS NULL OR p.unpublish_at > NOW())
+        )
+        WHERE t.id = ANY($1)
+        "#,
+    )
+    .bind(tag_ids)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Recount the tags of posts whose publication state changed
+#[cfg(feature = "ssr")]
+pub async fn refresh_for_posts(conn: &mut PgConnection, post_ids: &[i64]) -> AppResult<()> {
+    let tag_ids = sqlx::query_scalar::<_, i64>("SELECT DISTINCT tag_id FROM post_tags WHERE post_id = ANY($1)")
+        .bind(post_ids)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    refresh_usage_counts(conn, &tag_ids).await
+}
+
+#[cfg(feature = "ssr")]
+fn name_or_slug_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            AppError::Validation("A tag with that name or slug already exists".to_string())
+        }
+        _ => AppError::Database(e),
+    }
+}
+
+/// Point `old_slug` at `tag_id` so links to it redirect, unless it is live again
+#[cfg(feature = "ssr")]
+async fn record_slug_change(conn: &mut PgConnection, old_slug: &str, new_slug: &str, tag_id: i64) -> AppResult<()> {
+    // A live slug always wins over a redirect
+    sqlx::query("DELETE FROM tag_slug_history WHERE old_slug = $1")
+        .bind(new_slug)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO tag_slug_history (old_slug, tag_id) VALUES ($1, $2)
+        ON CONFLICT (old_slug) DO UPDATE SET tag_id = EXCLUDED.tag_id, created_at = NOW()
+        "#,
+    )
+    .bind(old_slug)
+    .bind(tag_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Tag administration, lookup and keeping counters honest
+#[cfg(feature = "ssr")]
+pub struct TagService;
+
+#[cfg(feature = "ssr")]
+impl TagService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn for_post(&self, db: &Db, post_id: i64) -> AppResult<Vec<Tag>> {
+        sqlx::query_as::<_, Tag>(
+            r#"
+            SELECT t.* FROM tags t
+            JOIN post_tags pt ON pt.tag_id = t.id
+            WHERE pt.post_id = $1
+            ORDER BY t.name
+            "#,
+        )
+        .bind(post_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    /// Every tag, for the admin screen
+    pub async fn list_all(&self, db: &Db) -> AppResult<Vec<Tag>> {
+        sqlx::query_as::<_, Tag>("SELECT * FROM tags ORDER BY name")
+            .fetch_all(&**db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    pub async fn create(&self, db: &Db, input: &TagInput) -> AppResult<Tag> {
+        let slug = slug_from_input(&input.name, &input.slug)?;
+
+        let tag = sqlx::query_as::<_, Tag>(
+            "INSERT INTO tags (name, slug, description) VALUES ($1, $2, NULLIF($3, '')) RETURNING *",
+        )
+        .bind(input.name.trim())
+        .bind(&slug)
+        .bind(input.description.trim())
+        .fetch_one(&**db)
+        .await
+        .map_err(name_or_slug_conflict)?;
+
+        tracing::info!(tag_id = tag.id, "Tag created");
+        Ok(tag)
+    }
+
+    /// Rename a tag; a changed slug is kept in the slug history so old links redirect
+    pub async fn update(&self, db: &Db, tag_id: i64, input: &TagInput) -> AppResult<Tag> {
+        let slug = slug_from_input(&input.name, &input.slug)?;
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let old_slug = sqlx::query_scalar::<_, String>("SELECT slug FROM tags WHERE id = $1 FOR UPDATE")
+            .bind(tag_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::Database)?
+            .ok_or_else(|| AppError::NotFound("Tag not found".to_string()))?;
+
+        let tag = sqlx::query_as::<_, Tag>(
+            r#"
+            UPDATE tags SET name = $2, slug = $3, description = NULLIF($4, ''), updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(tag_id)
+        .bind(input.name.trim())
+        .bind(&slug)
+        .bind(input.description.trim())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(name_or_slug_conflict)?;
+
+        if old_slug != slug {
+            record_slug_change(&mut tx, &old_slug, &slug, tag_id).await?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(tag_id = tag_id, "Tag updated");
+        Ok(tag)
+    }
+
+    /// Delete a tag and remove it from every post
+    pub async fn delete(&self, db: &Db, tag_id: i64) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM tags WHERE id = $1")
+            .bind(tag_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Tag not found".to_string()));
+        }
+
+        tracing::info!(tag_id = tag_id, "Tag deleted");
+        Ok(())
+    }
+
+    /// Fold `source_id` into `target_id`: its posts get the target tag, and
+    /// its slug and former slugs redirect to the target
+    pub async fn merge(&self, db: &Db, source_id: i64, target_id: i64) -> AppResult<Tag> {
+        if source_id == target_id {
+            return Err(AppError::Validation("Choose two different tags to merge".to_string()));
+        }
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        let slugs = sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, slug FROM tags WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+        )
+        .bind(vec![source_id, target_id])
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        let slug_of = |id: i64| slugs.iter().find(|(tag_id, _)| *tag_id == id).map(|(_, slug)| slug.clone());
+        let (Some(source_slug), Some(target_slug)) = (slug_of(source_id), slug_of(target_id)) else {
+            return Err(AppError::NotFound("Tag not found".to_string()));
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO post_tags (post_id, tag_id)
+            SELECT post_id, $2 FROM post_tags WHERE tag_id = $1
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        sqlx::query("UPDATE tag_slug_history SET tag_id = $2 WHERE tag_id = $1")
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        // Removes the source's own post_tags rows too
+        sqlx::query("DELETE FROM tags WHERE id = $1")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        record_slug_change(&mut tx, &source_slug, &target_slug, target_id).await?;
+        refresh_usage_counts(&mut tx, &[target_id]).await?;
+
+        let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = $1")
+            .bind(target_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!(source_id = source_id, target_id = target_id, "Tags merged");
+        Ok(tag)
+    }
+
+    /// A tag by its current or a former slug
+    pub async fn resolve(&self, db: &Db, slug: &str) -> AppResult<Tag> {
+        sqlx::query_as::<_, Tag>(
+            r#"
+            SELECT t.* FROM tags t
+            LEFT JOIN tag_slug_history h ON h.tag_id = t.id AND h.old_slug = $1
+            WHERE t.slug = $1 OR h.old_slug IS NOT NULL
+            ORDER BY t.slug = $1 DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(slug)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Tag '{}' not found", slug)))
+    }
+
+    /// Live posts with a tag, newest first
+    pub async fn posts(&self, db: &Db, tag_id: i64) -> AppResult<Vec<Post>> {
+        sqlx::query_as::<_, Post>(
+            r#"
+            SELECT p.* FROM posts p
+            JOIN post_tags pt ON pt.post_id = p.id
+            WHERE pt.tag_id = $1
+              AND p.published_at <= NOW()
+              AND (p.unpublish_at IS NULL OR p.unpublish_at > NOW())
+            ORDER BY p.published_at DESC
+            LIMIT 50
+            "#,
+        )
+        .bind(tag_id)
+        .fetch_all(&**db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    /// Recompute every tag's `usage_count`; returns the number of tags whose count was wrong
+    pub async fn recount_all(&self, db: &Db) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE tags t SET usage_count = c.count
+            FROM (
+                SELECT tg.id, COUNT(p.id)::int AS count
+                FROM tags tg
+                LEFT JOIN post_tags pt ON pt.tag_id = tg.id
+                LEFT JOIN posts p ON p.id = pt.post_id
+                    AND p.published_at <= NOW()
+                    AND (p.unpublish_at IS NULL OR p.unpublish_at > NOW())
+                GROUP BY tg.id
+            ) c
+            WHERE c.id = t.id AND t.usage_count <> c.count
+            "#,
+        )
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        tracing::info!(fixed = result.rows_affected(), "Tag usage counts recomputed");
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for TagService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
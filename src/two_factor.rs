@@ -0,0 +1,356 @@
+#[cfg(feature = "ssr")]
+use crate::{
+    db::Db,
+    error::{AppError, AppResult},
+    models::{TotpEnrollment, TwoFactorStatus, User, UserRole},
+    security::{generate_secure_token, hash_token},
+    settings::{get_bool_setting, REQUIRE_TWO_FACTOR_FOR_PRIVILEGED},
+};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "ssr")]
+use rand::Rng;
+#[cfg(feature = "ssr")]
+use totp_rs::{Algorithm, Secret, TOTP};
+
+#[cfg(feature = "ssr")]
+const TOTP_DIGITS: usize = 6;
+#[cfg(feature = "ssr")]
+const TOTP_STEP_SECS: u64 = 30;
+#[cfg(feature = "ssr")]
+const RECOVERY_CODE_COUNT: usize = 10;
+#[cfg(feature = "ssr")]
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+#[cfg(feature = "ssr")]
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+/// RFC 6238 TOTP enrollment, verification and recovery codes
+#[cfg(feature = "ssr")]
+pub struct TwoFactorService {
+    issuer: String,
+}
+
+#[cfg(feature = "ssr")]
+impl TwoFactorService {
+    pub fn new() -> Self {
+        Self {
+            issuer: std::env::var("TOTP_ISSUER").unwrap_or_else(|_| "Taleji".to_string()),
+        }
+    }
+
+    fn totp(&self, secret: &str, account_name: &str) -> AppResult<TOTP> {
+        let secret = Secret::Encoded(secret.to_string()).to_bytes().map_err(|e| {
+            tracing::error!("Stored TOTP secret is not valid base32: {:?}", e);
+            AppError::Internal("Two-factor configuration is invalid".to_string())
+        })?;
+
+        TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            0,
+            TOTP_STEP_SECS,
+            secret,
+            Some(self.issuer.clone()),
+            account_name.to_string(),
+        )
+        .map_err(|e| {
+            tracing::error!("Failed to build TOTP: {}", e);
+            AppError::Internal("Two-factor configuration is invalid".to_string())
+        })
+    }
+
+    /// Find the time step `code` belongs to, allowing one step of clock drift
+    fn matching_step(&self, totp: &TOTP, code: &str) -> Option<i64> {
+        let now = Utc::now().timestamp() as u64;
+        [0i64, -1, 1].into_iter().find_map(|offset| {
+            let time = now.checked_add_signed(offset * TOTP_STEP_SECS as i64)?;
+            (totp.generate(time) == code).then_some((time / TOTP_STEP_SECS) as i64)
+        })
+    }
+
+    /// Whether the role must have two-factor enabled under the site policy
+    pub async fn is_required_for(&self, db: &Db, role: &UserRole) -> AppResult<bool> {
+        if !matches!(role, UserRole::Author | UserRole::Admin) {
+            return Ok(false);
+        }
+        get_bool_setting(db, REQUIRE_TWO_FACTOR_FOR_PRIVILEGED, false).await
+    }
+
+    /// Reject privileged accounts without two-factor when the policy demands it
+    pub async fn ensure_policy_compliance(&self, db: &Db, user: &User) -> AppResult<()> {
+        if self.is_required_for(db, &user.role).await? && !self.is_enabled(db, user.id).await? {
+            return Err(AppError::Forbidden(
+                "Two-factor authentication is required for your role. Set it up in your account settings to continue."
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn is_enabled(&self, db: &Db, user_id: i64) -> AppResult<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT totp_enabled FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&**db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    pub async fn status(&self, db: &Db, user: &User) -> AppResult<TwoFactorStatus> {
+        let recovery_codes_remaining = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user.id)
+        .fetch_one(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(TwoFactorStatus {
+            enabled: self.is_enabled(db, user.id).await?,
+            recovery_codes_remaining,
+            required_by_policy: self.is_required_for(db, &user.role).await?,
+        })
+    }
+
+    /// Generate a new secret; it takes effect once confirmed with a valid code
+    pub async fn begin_enrollment(&self, db: &Db, user: &User) -> AppResult<TotpEnrollment> {
+        if self.is_enabled(db, user.id).await? {
+            return Err(AppError::Validation("Two-factor authentication is already enabled".to_string()));
+        }
+
+        let mut secret_bytes = [0u8; 20];
+        rand::thread_rng().fill(&mut secret_bytes);
+        let secret = Secret::Raw(secret_bytes.to_vec()).to_encoded().to_string();
+
+        sqlx::query("UPDATE users SET totp_secret = $1, totp_last_step = NULL WHERE id = $2")
+            .bind(&secret)
+            .bind(user.id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let otpauth_uri = self.totp(&secret, &user.username)?.get_url();
+        let qr_svg = qrcode::QrCode::new(otpauth_uri.as_bytes())
+            .map_err(|e| {
+                tracing::error!("Failed to render TOTP QR code: {}", e);
+                AppError::Internal("QR code generation failed".to_string())
+            })?
+            .render::<qrcode::render::svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+
+        Ok(TotpEnrollment {
+            secret,
+            otpauth_uri,
+            qr_svg,
+        })
+    }
+
+    /// Activate two-factor after checking a code from the new secret; returns recovery codes
+    pub async fn confirm_enrollment(&self, db: &Db, user: &User, code: &str) -> AppResult<Vec<String>> {
+        let secret = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT totp_secret FROM users WHERE id = $1 AND totp_enabled = false",
+        )
+        .bind(user.id)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .flatten()
+        .ok_or_else(|| AppError::Validation("Start two-factor setup first".to_string()))?;
+
+        let step = self
+            .matching_step(&self.totp(&secret, &user.username)?, code.trim())
+            .ok_or_else(|| AppError::Validation("Invalid verification code".to_string()))?;
+
+        sqlx::query("UPDATE users SET totp_enabled = true, totp_last_step = $1, updated_at = NOW() WHERE id = $2")
+            .bind(step)
+            .bind(user.id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        tracing::info!("Two-factor enabled for user {}", user.id);
+        self.regenerate_recovery_codes(db, user.id).await
+    }
+
+    /// Turn two-factor off and discard the secret and recovery codes
+    pub async fn disable(&self, db: &Db, user_id: i64) -> AppResult<()> {
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_enabled = false, totp_secret = NULL, totp_last_step = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        tx.commit().await.map_err(AppError::Database)?;
+
+        tracing::info!("Two-factor disabled for user {}", user_id);
+        Ok(())
+    }
+
+    /// Replace all recovery codes and return the new ones in plain text
+    pub async fn regenerate_recovery_codes(&self, db: &Db, user_id: i64) -> AppResult<Vec<String>> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let mut rng = rand::thread_rng();
+                format!("{:05}-{:05}", rng.gen_range(0..100_000), rng.gen_range(0..100_000))
+            })
+            .collect();
+
+        let mut tx = db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        for code in &codes {
+            sqlx::query("INSERT INTO recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+                .bind(user_id)
+                .bind(hash_token(code))
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(codes)
+    }
+
+    /// Check a TOTP or recovery code for an enrolled user. Each TOTP step and
+    /// each recovery code is accepted at most once.
+    pub async fn verify_code(&self, db: &Db, user: &User, code: &str) -> AppResult<bool> {
+        let code = code.trim();
+
+        let state = sqlx::query_as::<_, (Option<String>, Option<i64>)>(
+            "SELECT totp_secret, totp_last_step FROM users WHERE id = $1 AND totp_enabled = true",
+        )
+        .bind(user.id)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let Some((totp_secret, totp_last_step)) = state else {
+            return Ok(false);
+        };
+
+        if let Some(secret) = totp_secret {
+            if let Some(step) = self.matching_step(&self.totp(&secret, &user.username)?, code) {
+                if totp_last_step.is_some_and(|last| step <= last) {
+                    tracing::warn!("Replayed TOTP code for user {}", user.id);
+                    return Ok(false);
+                }
+
+                // Conditional update so concurrent logins can't both use one step
+                let updated = sqlx::query(
+                    r#"
+                    UPDATE users SET totp_last_step = $1
+                    WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)
+                    "#,
+                )
+                .bind(step)
+                .bind(user.id)
+                .execute(&**db)
+                .await
+                .map_err(AppError::Database)?;
+
+                return Ok(updated.rows_affected() == 1);
+            }
+        }
+
+        let used = sqlx::query(
+            r#"
+            UPDATE recovery_codes SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user.id)
+        .bind(hash_token(code))
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        if used.rows_affected() == 1 {
+            tracing::info!("Recovery code used by user {}", user.id);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Record a login that passed the password check; returns the challenge token
+    pub async fn create_challenge(&self, db: &Db, user_id: i64, remember_me: bool) -> AppResult<(String, DateTime<Utc>)> {
+        let token = generate_secure_token(32);
+        let expires_at = Utc::now() + Duration::minutes(CHALLENGE_TTL_MINUTES);
+
+        sqlx::query("DELETE FROM two_factor_challenges WHERE user_id = $1 OR expires_at < NOW()")
+            .bind(user_id)
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO two_factor_challenges (id, user_id, remember_me, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(hash_token(&token))
+        .bind(user_id)
+        .bind(remember_me)
+        .bind(expires_at)
+        .execute(&**db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok((token, expires_at))
+    }
+
+    /// Count an attempt against a challenge; returns `(user_id, remember_me)`
+    /// while the challenge is still usable
+    pub async fn load_challenge(&self, db: &Db, token: &str) -> AppResult<(i64, bool)> {
+        sqlx::query_as::<_, (i64, bool)>(
+            r#"
+            UPDATE two_factor_challenges SET attempts = attempts + 1
+            WHERE id = $1 AND expires_at > NOW() AND attempts < $2
+            RETURNING user_id, remember_me
+            "#,
+        )
+        .bind(hash_token(token))
+        .bind(MAX_CHALLENGE_ATTEMPTS)
+        .fetch_optional(&**db)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::Validation("Your login attempt has expired, please sign in again".to_string()))
+    }
+
+    /// Delete a challenge once it has been completed
+    pub async fn complete_challenge(&self, db: &Db, token: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM two_factor_challenges WHERE id = $1")
+            .bind(hash_token(token))
+            .execute(&**db)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl Default for TwoFactorService {
+    fn default() -> Self {
+        Self::new()
+    }
+}